@@ -0,0 +1,410 @@
+//! Typed view of [`AdvisorAdviceBackend::advice_details`].
+//!
+//! On the wire the details are a plain string whose meaning depends on the
+//! advice type:
+//!
+//! | advice type      | details                                        |
+//! |------------------|------------------------------------------------|
+//! | `Policy`         | policy name                                    |
+//! | `Threat`         | threat metric name                             |
+//! | `NetworkPort`    | port number, e.g. `22`                         |
+//! | `NetworkSession` | `<protocol>/<ip>:<port>`, e.g. `TCP/[::1]:53`  |
+//! | `PwnedBreach`    | breach name, as in `BreachDetailBackend`       |
+//! | `Configure`      | free-form hint                                 |
+//!
+//! [`AdviceDetailsBackend`] carries the parsed target instead, and may carry
+//! more than the wire says: the transport of a port, and the domain, process
+//! and full payload of a session. Those live in the typed value only -- the
+//! wire form does not change, so a value parsed from the wire has none of
+//! them. Parsing accepts every string, and details that are not in the
+//! canonical form for their type (`"022"`, `"ssh"` for a port) land in
+//! [`AdviceDetailsBackend::Unparsed`] verbatim, so a parse/format round trip
+//! always gives back the original string. It serializes as the
+//! [`AdvisorAdviceBackend`] it came from.
+
+use crate::advisor_todos_backend::{AdviceTypeBackend, AdvisorAdviceBackend};
+use crate::session_info_backend::SessionInfoBackend;
+use crate::session_typed_backend::SessionProtocolBackend;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::net::{IpAddr, SocketAddr};
+
+/// What identifies the session a `NetworkSession` advice is about: the flow
+/// endpoint plus the domain and the process behind it, so two processes
+/// talking to the same address are two sessions. ASN and criticality are
+/// attributes of the session, not part of its identity.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SessionKeyBackend {
+    pub protocol: SessionProtocolBackend,
+    pub ip: IpAddr,
+    pub port: u16,
+    pub domain: Option<String>,
+    pub l7_process_name: Option<String>,
+    pub l7_process_path: Option<String>,
+}
+
+impl SessionKeyBackend {
+    pub fn from_session(session: &SessionInfoBackend) -> Result<Self> {
+        let typed = session.typed()?;
+        Ok(Self {
+            protocol: typed.protocol,
+            ip: typed.ip,
+            port: typed.port,
+            domain: typed.domain,
+            l7_process_name: typed.l7_process_name,
+            l7_process_path: typed.l7_process_path,
+        })
+    }
+
+    /// Key of a wire endpoint: no domain, no process. Strict: only the
+    /// canonical form produced by [`SessionKeyBackend::endpoint`] is
+    /// accepted.
+    pub fn parse_endpoint(value: &str) -> Result<Self> {
+        let (protocol, address) = value
+            .split_once('/')
+            .ok_or_else(|| anyhow!("session endpoint without protocol: {value:?}"))?;
+        let address = address
+            .parse::<SocketAddr>()
+            .map_err(|e| anyhow!("invalid session endpoint address {address:?}: {e}"))?;
+        let key = Self {
+            protocol: SessionProtocolBackend::parse(protocol),
+            ip: address.ip(),
+            port: address.port(),
+            domain: None,
+            l7_process_name: None,
+            l7_process_path: None,
+        };
+        if key.endpoint() != value {
+            return Err(anyhow!("non-canonical session endpoint: {value:?}"));
+        }
+        Ok(key)
+    }
+
+    /// Wire form: `<protocol>/<ip>:<port>`, e.g. `TCP/[::1]:53`.
+    pub fn endpoint(&self) -> String {
+        format!("{}/{}", self.protocol, SocketAddr::new(self.ip, self.port))
+    }
+
+    pub fn matches(&self, session: &SessionInfoBackend) -> bool {
+        Self::from_session(session).is_ok_and(|key| key == *self)
+    }
+}
+
+/// Human-readable, for reasoning and logs: `TCP/10.0.0.1:443 example.com (curl)`.
+impl Display for SessionKeyBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.endpoint())?;
+        if let Some(domain) = &self.domain {
+            write!(f, " {domain}")?;
+        }
+        if let Some(process) = self
+            .l7_process_name
+            .as_ref()
+            .or(self.l7_process_path.as_ref())
+        {
+            write!(f, " ({process})")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(into = "AdvisorAdviceBackend", from = "AdvisorAdviceBackend")]
+pub enum AdviceDetailsBackend {
+    Policy {
+        policy_name: String,
+    },
+    Threat {
+        metric_name: String,
+    },
+    NetworkPort {
+        port: u16,
+        /// Lowercase, as in `PortInfoBackend::protocol`. Typed only: `None`
+        /// when parsed from the wire.
+        protocol: Option<String>,
+    },
+    NetworkSession {
+        key: SessionKeyBackend,
+        /// The flagged session. Typed only: `None` when parsed from the
+        /// wire.
+        session: Option<Box<SessionInfoBackend>>,
+    },
+    PwnedBreach {
+        breach_name: String,
+    },
+    Configure {
+        hint: String,
+    },
+    /// Details that are not canonical for their advice type, verbatim.
+    Unparsed {
+        advice_type: AdviceTypeBackend,
+        details: String,
+    },
+}
+
+impl AdviceDetailsBackend {
+    /// Never fails: see [`AdviceDetailsBackend::Unparsed`].
+    pub fn parse(advice_type: &AdviceTypeBackend, details: &str) -> Self {
+        Self::try_parse(advice_type, details).unwrap_or_else(|_| Self::Unparsed {
+            advice_type: advice_type.clone(),
+            details: details.to_string(),
+        })
+    }
+
+    /// Strict variant of [`AdviceDetailsBackend::parse`].
+    pub fn try_parse(advice_type: &AdviceTypeBackend, details: &str) -> Result<Self> {
+        Ok(match advice_type {
+            AdviceTypeBackend::Policy => Self::Policy {
+                policy_name: details.to_string(),
+            },
+            AdviceTypeBackend::Threat => Self::Threat {
+                metric_name: details.to_string(),
+            },
+            AdviceTypeBackend::NetworkPort => {
+                let port = details
+                    .parse::<u16>()
+                    .map_err(|e| anyhow!("invalid port {details:?}: {e}"))?;
+                if port.to_string() != details {
+                    return Err(anyhow!("non-canonical port: {details:?}"));
+                }
+                Self::NetworkPort {
+                    port,
+                    protocol: None,
+                }
+            }
+            AdviceTypeBackend::NetworkSession => Self::NetworkSession {
+                key: SessionKeyBackend::parse_endpoint(details)?,
+                session: None,
+            },
+            AdviceTypeBackend::PwnedBreach => Self::PwnedBreach {
+                breach_name: details.to_string(),
+            },
+            AdviceTypeBackend::Configure => Self::Configure {
+                hint: details.to_string(),
+            },
+        })
+    }
+
+    /// `NetworkSession` details for a flagged session; fails when its
+    /// address does not parse.
+    pub fn network_session(session: &SessionInfoBackend) -> Result<Self> {
+        Ok(Self::NetworkSession {
+            key: SessionKeyBackend::from_session(session)?,
+            session: Some(Box::new(session.clone())),
+        })
+    }
+
+    pub fn advice_type(&self) -> AdviceTypeBackend {
+        match self {
+            Self::Policy { .. } => AdviceTypeBackend::Policy,
+            Self::Threat { .. } => AdviceTypeBackend::Threat,
+            Self::NetworkPort { .. } => AdviceTypeBackend::NetworkPort,
+            Self::NetworkSession { .. } => AdviceTypeBackend::NetworkSession,
+            Self::PwnedBreach { .. } => AdviceTypeBackend::PwnedBreach,
+            Self::Configure { .. } => AdviceTypeBackend::Configure,
+            Self::Unparsed { advice_type, .. } => advice_type.clone(),
+        }
+    }
+
+    /// Wire string for `advice_details`.
+    pub fn to_wire(&self) -> String {
+        match self {
+            Self::Policy { policy_name } => policy_name.clone(),
+            Self::Threat { metric_name } => metric_name.clone(),
+            Self::NetworkPort { port, .. } => port.to_string(),
+            Self::NetworkSession { key, .. } => key.endpoint(),
+            Self::PwnedBreach { breach_name } => breach_name.clone(),
+            Self::Configure { hint } => hint.clone(),
+            Self::Unparsed { details, .. } => details.clone(),
+        }
+    }
+
+    /// Key of a `NetworkSession` target; `None` for other advice.
+    pub fn session_key(&self) -> Option<&SessionKeyBackend> {
+        match self {
+            Self::NetworkSession { key, .. } => Some(key),
+            _ => None,
+        }
+    }
+}
+
+impl From<&AdvisorAdviceBackend> for AdviceDetailsBackend {
+    fn from(advice: &AdvisorAdviceBackend) -> Self {
+        Self::parse(&advice.advice_type, &advice.advice_details)
+    }
+}
+
+impl From<AdvisorAdviceBackend> for AdviceDetailsBackend {
+    fn from(advice: AdvisorAdviceBackend) -> Self {
+        Self::from(&advice)
+    }
+}
+
+impl From<AdviceDetailsBackend> for AdvisorAdviceBackend {
+    fn from(details: AdviceDetailsBackend) -> Self {
+        Self {
+            advice_type: details.advice_type(),
+            advice_details: details.to_wire(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures as fixtures;
+
+    fn advice(advice_type: AdviceTypeBackend, details: &str) -> AdvisorAdviceBackend {
+        AdvisorAdviceBackend {
+            advice_type,
+            advice_details: details.to_string(),
+        }
+    }
+
+    fn session() -> SessionInfoBackend {
+        SessionInfoBackend {
+            criticality: "blacklist:firehol_level1".to_string(),
+            ..fixtures::session("10.0.0.1")
+        }
+    }
+
+    #[test]
+    fn parses_each_advice_type() {
+        assert_eq!(
+            advice(AdviceTypeBackend::NetworkPort, "22").details(),
+            AdviceDetailsBackend::NetworkPort {
+                port: 22,
+                protocol: None
+            }
+        );
+        assert_eq!(
+            advice(AdviceTypeBackend::NetworkSession, "TCP/[2001:db8::1]:443").details(),
+            AdviceDetailsBackend::NetworkSession {
+                key: SessionKeyBackend {
+                    protocol: SessionProtocolBackend::Tcp,
+                    ip: "2001:db8::1".parse().unwrap(),
+                    port: 443,
+                    domain: None,
+                    l7_process_name: None,
+                    l7_process_path: None,
+                },
+                session: None,
+            }
+        );
+        assert_eq!(
+            advice(AdviceTypeBackend::PwnedBreach, "Adobe").details(),
+            AdviceDetailsBackend::PwnedBreach {
+                breach_name: "Adobe".to_string()
+            }
+        );
+        assert_eq!(
+            advice(AdviceTypeBackend::Threat, "edamame helper disabled").details(),
+            AdviceDetailsBackend::Threat {
+                metric_name: "edamame helper disabled".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn non_canonical_details_are_kept_verbatim() {
+        for (advice_type, details) in [
+            (AdviceTypeBackend::NetworkPort, "022"),
+            (AdviceTypeBackend::NetworkPort, "ssh"),
+            (AdviceTypeBackend::NetworkPort, "70000"),
+            (AdviceTypeBackend::NetworkPort, "22/tcp"),
+            (AdviceTypeBackend::NetworkSession, "10.0.0.1:443"),
+            (AdviceTypeBackend::NetworkSession, "tcp/10.0.0.1:443"),
+            (AdviceTypeBackend::NetworkSession, "TCP/::1:53"),
+            (AdviceTypeBackend::NetworkSession, "{}"),
+        ] {
+            let parsed = advice(advice_type.clone(), details).details();
+            assert!(
+                matches!(parsed, AdviceDetailsBackend::Unparsed { .. }),
+                "{details}"
+            );
+            assert_eq!(parsed.to_wire(), details);
+            assert_eq!(parsed.advice_type(), advice_type);
+        }
+    }
+
+    #[test]
+    fn round_trips_through_the_wire_form() {
+        for (advice_type, details) in [
+            (AdviceTypeBackend::Policy, "baseline"),
+            (AdviceTypeBackend::Configure, ""),
+            (AdviceTypeBackend::NetworkPort, "8080"),
+            (AdviceTypeBackend::NetworkSession, "UDP/192.168.1.1:53"),
+            (AdviceTypeBackend::NetworkSession, "QUIC/[::1]:443"),
+            (AdviceTypeBackend::NetworkSession, "not an endpoint"),
+            (AdviceTypeBackend::PwnedBreach, "LinkedIn"),
+        ] {
+            let original = advice(advice_type, details);
+            let back = AdvisorAdviceBackend::from(original.details());
+            assert_eq!(back, original);
+
+            // Serde goes through the same wire form.
+            let json = serde_json::to_string(&original.details()).unwrap();
+            assert_eq!(json, serde_json::to_string(&original).unwrap());
+            let parsed: AdviceDetailsBackend = serde_json::from_str(&json).unwrap();
+            assert_eq!(parsed, original.details());
+        }
+    }
+
+    #[test]
+    fn typed_extras_stay_off_the_wire() {
+        let port = AdviceDetailsBackend::NetworkPort {
+            port: 161,
+            protocol: Some("udp".to_string()),
+        };
+        assert_eq!(port.to_wire(), "161");
+
+        let details = AdviceDetailsBackend::network_session(&session()).unwrap();
+        assert_eq!(details.to_wire(), "TCP/10.0.0.1:443");
+        let AdviceDetailsBackend::NetworkSession { key, session: kept } = &details else {
+            panic!("not a session: {details:?}");
+        };
+        assert_eq!(key.domain.as_deref(), Some("example.com"));
+        assert_eq!(kept.as_deref(), Some(&session()));
+
+        // Through the wire only the endpoint is left.
+        let back = AdviceDetailsBackend::from(AdvisorAdviceBackend::from(details.clone()));
+        assert_eq!(
+            back.session_key().map(SessionKeyBackend::endpoint),
+            Some(key.endpoint())
+        );
+        assert_ne!(back, details);
+    }
+
+    #[test]
+    fn session_key_identifies_domain_and_process() {
+        let key = SessionKeyBackend::from_session(&session()).unwrap();
+        assert_eq!(key.to_string(), "TCP/10.0.0.1:443 example.com (curl)");
+        assert_eq!(key.endpoint(), "TCP/10.0.0.1:443");
+        assert!(key.matches(&session()));
+
+        // Attributes that are not identity do not matter.
+        let mut other = session();
+        other.criticality = String::new();
+        other.asn_owner = None;
+        other.l7_process_user = None;
+        assert!(key.matches(&other));
+
+        for change in [
+            |s: &mut SessionInfoBackend| s.port = 80,
+            |s: &mut SessionInfoBackend| s.domain = Some("example.org".to_string()),
+            |s: &mut SessionInfoBackend| s.l7_process_name = Some("wget".to_string()),
+            |s: &mut SessionInfoBackend| s.l7_process_path = None,
+        ] {
+            let mut other = session();
+            change(&mut other);
+            assert!(!key.matches(&other));
+        }
+
+        let json = serde_json::to_string(&key).unwrap();
+        assert_eq!(
+            serde_json::from_str::<SessionKeyBackend>(&json).unwrap(),
+            key
+        );
+    }
+}
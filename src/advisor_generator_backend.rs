@@ -0,0 +1,602 @@
+//! Reference advisor todo generator.
+//!
+//! [`AdvisorTodosBuilderBackend`] turns a [`DetailedScoreBackend`] plus whatever else
+//! the caller has (policy results, sessions, LAN devices, breaches) into the
+//! prioritized todo list the app shows, so the Hub and offline tools can
+//! reproduce it. Every todo carries the reasoning that set its priority.
+//!
+//! - Threat: every Active metric. Severity 1-2 is low, 3 medium, 4 high, 5
+//!   critical; a metric a failing policy requires is raised one level.
+//! - Policy: every failing policy, high.
+//! - NetworkPort: every open port with a port risk -- see
+//!   [`crate::lanscan_port_risk_backend`].
+//! - NetworkSession: every flagged session, at its criticality: a blacklist
+//!   hit is high, an anomaly medium, an unrecognised flag low.
+//!   The details carry the [`SessionKeyBackend`] only, not a session
+//!   payload, and sessions on one endpoint are one todo whatever the
+//!   process; sessions whose address does not parse have no key and are
+//!   skipped.
+//! - PwnedBreach: every breach, at its derived criticality -- see
+//!   [`crate::pwned_criticality_backend`]. Breach age is measured from the
+//!   report timestamp; when that does not parse the breach keeps the
+//!   criticality it was sent with.
+//!
+//! Todos with the same advice are merged (one port open on two devices is one
+//! todo) at the highest priority, keeping every reason. The list is ordered
+//! by priority, then advice type, then details, so equal inputs always give
+//! an equal list.
+
+use crate::advice_details_backend::{AdviceDetailsBackend, SessionKeyBackend};
+use crate::advisor_todos_backend::{
+    AdvicePriorityBackend, AdviceTypeBackend, AdvisorAdviceBackend, AdvisorTodoBackend,
+};
+use crate::lanscan_device_info_backend::DeviceInfoBackend;
+use crate::lanscan_port_risk_backend::assess_ports;
+use crate::policy_backend::{PoliciesStatusBackend, ReasonBackend};
+use crate::pwned_backend::{BreachDetailBackend, PwnedCriticalityBackend};
+use crate::score_backend::DetailedScoreBackend;
+use crate::session_info_backend::SessionInfoBackend;
+use crate::session_typed_backend::SessionCriticalityBackend;
+use crate::threat_backend::ThreatStatusBackend;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct GeneratedAdvisorTodoBackend {
+    pub todo: AdvisorTodoBackend,
+    /// Why the todo exists and has its priority, one line per source.
+    pub reasoning: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AdvisorTodosBuilderBackend<'a> {
+    score: &'a DetailedScoreBackend,
+    policies: &'a [PoliciesStatusBackend],
+    sessions: &'a [SessionInfoBackend],
+    devices: &'a [DeviceInfoBackend],
+    breaches: &'a [BreachDetailBackend],
+}
+
+impl<'a> AdvisorTodosBuilderBackend<'a> {
+    pub fn new(score: &'a DetailedScoreBackend) -> Self {
+        Self {
+            score,
+            policies: &[],
+            sessions: &[],
+            devices: &[],
+            breaches: &[],
+        }
+    }
+
+    pub fn with_policies(mut self, policies: &'a [PoliciesStatusBackend]) -> Self {
+        self.policies = policies;
+        self
+    }
+
+    pub fn with_sessions(mut self, sessions: &'a [SessionInfoBackend]) -> Self {
+        self.sessions = sessions;
+        self
+    }
+
+    pub fn with_devices(mut self, devices: &'a [DeviceInfoBackend]) -> Self {
+        self.devices = devices;
+        self
+    }
+
+    pub fn with_breaches(mut self, breaches: &'a [BreachDetailBackend]) -> Self {
+        self.breaches = breaches;
+        self
+    }
+
+    pub fn build(&self) -> Vec<GeneratedAdvisorTodoBackend> {
+        let mut todos = TodoAccumulator::default();
+        self.add_threats(&mut todos);
+        self.add_policies(&mut todos);
+        self.add_ports(&mut todos);
+        self.add_sessions(&mut todos);
+        self.add_breaches(&mut todos);
+        todos.into_sorted()
+    }
+
+    fn add_threats(&self, todos: &mut TodoAccumulator) {
+        let failing = self.policies.iter().filter(|policy| !policy.passed);
+        let mut required: Vec<(&str, &str)> = Vec::new();
+        for policy in failing {
+            for reason in &policy.reason {
+                let failed = match reason {
+                    ReasonBackend::SecurityChecksNotPassedBackend { failed, .. } => failed,
+                    ReasonBackend::TagsNotRespectedBackend {
+                        failed_security_checks,
+                        ..
+                    } => failed_security_checks,
+                    ReasonBackend::MinScoreNotRespectedBackend { .. } => continue,
+                };
+                required.extend(
+                    failed
+                        .iter()
+                        .map(|check| (check.as_str(), policy.name.as_str())),
+                );
+            }
+        }
+        required.sort();
+        required.dedup();
+
+        for metric in &self.score.score.metrics.metrics {
+            if metric.status != ThreatStatusBackend::Active {
+                continue;
+            }
+            let name = &metric.metric.name;
+            let mut priority = threat_priority(metric.metric.severity);
+            let mut reasoning = vec![format!(
+                "active threat, severity {}",
+                metric.metric.severity
+            )];
+            let policies: Vec<&str> = required
+                .iter()
+                .filter(|(check, _)| check == name)
+                .map(|(_, policy)| *policy)
+                .collect();
+            if !policies.is_empty() {
+                priority = raise(priority);
+                reasoning.push(format!(
+                    "required by failing policy {}",
+                    policies.join(", ")
+                ));
+            }
+            todos.add(
+                AdviceDetailsBackend::Threat {
+                    metric_name: name.clone(),
+                },
+                priority,
+                reasoning,
+            );
+        }
+    }
+
+    fn add_policies(&self, todos: &mut TodoAccumulator) {
+        for policy in self.policies.iter().filter(|policy| !policy.passed) {
+            let mut reasoning: Vec<String> = policy.reason.iter().map(describe_reason).collect();
+            if reasoning.is_empty() {
+                reasoning.push("policy failed".to_string());
+            }
+            todos.add(
+                AdviceDetailsBackend::Policy {
+                    policy_name: policy.name.clone(),
+                },
+                AdvicePriorityBackend::High,
+                reasoning,
+            );
+        }
+    }
+
+    fn add_ports(&self, todos: &mut TodoAccumulator) {
+        for device in self.devices {
+            for risk in assess_ports(&device.open_ports) {
+                let Some(priority) = risk.level().priority() else {
+                    continue;
+                };
+                let reasoning = risk
+                    .rationale
+                    .iter()
+                    .map(|reason| {
+                        if device.device_vendor.is_empty() {
+                            reason.message.clone()
+                        } else {
+                            format!("{}: {}", device.device_vendor, reason.message)
+                        }
+                    })
+                    .collect();
+                todos.add(risk.details(), priority, reasoning);
+            }
+        }
+    }
+
+    fn add_sessions(&self, todos: &mut TodoAccumulator) {
+        let mut flagged = Vec::new();
+        for session in self.sessions {
+            let criticality = SessionCriticalityBackend::parse(&session.criticality);
+            let priority = match criticality {
+                SessionCriticalityBackend::Low => AdvicePriorityBackend::Low,
+                SessionCriticalityBackend::Medium => AdvicePriorityBackend::Medium,
+                SessionCriticalityBackend::High => AdvicePriorityBackend::High,
+                SessionCriticalityBackend::Critical => AdvicePriorityBackend::Critical,
+                SessionCriticalityBackend::Anomaly(_) => AdvicePriorityBackend::Medium,
+                SessionCriticalityBackend::Blacklist(_) => AdvicePriorityBackend::High,
+                // Flagged by something this build does not know yet.
+                SessionCriticalityBackend::Unknown(_) => AdvicePriorityBackend::Low,
+                SessionCriticalityBackend::Normal => continue,
+            };
+            let Ok(key) = SessionKeyBackend::from_session(session) else {
+                continue;
+            };
+            flagged.push((priority, key, criticality));
+        }
+        // Reasons of merged sessions come in this order, not input order.
+        flagged.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| (&a.1, &a.2).cmp(&(&b.1, &b.2))));
+        flagged.dedup();
+        for (priority, key, criticality) in flagged {
+            let process = key.l7_process_name.as_deref().unwrap_or("unknown process");
+            let destination = match &key.domain {
+                Some(domain) => domain.clone(),
+                None => key.ip.to_string(),
+            };
+            let reason = format!("{criticality} session from {process} to {destination}");
+            todos.add(
+                AdviceDetailsBackend::NetworkSession { key, session: None },
+                priority,
+                vec![reason],
+            );
+        }
+    }
+
+    fn add_breaches(&self, todos: &mut TodoAccumulator) {
+        let now = DateTime::parse_from_rfc3339(&self.score.timestamp)
+            .ok()
+            .map(|timestamp| timestamp.with_timezone(&Utc));
+        for breach in self.breaches {
+            let (criticality, reason) = match now {
+                Some(now) => {
+                    let verdict = breach.derive_criticality(now);
+                    let reason = format!(
+                        "breach criticality {} (rule {})",
+                        verdict.criticality, verdict.rule
+                    );
+                    (verdict.criticality, reason)
+                }
+                None => (
+                    breach.criticality.clone(),
+                    format!("breach criticality {} (as reported)", breach.criticality),
+                ),
+            };
+            let priority = match criticality {
+                PwnedCriticalityBackend::Unknown | PwnedCriticalityBackend::Low => {
+                    AdvicePriorityBackend::Low
+                }
+                PwnedCriticalityBackend::Medium => AdvicePriorityBackend::Medium,
+                PwnedCriticalityBackend::High => AdvicePriorityBackend::High,
+            };
+            todos.add(
+                AdviceDetailsBackend::PwnedBreach {
+                    breach_name: breach.name.clone(),
+                },
+                priority,
+                vec![reason],
+            );
+        }
+    }
+}
+
+fn threat_priority(severity: i32) -> AdvicePriorityBackend {
+    match severity {
+        i32::MIN..=2 => AdvicePriorityBackend::Low,
+        3 => AdvicePriorityBackend::Medium,
+        4 => AdvicePriorityBackend::High,
+        _ => AdvicePriorityBackend::Critical,
+    }
+}
+
+fn raise(priority: AdvicePriorityBackend) -> AdvicePriorityBackend {
+    match priority {
+        AdvicePriorityBackend::Low => AdvicePriorityBackend::Medium,
+        AdvicePriorityBackend::Medium => AdvicePriorityBackend::High,
+        AdvicePriorityBackend::High | AdvicePriorityBackend::Critical => {
+            AdvicePriorityBackend::Critical
+        }
+    }
+}
+
+fn describe_reason(reason: &ReasonBackend) -> String {
+    match reason {
+        ReasonBackend::MinScoreNotRespectedBackend { required, got } => {
+            format!("score {got} below required {required}")
+        }
+        ReasonBackend::SecurityChecksNotPassedBackend { failed, .. } => {
+            format!("failed security checks: {}", failed.join(", "))
+        }
+        ReasonBackend::TagsNotRespectedBackend { required, got, .. } => {
+            format!("{required} compliance at {got}")
+        }
+    }
+}
+
+#[derive(Default)]
+struct TodoAccumulator {
+    todos: BTreeMap<(AdviceTypeBackend, String), (AdvicePriorityBackend, Vec<String>)>,
+}
+
+impl TodoAccumulator {
+    fn add(
+        &mut self,
+        details: AdviceDetailsBackend,
+        priority: AdvicePriorityBackend,
+        reasoning: Vec<String>,
+    ) {
+        let entry = self
+            .todos
+            .entry((details.advice_type(), details.to_wire()))
+            .or_insert_with(|| (priority.clone(), Vec::new()));
+        entry.0 = entry.0.clone().max(priority);
+        for reason in reasoning {
+            if !entry.1.contains(&reason) {
+                entry.1.push(reason);
+            }
+        }
+    }
+
+    fn into_sorted(self) -> Vec<GeneratedAdvisorTodoBackend> {
+        let mut todos: Vec<GeneratedAdvisorTodoBackend> = self
+            .todos
+            .into_iter()
+            .map(|((advice_type, advice_details), (priority, reasoning))| {
+                GeneratedAdvisorTodoBackend {
+                    todo: AdvisorTodoBackend {
+                        advice: AdvisorAdviceBackend {
+                            advice_type,
+                            advice_details,
+                        },
+                        priority,
+                    },
+                    reasoning,
+                }
+            })
+            .collect();
+        // The map already orders by advice; a stable sort keeps that within
+        // each priority.
+        todos.sort_by(|a, b| b.todo.priority.cmp(&a.todo.priority));
+        todos
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lanscan_port_info_backend::PortInfoBackend;
+    use crate::test_fixtures::{self as fixtures, detailed_score};
+    use crate::threat_backend::ThreatMetricBackend;
+
+    fn metric(name: &str, severity: i32, status: ThreatStatusBackend) -> ThreatMetricBackend {
+        let mut metric = fixtures::metric(name, status);
+        metric.metric.severity = severity;
+        metric
+    }
+
+    fn session(ip: &str, criticality: &str) -> SessionInfoBackend {
+        SessionInfoBackend {
+            domain: Some("evil.example".to_string()),
+            criticality: criticality.to_string(),
+            ..fixtures::session(ip)
+        }
+    }
+
+    fn telnet_device(vendor: &str) -> DeviceInfoBackend {
+        DeviceInfoBackend {
+            mdns_services: vec![],
+            device_vendor: vendor.to_string(),
+            vulnerabilities: vec![],
+            open_ports: vec![
+                PortInfoBackend {
+                    port: 23,
+                    protocol: "tcp".to_string(),
+                    service: "telnet".to_string(),
+                    banner: String::new(),
+                    vulnerabilities: vec![],
+                },
+                PortInfoBackend {
+                    port: 443,
+                    protocol: "tcp".to_string(),
+                    service: "https".to_string(),
+                    banner: String::new(),
+                    vulnerabilities: vec![],
+                },
+            ],
+        }
+    }
+
+    fn breach(name: &str, criticality: PwnedCriticalityBackend) -> BreachDetailBackend {
+        BreachDetailBackend {
+            breachdate: "2013-10-04".to_string(),
+            criticality,
+            ..fixtures::breach(name)
+        }
+    }
+
+    fn keys(todos: &[GeneratedAdvisorTodoBackend]) -> Vec<(AdvicePriorityBackend, String)> {
+        todos
+            .iter()
+            .map(|todo| {
+                let advice = &todo.todo.advice;
+                (
+                    todo.todo.priority.clone(),
+                    format!("{:?}:{}", advice.advice_type, advice.advice_details),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn threats_follow_severity_and_policy_failures() {
+        let score = detailed_score(
+            vec![
+                metric("firewall disabled", 3, ThreatStatusBackend::Active),
+                metric("no disk encryption", 5, ThreatStatusBackend::Active),
+                metric("screen lock", 2, ThreatStatusBackend::Active),
+                metric("remote login", 4, ThreatStatusBackend::Inactive),
+            ],
+            "2024-01-01T00:00:00Z",
+        );
+        let policies = vec![PoliciesStatusBackend {
+            name: "baseline".to_string(),
+            passed: false,
+            reason: vec![ReasonBackend::SecurityChecksNotPassedBackend {
+                required: vec!["firewall disabled".to_string()],
+                passed: vec![],
+                failed: vec!["firewall disabled".to_string()],
+            }],
+            providers: vec![],
+            passed_rules: vec![],
+        }];
+        let todos = AdvisorTodosBuilderBackend::new(&score)
+            .with_policies(&policies)
+            .build();
+        assert_eq!(
+            keys(&todos),
+            vec![
+                (
+                    AdvicePriorityBackend::Critical,
+                    "Threat:no disk encryption".to_string()
+                ),
+                (AdvicePriorityBackend::High, "Policy:baseline".to_string()),
+                (
+                    AdvicePriorityBackend::High,
+                    "Threat:firewall disabled".to_string()
+                ),
+                (AdvicePriorityBackend::Low, "Threat:screen lock".to_string()),
+            ]
+        );
+        let firewall = &todos[2];
+        assert_eq!(
+            firewall.reasoning,
+            vec![
+                "active threat, severity 3".to_string(),
+                "required by failing policy baseline".to_string()
+            ]
+        );
+        assert_eq!(
+            todos[1].reasoning,
+            vec!["failed security checks: firewall disabled".to_string()]
+        );
+    }
+
+    #[test]
+    fn ports_sessions_and_breaches_are_merged_and_ordered() {
+        let score = detailed_score(vec![], "2024-01-01T00:00:00Z");
+        let devices = vec![telnet_device("Acme"), telnet_device("Globex")];
+        let sessions = vec![
+            session("203.0.113.7", "blacklist:firehol_level1"),
+            session("203.0.113.7", "anomaly:abnormal"),
+            session("203.0.113.8", ""),
+            session("203.0.113.9", "anomaly:suspicious"),
+            session("not an ip", "blacklist:firehol_level1"),
+        ];
+        let breaches = vec![breach("Adobe", PwnedCriticalityBackend::High)];
+        let todos = AdvisorTodosBuilderBackend::new(&score)
+            .with_devices(&devices)
+            .with_sessions(&sessions)
+            .with_breaches(&breaches)
+            .build();
+
+        let keys = keys(&todos);
+        assert!(keys.contains(&(
+            AdvicePriorityBackend::High,
+            "NetworkSession:TCP/203.0.113.7:443".to_string()
+        )));
+        assert!(keys.contains(&(
+            AdvicePriorityBackend::Medium,
+            "NetworkSession:TCP/203.0.113.9:443".to_string()
+        )));
+        assert_eq!(
+            keys.iter()
+                .filter(|(_, key)| key.starts_with("NetworkSession"))
+                .count(),
+            2
+        );
+        let telnet = todos
+            .iter()
+            .find(|todo| todo.todo.advice.advice_details == "23")
+            .unwrap();
+        assert!(telnet.todo.priority >= AdvicePriorityBackend::High);
+        assert!(telnet.reasoning.iter().any(|r| r.starts_with("Acme: ")));
+        assert!(telnet.reasoning.iter().any(|r| r.starts_with("Globex: ")));
+        assert!(!keys.iter().any(|(_, key)| key == "NetworkPort:443"));
+
+        let breach = todos
+            .iter()
+            .find(|todo| todo.todo.advice.advice_type == AdviceTypeBackend::PwnedBreach)
+            .unwrap();
+        assert!(breach.reasoning[0].contains("(rule "));
+
+        let mut sorted = todos.clone();
+        sorted.sort_by(|a, b| b.todo.priority.cmp(&a.todo.priority));
+        assert_eq!(sorted, todos);
+    }
+
+    #[test]
+    fn unparsable_timestamp_keeps_reported_breach_criticality() {
+        let score = detailed_score(vec![], "yesterday");
+        let breaches = vec![breach("Adobe", PwnedCriticalityBackend::Medium)];
+        let todos = AdvisorTodosBuilderBackend::new(&score)
+            .with_breaches(&breaches)
+            .build();
+        assert_eq!(todos[0].todo.priority, AdvicePriorityBackend::Medium);
+        assert_eq!(
+            todos[0].reasoning,
+            vec!["breach criticality Medium (as reported)".to_string()]
+        );
+    }
+
+    #[test]
+    fn output_is_deterministic() {
+        let score = detailed_score(
+            vec![metric("firewall disabled", 3, ThreatStatusBackend::Active)],
+            "2024-01-01T00:00:00Z",
+        );
+        let devices = vec![telnet_device("Acme")];
+        let mut reversed = devices.clone();
+        reversed[0].open_ports.reverse();
+        let a = AdvisorTodosBuilderBackend::new(&score)
+            .with_devices(&devices)
+            .build();
+        let b = AdvisorTodosBuilderBackend::new(&score)
+            .with_devices(&reversed)
+            .build();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn sessions_do_not_depend_on_input_order() {
+        let score = detailed_score(vec![], "2024-01-01T00:00:00Z");
+        let mut wget = session("203.0.113.7", "anomaly:abnormal");
+        wget.l7_process_name = Some("wget".to_string());
+        let mut sessions = vec![
+            session("203.0.113.7", "anomaly:suspicious"),
+            session("203.0.113.7", "blacklist:firehol_level1"),
+            wget,
+            session("2001:db8::1", "anomaly:abnormal"),
+            session("2001:DB8::0001", "blacklist:firehol_level1"),
+        ];
+        let expected = AdvisorTodosBuilderBackend::new(&score)
+            .with_sessions(&sessions)
+            .build();
+        assert_eq!(
+            keys(&expected),
+            vec![
+                (
+                    AdvicePriorityBackend::High,
+                    "NetworkSession:TCP/203.0.113.7:443".to_string()
+                ),
+                (
+                    AdvicePriorityBackend::High,
+                    "NetworkSession:TCP/[2001:db8::1]:443".to_string()
+                ),
+            ]
+        );
+        assert_eq!(
+            expected[0].reasoning,
+            vec![
+                "blacklist:firehol_level1 session from curl to evil.example".to_string(),
+                "anomaly:suspicious session from curl to evil.example".to_string(),
+                "anomaly:abnormal session from wget to evil.example".to_string(),
+            ]
+        );
+        for _ in 0..sessions.len() {
+            sessions.rotate_left(1);
+            for order in [sessions.clone(), sessions.iter().rev().cloned().collect()] {
+                let todos = AdvisorTodosBuilderBackend::new(&score)
+                    .with_sessions(&order)
+                    .build();
+                assert_eq!(todos, expected);
+            }
+        }
+    }
+}
@@ -0,0 +1,543 @@
+//! Lifecycle of advisor todos across successive snapshots.
+//!
+//! An [`AdvisorTodoBackend`] has no identity: each [`AdvisorTodosBackend`] is a
+//! fresh list. [`AdvisorTodoTrackerBackend`] keys todos by
+//! [`advisor_todo_id`] (the advice target, not the priority, so a todo whose
+//! priority moves is still the same todo) and carries them from one snapshot
+//! to the next:
+//!
+//! - a todo seen for the first time is Open;
+//! - a tracked todo missing from a snapshot is Done;
+//! - a Done todo that shows up again is Regressed, and stays so until it is
+//!   resolved again;
+//! - a Snoozed todo stays Snoozed while present until its expiry, then
+//!   returns to Open (or Regressed if it had been resolved before).
+//!
+//! Open time counts every period a todo was not Done, snoozed periods
+//! included: snoozing defers a todo, it does not fix it. The tracker
+//! serializes as-is so it can be persisted per device, and
+//! [`fleet_closed_between`] aggregates "closed this week" across devices.
+
+use crate::advice_details_backend::AdviceDetailsBackend;
+use crate::advisor_todos_backend::{
+    AdvicePriorityBackend, AdvisorAdviceBackend, AdvisorTodoBackend, AdvisorTodosBackend,
+};
+use crate::stable_hash_backend::{StableHash, StableHasher};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Identity of a todo: a digest of its typed advice
+/// ([`AdviceDetailsBackend`]). A port is its number and transport, a session
+/// its [`crate::advice_details_backend::SessionKeyBackend`], and any other
+/// advice its type and details.
+pub fn advisor_todo_id(advice: &AdvisorAdviceBackend) -> String {
+    let mut hasher = StableHasher::new();
+    hasher.write_str("advisor_todo_id");
+    match advice.details() {
+        AdviceDetailsBackend::NetworkPort { port, protocol } => {
+            hasher.write_str("NetworkPort");
+            hasher.write_field("port", &port);
+            hasher.write_field("protocol", &protocol);
+        }
+        AdviceDetailsBackend::NetworkSession { key, .. } => {
+            hasher.write_str("NetworkSession");
+            hasher.write_field("protocol", key.protocol.as_str());
+            hasher.write_field("ip", &key.ip.to_string());
+            hasher.write_field("port", &key.port);
+            hasher.write_field("domain", &key.domain);
+            hasher.write_field("l7_process_name", &key.l7_process_name);
+            hasher.write_field("l7_process_path", &key.l7_process_path);
+        }
+        _ => advice.stable_hash_into(&mut hasher),
+    }
+    hasher.finish()
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AdvisorTodoStateBackend {
+    Open,
+    Snoozed,
+    Done,
+    /// Open again after having been Done.
+    Regressed,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TrackedAdvisorTodoBackend {
+    pub id: String,
+    pub advice: AdvisorAdviceBackend,
+    /// As of the last snapshot the todo was in.
+    pub priority: AdvicePriorityBackend,
+    pub state: AdvisorTodoStateBackend,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    /// Start of the current open period; unchanged while Done.
+    pub opened_at: DateTime<Utc>,
+    /// Set while Done.
+    pub resolved_at: Option<DateTime<Utc>>,
+    /// Set while Snoozed.
+    pub snoozed_until: Option<DateTime<Utc>>,
+    /// Times the todo came back after being Done.
+    pub regressions: u32,
+    /// Open time of the periods that already ended, in seconds.
+    pub closed_open_secs: i64,
+}
+
+impl TrackedAdvisorTodoBackend {
+    pub fn is_open(&self) -> bool {
+        self.state != AdvisorTodoStateBackend::Done
+    }
+
+    /// Total time spent not Done, up to `now`.
+    pub fn open_time(&self, now: DateTime<Utc>) -> Duration {
+        let current = if self.is_open() {
+            (now - self.opened_at).max(Duration::zero())
+        } else {
+            Duration::zero()
+        };
+        Duration::seconds(self.closed_open_secs) + current
+    }
+
+    fn reopened_state(&self) -> AdvisorTodoStateBackend {
+        if self.regressions > 0 {
+            AdvisorTodoStateBackend::Regressed
+        } else {
+            AdvisorTodoStateBackend::Open
+        }
+    }
+}
+
+/// A state change reported by [`AdvisorTodoTrackerBackend::observe`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AdvisorTodoTransitionBackend {
+    pub id: String,
+    /// `None` for a todo seen for the first time.
+    pub from: Option<AdvisorTodoStateBackend>,
+    pub to: AdvisorTodoStateBackend,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct AdvisorTodoTrackerBackend {
+    /// Sorted by id.
+    pub todos: Vec<TrackedAdvisorTodoBackend>,
+    /// Time of the last observed snapshot.
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl AdvisorTodoTrackerBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn todo(&self, id: &str) -> Option<&TrackedAdvisorTodoBackend> {
+        self.position(id).ok().map(|index| &self.todos[index])
+    }
+
+    /// Applies the snapshot taken at `at`. Fails when `at` precedes the
+    /// previous snapshot: a late snapshot would resolve todos that are
+    /// already known to be back.
+    pub fn observe(
+        &mut self,
+        snapshot: &AdvisorTodosBackend,
+        at: DateTime<Utc>,
+    ) -> Result<Vec<AdvisorTodoTransitionBackend>> {
+        if let Some(updated_at) = self.updated_at {
+            if at < updated_at {
+                return Err(anyhow!(
+                    "snapshot at {at} precedes the last one at {updated_at}"
+                ));
+            }
+        }
+        self.updated_at = Some(at);
+
+        let mut present: Vec<(String, &AdvisorTodoBackend)> = Vec::new();
+        for todo in &snapshot.todos {
+            let id = advisor_todo_id(&todo.advice);
+            match present.iter_mut().find(|(other, _)| *other == id) {
+                Some(entry) => {
+                    if todo.priority > entry.1.priority {
+                        entry.1 = todo;
+                    }
+                }
+                None => present.push((id, todo)),
+            }
+        }
+        present.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut transitions = Vec::new();
+        for (id, todo) in &present {
+            match self.position(id) {
+                Ok(index) => {
+                    let tracked = &mut self.todos[index];
+                    let from = tracked.state;
+                    tracked.priority = todo.priority.clone();
+                    tracked.last_seen = at;
+                    match from {
+                        AdvisorTodoStateBackend::Done => {
+                            tracked.regressions += 1;
+                            tracked.state = AdvisorTodoStateBackend::Regressed;
+                            tracked.opened_at = at;
+                            tracked.resolved_at = None;
+                        }
+                        AdvisorTodoStateBackend::Snoozed
+                            if tracked.snoozed_until.is_none_or(|until| until <= at) =>
+                        {
+                            tracked.state = tracked.reopened_state();
+                            tracked.snoozed_until = None;
+                        }
+                        _ => {}
+                    }
+                    if tracked.state != from {
+                        transitions.push(AdvisorTodoTransitionBackend {
+                            id: id.clone(),
+                            from: Some(from),
+                            to: tracked.state,
+                        });
+                    }
+                }
+                Err(index) => {
+                    self.todos.insert(
+                        index,
+                        TrackedAdvisorTodoBackend {
+                            id: id.clone(),
+                            advice: todo.advice.clone(),
+                            priority: todo.priority.clone(),
+                            state: AdvisorTodoStateBackend::Open,
+                            first_seen: at,
+                            last_seen: at,
+                            opened_at: at,
+                            resolved_at: None,
+                            snoozed_until: None,
+                            regressions: 0,
+                            closed_open_secs: 0,
+                        },
+                    );
+                    transitions.push(AdvisorTodoTransitionBackend {
+                        id: id.clone(),
+                        from: None,
+                        to: AdvisorTodoStateBackend::Open,
+                    });
+                }
+            }
+        }
+
+        for tracked in &mut self.todos {
+            if !tracked.is_open()
+                || present
+                    .binary_search_by(|(id, _)| id.cmp(&tracked.id))
+                    .is_ok()
+            {
+                continue;
+            }
+            let from = tracked.state;
+            tracked.closed_open_secs += (at - tracked.opened_at).num_seconds().max(0);
+            tracked.state = AdvisorTodoStateBackend::Done;
+            tracked.resolved_at = Some(at);
+            tracked.snoozed_until = None;
+            transitions.push(AdvisorTodoTransitionBackend {
+                id: tracked.id.clone(),
+                from: Some(from),
+                to: AdvisorTodoStateBackend::Done,
+            });
+        }
+        transitions.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(transitions)
+    }
+
+    /// Snoozes an open todo until `until`. Fails for an unknown or Done todo.
+    pub fn snooze(&mut self, id: &str, until: DateTime<Utc>) -> Result<()> {
+        let index = self
+            .position(id)
+            .map_err(|_| anyhow!("unknown advisor todo {id}"))?;
+        let tracked = &mut self.todos[index];
+        if !tracked.is_open() {
+            return Err(anyhow!("advisor todo {id} is done"));
+        }
+        tracked.state = AdvisorTodoStateBackend::Snoozed;
+        tracked.snoozed_until = Some(until);
+        Ok(())
+    }
+
+    /// Todos currently in `state`.
+    pub fn in_state(&self, state: AdvisorTodoStateBackend) -> Vec<&TrackedAdvisorTodoBackend> {
+        self.todos
+            .iter()
+            .filter(|todo| todo.state == state)
+            .collect()
+    }
+
+    /// Todos resolved in `[from, to)` and still Done.
+    pub fn closed_between(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Vec<&TrackedAdvisorTodoBackend> {
+        self.todos
+            .iter()
+            .filter(|todo| {
+                todo.resolved_at
+                    .is_some_and(|resolved| from <= resolved && resolved < to)
+            })
+            .collect()
+    }
+
+    fn position(&self, id: &str) -> std::result::Result<usize, usize> {
+        self.todos.binary_search_by(|todo| todo.id.as_str().cmp(id))
+    }
+}
+
+/// Number of todos resolved in `[from, to)` across every device's tracker.
+pub fn fleet_closed_between(
+    trackers: &[AdvisorTodoTrackerBackend],
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> usize {
+    trackers
+        .iter()
+        .map(|tracker| tracker.closed_between(from, to).len())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::advisor_generator_backend::AdvisorTodosBuilderBackend;
+    use crate::advisor_todos_backend::AdviceTypeBackend;
+    use crate::session_info_backend::SessionInfoBackend;
+    use crate::test_fixtures::{detailed_score, session};
+    use chrono::TimeZone;
+
+    fn day(day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, day, 12, 0, 0).unwrap()
+    }
+
+    fn todo(details: &str, priority: AdvicePriorityBackend) -> AdvisorTodoBackend {
+        AdvisorTodoBackend {
+            advice: AdvisorAdviceBackend {
+                advice_type: AdviceTypeBackend::Threat,
+                advice_details: details.to_string(),
+            },
+            priority,
+        }
+    }
+
+    fn snapshot(todos: Vec<AdvisorTodoBackend>) -> AdvisorTodosBackend {
+        AdvisorTodosBackend {
+            system_overview: String::new(),
+            todos,
+            email: None,
+            question: None,
+        }
+    }
+
+    fn id(details: &str) -> String {
+        advisor_todo_id(&todo(details, AdvicePriorityBackend::Low).advice)
+    }
+
+    #[test]
+    fn id_ignores_priority_but_not_advice() {
+        assert_eq!(
+            advisor_todo_id(&todo("firewall", AdvicePriorityBackend::Low).advice),
+            advisor_todo_id(&todo("firewall", AdvicePriorityBackend::High).advice)
+        );
+        let mut port = todo("firewall", AdvicePriorityBackend::Low);
+        port.advice.advice_type = AdviceTypeBackend::NetworkPort;
+        assert_ne!(advisor_todo_id(&port.advice), id("firewall"));
+    }
+
+    #[test]
+    fn resolves_and_regresses() {
+        let mut tracker = AdvisorTodoTrackerBackend::new();
+        let opened = tracker
+            .observe(
+                &snapshot(vec![
+                    todo("firewall", AdvicePriorityBackend::High),
+                    todo("encryption", AdvicePriorityBackend::Low),
+                ]),
+                day(1),
+            )
+            .unwrap();
+        assert_eq!(opened.len(), 2);
+        assert!(opened.iter().all(|t| t.from.is_none()));
+
+        let resolved = tracker
+            .observe(
+                &snapshot(vec![todo("encryption", AdvicePriorityBackend::Medium)]),
+                day(3),
+            )
+            .unwrap();
+        assert_eq!(
+            resolved,
+            vec![AdvisorTodoTransitionBackend {
+                id: id("firewall"),
+                from: Some(AdvisorTodoStateBackend::Open),
+                to: AdvisorTodoStateBackend::Done,
+            }]
+        );
+        let encryption = tracker.todo(&id("encryption")).unwrap();
+        assert_eq!(encryption.priority, AdvicePriorityBackend::Medium);
+        assert_eq!(encryption.state, AdvisorTodoStateBackend::Open);
+
+        let regressed = tracker
+            .observe(
+                &snapshot(vec![
+                    todo("firewall", AdvicePriorityBackend::High),
+                    todo("encryption", AdvicePriorityBackend::Medium),
+                ]),
+                day(5),
+            )
+            .unwrap();
+        assert_eq!(regressed.len(), 1);
+        assert_eq!(regressed[0].to, AdvisorTodoStateBackend::Regressed);
+
+        let firewall = tracker.todo(&id("firewall")).unwrap();
+        assert_eq!(firewall.regressions, 1);
+        assert_eq!(firewall.first_seen, day(1));
+        // Two days open, two days done, one day open again.
+        assert_eq!(firewall.open_time(day(6)), Duration::days(3));
+    }
+
+    #[test]
+    fn snooze_expires_while_present() {
+        let mut tracker = AdvisorTodoTrackerBackend::new();
+        let present = snapshot(vec![todo("firewall", AdvicePriorityBackend::High)]);
+        tracker.observe(&present, day(1)).unwrap();
+        tracker.snooze(&id("firewall"), day(4)).unwrap();
+
+        assert!(tracker.observe(&present, day(2)).unwrap().is_empty());
+        assert_eq!(tracker.in_state(AdvisorTodoStateBackend::Snoozed).len(), 1);
+
+        let woke = tracker.observe(&present, day(4)).unwrap();
+        assert_eq!(woke[0].from, Some(AdvisorTodoStateBackend::Snoozed));
+        assert_eq!(woke[0].to, AdvisorTodoStateBackend::Open);
+        // Snoozed time still counts as open.
+        assert_eq!(
+            tracker.todo(&id("firewall")).unwrap().open_time(day(4)),
+            Duration::days(3)
+        );
+    }
+
+    #[test]
+    fn snoozed_todo_that_disappears_is_done() {
+        let mut tracker = AdvisorTodoTrackerBackend::new();
+        tracker
+            .observe(
+                &snapshot(vec![todo("firewall", AdvicePriorityBackend::High)]),
+                day(1),
+            )
+            .unwrap();
+        tracker.snooze(&id("firewall"), day(10)).unwrap();
+        let done = tracker.observe(&snapshot(vec![]), day(2)).unwrap();
+        assert_eq!(done[0].from, Some(AdvisorTodoStateBackend::Snoozed));
+        assert_eq!(done[0].to, AdvisorTodoStateBackend::Done);
+        let tracked = tracker.todo(&id("firewall")).unwrap();
+        assert_eq!(tracked.snoozed_until, None);
+        assert!(tracker.snooze(&id("firewall"), day(12)).is_err());
+        assert!(tracker.snooze(&id("unknown"), day(12)).is_err());
+    }
+
+    #[test]
+    fn rejects_out_of_order_snapshots() {
+        let mut tracker = AdvisorTodoTrackerBackend::new();
+        tracker.observe(&snapshot(vec![]), day(5)).unwrap();
+        assert!(tracker.observe(&snapshot(vec![]), day(4)).is_err());
+    }
+
+    #[test]
+    fn counts_closed_per_device_and_fleet() {
+        let mut a = AdvisorTodoTrackerBackend::new();
+        let mut b = AdvisorTodoTrackerBackend::new();
+        let both = snapshot(vec![
+            todo("firewall", AdvicePriorityBackend::High),
+            todo("encryption", AdvicePriorityBackend::Low),
+        ]);
+        a.observe(&both, day(1)).unwrap();
+        b.observe(&both, day(1)).unwrap();
+        a.observe(&snapshot(vec![]), day(3)).unwrap();
+        b.observe(
+            &snapshot(vec![todo("encryption", AdvicePriorityBackend::Low)]),
+            day(9),
+        )
+        .unwrap();
+
+        assert_eq!(a.closed_between(day(1), day(8)).len(), 2);
+        assert_eq!(b.closed_between(day(1), day(8)).len(), 0);
+        assert_eq!(
+            fleet_closed_between(&[a.clone(), b.clone()], day(1), day(8)),
+            2
+        );
+        assert_eq!(fleet_closed_between(&[a, b], day(1), day(10)), 3);
+    }
+
+    #[test]
+    fn duplicate_advice_in_a_snapshot_is_one_todo() {
+        let mut tracker = AdvisorTodoTrackerBackend::new();
+        tracker
+            .observe(
+                &snapshot(vec![
+                    todo("firewall", AdvicePriorityBackend::Low),
+                    todo("firewall", AdvicePriorityBackend::Critical),
+                ]),
+                day(1),
+            )
+            .unwrap();
+        assert_eq!(tracker.todos.len(), 1);
+        assert_eq!(tracker.todos[0].priority, AdvicePriorityBackend::Critical);
+    }
+
+    #[test]
+    fn port_and_session_ids_follow_their_target() {
+        let advice = |advice_type, details: &str| AdvisorAdviceBackend {
+            advice_type,
+            advice_details: details.to_string(),
+        };
+        let session = advice(AdviceTypeBackend::NetworkSession, "TCP/[2001:db8::1]:443");
+        assert_eq!(
+            advisor_todo_id(&session),
+            advisor_todo_id(&session.details().into())
+        );
+        assert_ne!(
+            advisor_todo_id(&session),
+            advisor_todo_id(&advice(
+                AdviceTypeBackend::NetworkSession,
+                "UDP/[2001:db8::1]:443"
+            ))
+        );
+        assert_ne!(
+            advisor_todo_id(&advice(AdviceTypeBackend::NetworkPort, "22")),
+            advisor_todo_id(&advice(AdviceTypeBackend::NetworkPort, "23"))
+        );
+        // Details that do not parse are still told apart.
+        assert_ne!(
+            advisor_todo_id(&advice(AdviceTypeBackend::NetworkPort, "ssh")),
+            advisor_todo_id(&advice(AdviceTypeBackend::NetworkPort, "telnet"))
+        );
+    }
+
+    #[test]
+    fn session_criticality_change_is_no_transition() {
+        let score = detailed_score(vec![], "2024-03-01T12:00:00Z");
+        let todos = |criticality: &str| {
+            let sessions = [SessionInfoBackend {
+                criticality: criticality.to_string(),
+                ..session("203.0.113.7")
+            }];
+            let todos = AdvisorTodosBuilderBackend::new(&score)
+                .with_sessions(&sessions)
+                .build();
+            snapshot(todos.into_iter().map(|todo| todo.todo).collect())
+        };
+
+        let mut tracker = AdvisorTodoTrackerBackend::new();
+        let opened = tracker.observe(&todos("anomaly:abnormal"), day(1)).unwrap();
+        assert_eq!(opened.len(), 1);
+        let changed = tracker
+            .observe(&todos("blacklist:firehol_level1"), day(2))
+            .unwrap();
+        assert!(changed.is_empty(), "{changed:?}");
+        assert_eq!(tracker.todos.len(), 1);
+        assert_eq!(tracker.todos[0].priority, AdvicePriorityBackend::High);
+        assert_eq!(tracker.todos[0].state, AdvisorTodoStateBackend::Open);
+    }
+}
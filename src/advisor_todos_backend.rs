@@ -1,3 +1,4 @@
+use crate::stable_hash_backend::StableHash;
 use blake3::Hasher;
 use serde::{Deserialize, Serialize};
 
@@ -11,12 +12,19 @@ pub enum AdviceTypeBackend {
     Configure,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct AdvisorAdviceBackend {
     pub advice_type: AdviceTypeBackend,
     pub advice_details: String,
 }
 
+impl AdvisorAdviceBackend {
+    /// Typed `advice_details` -- see [`crate::advice_details_backend`].
+    pub fn details(&self) -> crate::advice_details_backend::AdviceDetailsBackend {
+        crate::advice_details_backend::AdviceDetailsBackend::from(self)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
 pub enum AdvicePriorityBackend {
     Low,
@@ -25,7 +33,7 @@ pub enum AdvicePriorityBackend {
     Critical,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct AdvisorTodoBackend {
     pub advice: AdvisorAdviceBackend,
     pub priority: AdvicePriorityBackend,
@@ -41,6 +49,13 @@ pub struct AdvisorTodosBackend {
 
 impl AdvisorTodosBackend {
     pub fn uid(&self, language: &str) -> String {
+        let mut hasher = crate::uid_backend::uid_hasher("todos", language);
+        self.stable_hash_into(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Scheme 1 uid, kept to rekey caches -- see [`crate::uid_backend`].
+    pub fn legacy_uid(&self, language: &str) -> String {
         let mut hasher = Hasher::new();
         hasher.update(language.as_bytes());
         hasher.update(self.system_overview.as_bytes());
@@ -0,0 +1,283 @@
+//! CVSS v3 base score computed from a vector string.
+//!
+//! A vulnerability feed ships both a vector and a score, and the two disagree
+//! more often than they should. [`CvssV3Backend::parse`] reads the vector and
+//! [`CvssV3Backend::base_score`] recomputes the score per the CVSS v3.1
+//! specification (section 7.1, including its `Roundup`), so a score is
+//! derived rather than trusted.
+
+use anyhow::{anyhow, Result};
+use std::fmt::Display;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CvssAttackVectorBackend {
+    Network,
+    Adjacent,
+    Local,
+    Physical,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CvssLevelBackend {
+    None,
+    Low,
+    High,
+}
+
+/// Parsed CVSS v3.0/v3.1 base metrics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CvssV3Backend {
+    /// Minor version, `0` or `1`.
+    pub minor: u8,
+    pub attack_vector: CvssAttackVectorBackend,
+    /// Only `Low` or `High`.
+    pub attack_complexity: CvssLevelBackend,
+    pub privileges_required: CvssLevelBackend,
+    /// `true` when user interaction is required.
+    pub user_interaction: bool,
+    /// `true` when scope is changed.
+    pub scope_changed: bool,
+    pub confidentiality: CvssLevelBackend,
+    pub integrity: CvssLevelBackend,
+    pub availability: CvssLevelBackend,
+}
+
+/// Temporal and environmental metric keys. Accepted in a vector and ignored:
+/// they do not move the base score.
+const NON_BASE_METRICS: &[&str] = &[
+    "E", "RL", "RC", "CR", "IR", "AR", "MAV", "MAC", "MPR", "MUI", "MS", "MC", "MI", "MA",
+];
+
+impl CvssV3Backend {
+    /// Parse `CVSS:3.x/AV:_/AC:_/PR:_/UI:_/S:_/C:_/I:_/A:_`. Every base metric
+    /// must appear exactly once; order is free.
+    pub fn parse(vector: &str) -> Result<Self> {
+        let mut parts = vector.trim().split('/');
+        let minor = match parts.next() {
+            Some("CVSS:3.0") => 0,
+            Some("CVSS:3.1") => 1,
+            other => return Err(anyhow!("unsupported CVSS prefix: {other:?}")),
+        };
+
+        let mut base: [Option<&str>; 8] = [None; 8];
+        const KEYS: [&str; 8] = ["AV", "AC", "PR", "UI", "S", "C", "I", "A"];
+        for part in parts {
+            let (key, value) = part
+                .split_once(':')
+                .ok_or_else(|| anyhow!("malformed CVSS metric: {part:?}"))?;
+            match KEYS.iter().position(|known| *known == key) {
+                Some(index) => {
+                    if base[index].replace(value).is_some() {
+                        return Err(anyhow!("duplicate CVSS metric: {key}"));
+                    }
+                }
+                None if NON_BASE_METRICS.contains(&key) => {}
+                None => return Err(anyhow!("unknown CVSS metric: {key}")),
+            }
+        }
+        let get = |index: usize| {
+            base[index].ok_or_else(|| anyhow!("missing CVSS metric: {}", KEYS[index]))
+        };
+        let bad = |index: usize, value: &str| anyhow!("bad CVSS value {}:{value}", KEYS[index]);
+        let level = |index: usize, allow_none: bool| -> Result<CvssLevelBackend> {
+            match get(index)? {
+                "N" if allow_none => Ok(CvssLevelBackend::None),
+                "L" => Ok(CvssLevelBackend::Low),
+                "H" => Ok(CvssLevelBackend::High),
+                value => Err(bad(index, value)),
+            }
+        };
+
+        Ok(Self {
+            minor,
+            attack_vector: match get(0)? {
+                "N" => CvssAttackVectorBackend::Network,
+                "A" => CvssAttackVectorBackend::Adjacent,
+                "L" => CvssAttackVectorBackend::Local,
+                "P" => CvssAttackVectorBackend::Physical,
+                value => return Err(bad(0, value)),
+            },
+            attack_complexity: level(1, false)?,
+            privileges_required: level(2, true)?,
+            user_interaction: match get(3)? {
+                "N" => false,
+                "R" => true,
+                value => return Err(bad(3, value)),
+            },
+            scope_changed: match get(4)? {
+                "U" => false,
+                "C" => true,
+                value => return Err(bad(4, value)),
+            },
+            confidentiality: level(5, true)?,
+            integrity: level(6, true)?,
+            availability: level(7, true)?,
+        })
+    }
+
+    /// Base score, 0.0 to 10.0, one decimal.
+    pub fn base_score(&self) -> f64 {
+        let cia = |level: CvssLevelBackend| -> f64 {
+            match level {
+                CvssLevelBackend::High => 0.56,
+                CvssLevelBackend::Low => 0.22,
+                CvssLevelBackend::None => 0.0,
+            }
+        };
+        let iss = 1.0
+            - (1.0 - cia(self.confidentiality))
+                * (1.0 - cia(self.integrity))
+                * (1.0 - cia(self.availability));
+        let impact = if self.scope_changed {
+            7.52 * (iss - 0.029) - 3.25 * (iss - 0.02).powi(15)
+        } else {
+            6.42 * iss
+        };
+        if impact <= 0.0 {
+            return 0.0;
+        }
+
+        let attack_vector: f64 = match self.attack_vector {
+            CvssAttackVectorBackend::Network => 0.85,
+            CvssAttackVectorBackend::Adjacent => 0.62,
+            CvssAttackVectorBackend::Local => 0.55,
+            CvssAttackVectorBackend::Physical => 0.2,
+        };
+        let attack_complexity = match self.attack_complexity {
+            CvssLevelBackend::High => 0.44,
+            _ => 0.77,
+        };
+        let privileges_required = match (self.privileges_required, self.scope_changed) {
+            (CvssLevelBackend::None, _) => 0.85,
+            (CvssLevelBackend::Low, false) => 0.62,
+            (CvssLevelBackend::Low, true) => 0.68,
+            (CvssLevelBackend::High, false) => 0.27,
+            (CvssLevelBackend::High, true) => 0.5,
+        };
+        let user_interaction = if self.user_interaction { 0.62 } else { 0.85 };
+        let exploitability =
+            8.22 * attack_vector * attack_complexity * privileges_required * user_interaction;
+
+        if self.scope_changed {
+            roundup((1.08 * (impact + exploitability)).min(10.0))
+        } else {
+            roundup((impact + exploitability).min(10.0))
+        }
+    }
+}
+
+/// CVSS v3.1 `Roundup`: smallest one-decimal number ≥ `value`, computed on
+/// integers so floating-point noise cannot push 4.0 to 4.1.
+fn roundup(value: f64) -> f64 {
+    let int_input = (value * 100_000.0).round() as i64;
+    if int_input % 10_000 == 0 {
+        int_input as f64 / 100_000.0
+    } else {
+        (int_input / 10_000 + 1) as f64 / 10.0
+    }
+}
+
+/// Qualitative rating of a base score (CVSS v3.1 section 5).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CvssSeverityBackend {
+    None,
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl CvssSeverityBackend {
+    pub fn from_score(score: f64) -> Self {
+        match score {
+            s if s >= 9.0 => Self::Critical,
+            s if s >= 7.0 => Self::High,
+            s if s >= 4.0 => Self::Medium,
+            s if s > 0.0 => Self::Low,
+            _ => Self::None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+            Self::Critical => "critical",
+        }
+    }
+}
+
+impl Display for CvssSeverityBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(vector: &str) -> f64 {
+        CvssV3Backend::parse(vector).unwrap().base_score()
+    }
+
+    #[test]
+    fn computes_reference_scores() {
+        assert_eq!(score("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H"), 9.8);
+        assert_eq!(score("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:C/C:H/I:H/A:H"), 10.0);
+        assert_eq!(score("CVSS:3.1/AV:L/AC:L/PR:L/UI:N/S:U/C:H/I:H/A:H"), 7.8);
+        assert_eq!(score("CVSS:3.0/AV:N/AC:L/PR:N/UI:R/S:C/C:L/I:L/A:N"), 6.1);
+        assert_eq!(score("CVSS:3.1/AV:P/AC:H/PR:H/UI:R/S:U/C:L/I:N/A:N"), 1.6);
+        assert_eq!(score("CVSS:3.1/AV:N/AC:H/PR:N/UI:N/S:U/C:N/I:N/A:N"), 0.0);
+    }
+
+    #[test]
+    fn metric_order_is_free_and_non_base_metrics_are_ignored() {
+        assert_eq!(
+            score("CVSS:3.1/A:H/I:H/C:H/S:U/UI:N/PR:N/AC:L/AV:N/E:P/RL:O"),
+            9.8
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_vectors() {
+        for vector in [
+            "",
+            "CVSS:2.0/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H",
+            "CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H",
+            "CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H/A:L",
+            "CVSS:3.1/AV:X/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H",
+            "CVSS:3.1/AV:N/AC:N/PR:N/UI:N/S:U/C:H/I:H/A:H",
+            "CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H/ZZ:1",
+            "CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/AH",
+        ] {
+            assert!(CvssV3Backend::parse(vector).is_err(), "{vector}");
+        }
+    }
+
+    #[test]
+    fn severity_bands() {
+        assert_eq!(
+            CvssSeverityBackend::from_score(0.0),
+            CvssSeverityBackend::None
+        );
+        assert_eq!(
+            CvssSeverityBackend::from_score(3.9),
+            CvssSeverityBackend::Low
+        );
+        assert_eq!(
+            CvssSeverityBackend::from_score(4.0),
+            CvssSeverityBackend::Medium
+        );
+        assert_eq!(
+            CvssSeverityBackend::from_score(7.0),
+            CvssSeverityBackend::High
+        );
+        assert_eq!(
+            CvssSeverityBackend::from_score(9.8),
+            CvssSeverityBackend::Critical
+        );
+    }
+}
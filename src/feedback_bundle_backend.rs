@@ -0,0 +1,699 @@
+//! Size-bounded feedback bundles.
+//!
+//! `app_log` and `helper_log` are unbounded, and a large log makes the whole
+//! feedback submission fail at the API gateway. [`FeedbackBundleBuilderBackend`]
+//! packs a [`FeedbackInfoBackend`] into a [`FeedbackBundleBackend`] whose
+//! serialized JSON fits a byte budget:
+//!
+//! - the budget covers the whole `serde_json` output: field names, the
+//!   non-log fields, and the logs as escaped JSON strings. A newline costs
+//!   two bytes and a control character six, so a plain-text log is measured
+//!   escaped, not raw;
+//! - the log budget is shared fairly: a log smaller than its half keeps all
+//!   of it and the other log gets the rest;
+//! - a log that does not fit keeps its head and its tail, cut on line
+//!   boundaries when there are any, with an elision marker giving the number
+//!   of bytes removed in between;
+//! - logs can be compressed with gzip, or zstd with the `zstd` feature, and
+//!   carried as base64, in which case truncation only happens when even the
+//!   compressed log does not fit.
+//!
+//! Every log records its original size, and [`FeedbackBundleBackend::decode`]
+//! gives back the feedback with the (truncated) logs in plain text.
+
+use crate::feedback_info_backend::FeedbackInfoBackend;
+use anyhow::{anyhow, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+
+/// zstd level: a fast level is enough for text logs.
+#[cfg(feature = "zstd")]
+const ZSTD_LEVEL: i32 = 3;
+
+/// Hard ceiling on a decompressed log, whatever `original_bytes` claims.
+const MAX_DECODED_LOG_BYTES: u64 = 64 * 1024 * 1024;
+
+#[cfg(not(feature = "zstd"))]
+fn zstd_disabled() -> anyhow::Error {
+    anyhow!("zstd log compression needs the `zstd` feature")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FeedbackCompressionBackend {
+    None,
+    Zstd,
+    Gzip,
+}
+
+impl FeedbackCompressionBackend {
+    pub const ALL: [Self; 3] = [Self::None, Self::Zstd, Self::Gzip];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Zstd => "zstd",
+            Self::Gzip => "gzip",
+        }
+    }
+
+    pub fn from_str_opt(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.as_str() == value)
+    }
+
+    /// Wire content for `text`: the text itself, or base64 of its
+    /// compressed bytes.
+    fn encode(&self, text: &str) -> Result<String> {
+        match self {
+            Self::None => Ok(text.to_string()),
+            #[cfg(feature = "zstd")]
+            Self::Zstd => Ok(BASE64.encode(zstd::encode_all(text.as_bytes(), ZSTD_LEVEL)?)),
+            #[cfg(not(feature = "zstd"))]
+            Self::Zstd => Err(zstd_disabled()),
+            Self::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(text.as_bytes())?;
+                Ok(BASE64.encode(encoder.finish()?))
+            }
+        }
+    }
+
+    /// Plain text of wire `content`, failing if it decompresses to more than
+    /// `max_bytes`.
+    fn decode(&self, content: &str, max_bytes: u64) -> Result<String> {
+        let bytes = match self {
+            Self::None => return Ok(content.to_string()),
+            #[cfg(feature = "zstd")]
+            Self::Zstd => {
+                let compressed = BASE64.decode(content)?;
+                read_capped(zstd::Decoder::new(compressed.as_slice())?, max_bytes)?
+            }
+            #[cfg(not(feature = "zstd"))]
+            Self::Zstd => return Err(zstd_disabled()),
+            Self::Gzip => {
+                let compressed = BASE64.decode(content)?;
+                read_capped(
+                    flate2::read::GzDecoder::new(compressed.as_slice()),
+                    max_bytes,
+                )?
+            }
+        };
+        String::from_utf8(bytes).map_err(|e| anyhow!("decoded log is not UTF-8: {e}"))
+    }
+}
+
+fn read_capped(reader: impl Read, max_bytes: u64) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    reader.take(max_bytes + 1).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 > max_bytes {
+        return Err(anyhow!("decoded log exceeds {max_bytes} bytes"));
+    }
+    Ok(bytes)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct FeedbackLogBackend {
+    /// See [`FeedbackCompressionBackend`].
+    pub compression: String,
+    /// Plain text for `none`, base64 of the compressed text otherwise.
+    pub content: String,
+    /// Size of the log before truncation, in bytes.
+    pub original_bytes: u64,
+    /// Bytes removed by truncation; 0 when the log is whole.
+    pub elided_bytes: u64,
+}
+
+impl FeedbackLogBackend {
+    pub fn is_truncated(&self) -> bool {
+        self.elided_bytes > 0
+    }
+
+    /// The (possibly truncated) log as plain text. A compressed log may
+    /// not decompress past `original_bytes` plus its elision marker, nor
+    /// past 64 MiB.
+    pub fn decode(&self) -> Result<String> {
+        let marker = elision_marker(self.original_bytes as usize).len() as u64;
+        let max_bytes = self
+            .original_bytes
+            .saturating_add(marker)
+            .min(MAX_DECODED_LOG_BYTES);
+        FeedbackCompressionBackend::from_str_opt(&self.compression)
+            .ok_or_else(|| anyhow!("unknown log compression {:?}", self.compression))?
+            .decode(&self.content, max_bytes)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FeedbackBundleBackend {
+    /// The feedback with `app_log` and `helper_log` emptied: they travel
+    /// below.
+    pub feedback: FeedbackInfoBackend,
+    pub app_log: FeedbackLogBackend,
+    pub helper_log: FeedbackLogBackend,
+    pub budget_bytes: u64,
+}
+
+impl FeedbackBundleBackend {
+    /// The feedback with its logs decoded back in.
+    pub fn decode(&self) -> Result<FeedbackInfoBackend> {
+        let mut feedback = self.feedback.clone();
+        feedback.app_log = self.app_log.decode()?;
+        feedback.helper_log = self.helper_log.decode()?;
+        Ok(feedback)
+    }
+
+    /// Bytes counted against the budget: the length of the compact JSON
+    /// `serde_json` writes for the bundle, measured without serializing it.
+    pub fn size(&self) -> u64 {
+        let Self {
+            feedback,
+            app_log,
+            helper_log,
+            budget_bytes,
+        } = self;
+        json_object_len(&[
+            ("feedback", feedback_json_len(feedback)),
+            ("app_log", log_json_len(app_log)),
+            ("helper_log", log_json_len(helper_log)),
+            ("budget_bytes", json_u64_len(*budget_bytes)),
+        ]) as u64
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FeedbackBundleBuilderBackend {
+    budget_bytes: u64,
+    compression: FeedbackCompressionBackend,
+}
+
+impl FeedbackBundleBuilderBackend {
+    pub fn new(budget_bytes: u64) -> Self {
+        Self {
+            budget_bytes,
+            compression: FeedbackCompressionBackend::None,
+        }
+    }
+
+    pub fn with_compression(mut self, compression: FeedbackCompressionBackend) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Fails when the bundle does not fit even with both logs empty.
+    pub fn build(&self, feedback: &FeedbackInfoBackend) -> Result<FeedbackBundleBackend> {
+        let mut stripped = feedback.clone();
+        stripped.app_log = String::new();
+        stripped.helper_log = String::new();
+        // The bundle with empty log contents. Every compression name is four
+        // bytes and the elided count never has more digits than the original
+        // size, so whatever the logs become, the rest of the JSON is at most
+        // this long.
+        let empty_log = |log: &str| FeedbackLogBackend {
+            compression: self.compression.as_str().to_string(),
+            content: String::new(),
+            original_bytes: log.len() as u64,
+            elided_bytes: log.len() as u64,
+        };
+        let mut bundle = FeedbackBundleBackend {
+            feedback: stripped,
+            app_log: empty_log(&feedback.app_log),
+            helper_log: empty_log(&feedback.helper_log),
+            budget_bytes: self.budget_bytes,
+        };
+        let fixed = bundle.size();
+        if fixed > self.budget_bytes {
+            return Err(anyhow!(
+                "feedback fields take {fixed} bytes, over the {} byte budget",
+                self.budget_bytes
+            ));
+        }
+        let log_budget = (self.budget_bytes - fixed) as usize;
+
+        let app_full = self.compression.encode(&feedback.app_log)?;
+        let helper_full = self.compression.encode(&feedback.helper_log)?;
+        let (app_budget, helper_budget) = fair_shares(
+            json_escaped_len(&app_full),
+            json_escaped_len(&helper_full),
+            log_budget,
+        );
+
+        bundle.app_log = self.pack(&feedback.app_log, app_full, app_budget)?;
+        bundle.helper_log = self.pack(&feedback.helper_log, helper_full, helper_budget)?;
+        Ok(bundle)
+    }
+
+    fn pack(&self, log: &str, full: String, budget: usize) -> Result<FeedbackLogBackend> {
+        let mut packed = FeedbackLogBackend {
+            compression: self.compression.as_str().to_string(),
+            content: full,
+            original_bytes: log.len() as u64,
+            elided_bytes: 0,
+        };
+        if json_escaped_len(&packed.content) <= budget {
+            return Ok(packed);
+        }
+        // Largest plain size whose encoding fits; encoded size grows with the
+        // plain size, so a binary search finds it. When not even an empty
+        // compressed log fits, the log is dropped and sent as empty plain
+        // text.
+        let (mut low, mut high) = (0, log.len());
+        let mut best = (
+            FeedbackCompressionBackend::None,
+            String::new(),
+            log.len() as u64,
+        );
+        while low <= high {
+            let mid = low + (high - low) / 2;
+            let (text, elided) = truncate_middle(log, mid);
+            let content = self.compression.encode(&text)?;
+            if json_escaped_len(&content) <= budget {
+                best = (self.compression, content, elided);
+                low = mid + 1;
+            } else if mid == 0 {
+                break;
+            } else {
+                high = mid - 1;
+            }
+        }
+        let (compression, content, elided) = best;
+        packed.compression = compression.as_str().to_string();
+        packed.content = content;
+        packed.elided_bytes = elided;
+        Ok(packed)
+    }
+}
+
+// JSON lengths, byte for byte what `serde_json` writes in compact form. The
+// destructuring below breaks the build when a field is added unmeasured.
+
+fn feedback_json_len(feedback: &FeedbackInfoBackend) -> usize {
+    let FeedbackInfoBackend {
+        core_info,
+        threat_model_name,
+        threat_model_date,
+        threat_model_signature,
+        stars,
+        helper_state,
+        os_name,
+        os_version,
+        context,
+        note,
+        email,
+        app_log,
+        helper_log,
+    } = feedback;
+    json_object_len(&[
+        ("core_info", json_string_len(core_info)),
+        ("threat_model_name", json_string_len(threat_model_name)),
+        ("threat_model_date", json_string_len(threat_model_date)),
+        (
+            "threat_model_signature",
+            json_string_len(threat_model_signature),
+        ),
+        ("stars", json_f64_len(*stars)),
+        ("helper_state", json_string_len(helper_state)),
+        ("os_name", json_string_len(os_name)),
+        ("os_version", json_string_len(os_version)),
+        ("context", json_string_len(context)),
+        ("note", json_string_len(note)),
+        ("email", json_string_len(email)),
+        ("app_log", json_string_len(app_log)),
+        ("helper_log", json_string_len(helper_log)),
+    ])
+}
+
+fn log_json_len(log: &FeedbackLogBackend) -> usize {
+    let FeedbackLogBackend {
+        compression,
+        content,
+        original_bytes,
+        elided_bytes,
+    } = log;
+    json_object_len(&[
+        ("compression", json_string_len(compression)),
+        ("content", json_string_len(content)),
+        ("original_bytes", json_u64_len(*original_bytes)),
+        ("elided_bytes", json_u64_len(*elided_bytes)),
+    ])
+}
+
+/// `{"name":value,...}` around values of the given lengths.
+fn json_object_len(fields: &[(&str, usize)]) -> usize {
+    let fields_len: usize = fields
+        .iter()
+        .map(|(name, value_len)| json_string_len(name) + 1 + value_len)
+        .sum();
+    2 + fields_len + fields.len().saturating_sub(1)
+}
+
+fn json_string_len(text: &str) -> usize {
+    json_escaped_len(text) + 2
+}
+
+/// Bytes `text` takes inside a JSON string, quotes excluded: `"`, `\` and
+/// the short-escaped controls take two, other controls six (`\u001f`).
+fn json_escaped_len(text: &str) -> usize {
+    text.chars()
+        .map(|c| match c {
+            '"' | '\\' | '\n' | '\r' | '\t' | '\u{08}' | '\u{0c}' => 2,
+            c if (c as u32) < 0x20 => 6,
+            c => c.len_utf8(),
+        })
+        .sum()
+}
+
+fn json_u64_len(value: u64) -> usize {
+    value
+        .checked_ilog10()
+        .map_or(1, |digits| digits as usize + 1)
+}
+
+/// `serde_json` writes floats with the shortest round-trip digits, like
+/// `{:?}`, except that it keeps `[1e-5, 1e-4)` in decimal (`0.00005`, not
+/// `5e-5`), signs positive exponents (`1e+16`) and writes non-finite values
+/// as `null`.
+fn json_f64_len(value: f64) -> usize {
+    if !value.is_finite() {
+        return "null".len();
+    }
+    let debug = format!("{value:?}");
+    if (1e-5..1e-4).contains(&value.abs()) {
+        let mantissa = debug
+            .trim_start_matches('-')
+            .split('e')
+            .next()
+            .unwrap_or("");
+        let digits = mantissa.chars().filter(char::is_ascii_digit).count();
+        return usize::from(value < 0.0) + "0.0000".len() + digits;
+    }
+    let signed_exponent = debug.contains('e') && !debug.contains("e-");
+    debug.len() + usize::from(signed_exponent)
+}
+
+/// Splits `budget` between two logs needing `a` and `b` bytes.
+fn fair_shares(a: usize, b: usize, budget: usize) -> (usize, usize) {
+    if a + b <= budget {
+        return (a, b);
+    }
+    let half = budget / 2;
+    if a <= half {
+        (a, budget - a)
+    } else if b <= half {
+        (budget - b, b)
+    } else {
+        (half, budget - half)
+    }
+}
+
+fn elision_marker(elided: usize) -> String {
+    format!("\n[... {elided} bytes elided ...]\n")
+}
+
+/// `text` cut to at most `max_bytes`: head and tail around an elision
+/// marker. Returns the text and the number of bytes elided.
+pub fn truncate_middle(text: &str, max_bytes: usize) -> (String, u64) {
+    if text.len() <= max_bytes {
+        return (text.to_string(), 0);
+    }
+    // The marker for the whole text is the longest one this cut can need.
+    let marker_len = elision_marker(text.len()).len();
+    if max_bytes < marker_len {
+        return (String::new(), text.len() as u64);
+    }
+    let kept = max_bytes - marker_len;
+
+    let mut head = floor_char_boundary(text, kept / 2);
+    if let Some(newline) = text[..head].rfind('\n') {
+        head = newline + 1;
+    }
+    let mut tail = ceil_char_boundary(text, text.len() - (kept - kept / 2));
+    if let Some(newline) = text[tail..].find('\n') {
+        if tail + newline + 1 < text.len() {
+            tail += newline + 1;
+        }
+    }
+    let tail = tail.max(head);
+    let elided = tail - head;
+    (
+        format!(
+            "{}{}{}",
+            &text[..head],
+            elision_marker(elided),
+            &text[tail..]
+        ),
+        elided as u64,
+    )
+}
+
+fn floor_char_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+fn ceil_char_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index += 1;
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feedback(app_log: &str, helper_log: &str) -> FeedbackInfoBackend {
+        FeedbackInfoBackend {
+            core_info: "core".to_string(),
+            threat_model_name: String::new(),
+            threat_model_date: String::new(),
+            threat_model_signature: String::new(),
+            stars: 5.0,
+            helper_state: String::new(),
+            os_name: String::new(),
+            os_version: String::new(),
+            context: String::new(),
+            note: "note".to_string(),
+            email: String::new(),
+            app_log: app_log.to_string(),
+            helper_log: helper_log.to_string(),
+        }
+    }
+
+    fn log(lines: usize) -> String {
+        (0..lines)
+            .map(|i| format!("2024-01-01T00:00:{:02}Z line {i} of the log\n", i % 60))
+            .collect()
+    }
+
+    #[test]
+    fn small_feedback_passes_through() {
+        let original = feedback("app", "helper");
+        let bundle = FeedbackBundleBuilderBackend::new(1000)
+            .build(&original)
+            .unwrap();
+        assert!(!bundle.app_log.is_truncated());
+        assert_eq!(bundle.feedback.app_log, "");
+        assert_eq!(bundle.decode().unwrap().app_log, "app");
+        assert_eq!(bundle.decode().unwrap().helper_log, "helper");
+        assert_eq!(
+            bundle.size(),
+            serde_json::to_vec(&bundle).unwrap().len() as u64
+        );
+    }
+
+    #[test]
+    fn budget_counts_json_escaping() {
+        // Each control character serializes as six bytes.
+        let app_log: String = (0..2000)
+            .map(|i| if i % 10 == 9 { '\n' } else { '\u{1}' })
+            .collect();
+        let helper_log = "\"quoted\"\t\\path\n".repeat(200);
+        let bundle = FeedbackBundleBuilderBackend::new(3000)
+            .build(&feedback(&app_log, &helper_log))
+            .unwrap();
+        assert!(serde_json::to_vec(&bundle).unwrap().len() <= 3000);
+        assert!(bundle.size() > 2500);
+        assert!(bundle.app_log.is_truncated());
+        assert!(bundle.helper_log.is_truncated());
+    }
+
+    #[test]
+    fn size_matches_serde_json() {
+        let awkward = "quote \" slash \\ tab \t bell \u{7} del \u{7f} é 🦀\r\n";
+        for stars in [
+            0.0,
+            -0.0,
+            4.5,
+            5.0,
+            1e-4,
+            5e-5,
+            -1.5e-5,
+            1e-6,
+            1e15,
+            1e16,
+            1.5e21,
+            1e300,
+            5e-324,
+            f64::NAN,
+        ] {
+            let mut info = feedback(awkward, "");
+            info.stars = stars;
+            info.note = awkward.repeat(3);
+            let bundle = FeedbackBundleBuilderBackend::new(100_000)
+                .build(&info)
+                .unwrap();
+            assert_eq!(
+                bundle.size(),
+                serde_json::to_vec(&bundle).unwrap().len() as u64,
+                "stars {stars:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn keeps_head_and_tail_on_line_boundaries() {
+        let app_log = log(200);
+        let bundle = FeedbackBundleBuilderBackend::new(1000)
+            .build(&feedback(&app_log, ""))
+            .unwrap();
+        assert!(bundle.size() <= 1000);
+        assert_eq!(bundle.app_log.original_bytes, app_log.len() as u64);
+        assert!(bundle.app_log.is_truncated());
+
+        let text = bundle.app_log.decode().unwrap();
+        assert!(text.starts_with("2024-01-01T00:00:00Z line 0 of the log\n"));
+        assert!(text.ends_with("line 199 of the log\n"));
+        let marker = elision_marker(bundle.app_log.elided_bytes as usize);
+        let (head, tail) = text.split_once(&marker).unwrap();
+        assert!(head.ends_with('\n'));
+        assert!(tail.starts_with("2024-"));
+        assert_eq!(
+            head.len() + tail.len() + bundle.app_log.elided_bytes as usize,
+            app_log.len()
+        );
+    }
+
+    #[test]
+    fn shares_budget_fairly() {
+        let small = log(3);
+        let large = log(500);
+        let bundle = FeedbackBundleBuilderBackend::new(2000)
+            .build(&feedback(&large, &small))
+            .unwrap();
+        assert!(!bundle.helper_log.is_truncated());
+        assert!(bundle.app_log.is_truncated());
+        assert!(bundle.size() <= 2000);
+        assert!(bundle.size() > 1900);
+    }
+
+    #[test]
+    fn compressed_logs_round_trip() {
+        let app_log = log(500);
+        let mut compressions = vec![FeedbackCompressionBackend::Gzip];
+        if cfg!(feature = "zstd") {
+            compressions.push(FeedbackCompressionBackend::Zstd);
+        }
+        for compression in compressions {
+            let bundle = FeedbackBundleBuilderBackend::new(20_000)
+                .with_compression(compression)
+                .build(&feedback(&app_log, "helper"))
+                .unwrap();
+            assert_eq!(bundle.app_log.compression, compression.as_str());
+            assert!(!bundle.app_log.is_truncated());
+            assert!(bundle.app_log.content.len() < app_log.len());
+            assert_eq!(bundle.decode().unwrap().app_log, app_log);
+            assert_eq!(bundle.decode().unwrap().helper_log, "helper");
+        }
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn compressed_logs_are_truncated_to_fit() {
+        let app_log: String = (0..5000u32)
+            .map(|i| format!("{:08x}\n", i.wrapping_mul(2654435761)))
+            .collect();
+        let bundle = FeedbackBundleBuilderBackend::new(4000)
+            .with_compression(FeedbackCompressionBackend::Zstd)
+            .build(&feedback(&app_log, ""))
+            .unwrap();
+        assert!(bundle.size() <= 4000);
+        assert!(bundle.app_log.is_truncated());
+        let text = bundle.app_log.decode().unwrap();
+        assert!(text.starts_with("00000000\n"));
+        assert!(text.contains("bytes elided"));
+    }
+
+    #[cfg(not(feature = "zstd"))]
+    #[test]
+    fn zstd_needs_its_feature() {
+        assert!(FeedbackBundleBuilderBackend::new(10_000)
+            .with_compression(FeedbackCompressionBackend::Zstd)
+            .build(&feedback("log", ""))
+            .is_err());
+    }
+
+    #[test]
+    fn log_that_cannot_fit_compressed_is_dropped() {
+        let builder = |budget| {
+            FeedbackBundleBuilderBackend::new(budget)
+                .with_compression(FeedbackCompressionBackend::Gzip)
+        };
+        // Room for the JSON around the logs, not for a gzip header.
+        let fixed = FeedbackBundleBuilderBackend::new(10_000)
+            .build(&feedback("", ""))
+            .unwrap()
+            .size();
+        let bundle = builder(fixed + 5).build(&feedback("some log", "")).unwrap();
+        assert!(bundle.size() <= fixed + 5);
+        assert_eq!(bundle.app_log.compression, "none");
+        assert_eq!(bundle.app_log.content, "");
+        assert_eq!(bundle.app_log.elided_bytes, 8);
+    }
+
+    #[test]
+    fn rejects_budget_below_fixed_fields() {
+        assert!(FeedbackBundleBuilderBackend::new(4)
+            .build(&feedback("", ""))
+            .is_err());
+    }
+
+    #[test]
+    fn truncation_respects_char_boundaries() {
+        let text = "é".repeat(100);
+        for max in 0..text.len() {
+            let (cut, elided) = truncate_middle(&text, max);
+            assert!(cut.len() <= max, "{max}");
+            assert_eq!(
+                cut.replace(&elision_marker(elided as usize), "").len() + elided as usize,
+                text.len()
+            );
+        }
+    }
+
+    #[test]
+    fn decompression_stops_at_original_size() {
+        let mut log = FeedbackBundleBuilderBackend::new(100_000)
+            .with_compression(FeedbackCompressionBackend::Gzip)
+            .build(&feedback(&"a".repeat(50_000), ""))
+            .unwrap()
+            .app_log;
+        assert_eq!(log.decode().unwrap().len(), 50_000);
+        log.original_bytes = 100;
+        assert!(log.decode().is_err());
+    }
+
+    #[test]
+    fn unknown_compression_fails_to_decode() {
+        let log = FeedbackLogBackend {
+            compression: "brotli".to_string(),
+            content: String::new(),
+            original_bytes: 0,
+            elided_bytes: 0,
+        };
+        assert!(log.decode().is_err());
+    }
+}
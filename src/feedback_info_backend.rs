@@ -16,3 +16,11 @@ pub struct FeedbackInfoBackend {
     pub app_log: String,
     pub helper_log: String,
 }
+
+impl FeedbackInfoBackend {
+    /// Copy with PII and secrets replaced by placeholders, under the default
+    /// detectors -- see [`crate::feedback_scrub_backend`].
+    pub fn scrubbed(&self) -> (Self, crate::feedback_scrub_backend::ScrubReportBackend) {
+        crate::feedback_scrub_backend::ScrubberBackend::default().scrub_feedback(self)
+    }
+}
@@ -0,0 +1,52 @@
+//! Shell-style glob matching shared by the rule tables.
+//!
+//! Session rules and the LAN device classifier both let the Hub ship patterns
+//! as data; they match them the same way through [`glob_matches`].
+
+/// Glob match with `*` (any run, including empty) and `?` (one character).
+pub fn glob_matches(pattern: &str, value: &str, case_sensitive: bool) -> bool {
+    let fold = |s: &str| -> Vec<char> {
+        if case_sensitive {
+            s.chars().collect()
+        } else {
+            s.chars().flat_map(char::to_lowercase).collect()
+        }
+    };
+    let pattern = fold(pattern.trim());
+    let value = fold(value);
+    // Iterative matcher with single-star backtracking.
+    let (mut p, mut v) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while v < value.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == value[v]) {
+            p += 1;
+            v += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, v));
+            p += 1;
+        } else if let Some((star_p, star_v)) = star {
+            p = star_p + 1;
+            v = star_v + 1;
+            star = Some((star_p, star_v + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_semantics() {
+        assert!(glob_matches("*.google.com", "www.google.com", false));
+        assert!(!glob_matches("*.google.com", "google.com", false));
+        assert!(glob_matches("*", "", false));
+        assert!(glob_matches("a?c", "ABC", false));
+        assert!(!glob_matches("a?c", "ABC", true));
+        assert!(glob_matches("a*b*c", "axxbyyc", true));
+        assert!(!glob_matches("a*b*c", "axxbyy", true));
+    }
+}
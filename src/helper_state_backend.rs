@@ -9,3 +9,34 @@ pub enum HelperStateBackend {
     Fatal,
     Unsupported,
 }
+
+impl HelperStateBackend {
+    pub const ALL: [Self; 6] = [
+        Self::Disabled,
+        Self::Enabled,
+        Self::EnabledFullDisk,
+        Self::Outdated,
+        Self::Fatal,
+        Self::Unsupported,
+    ];
+
+    /// Serde spelling, as found in `FeedbackInfoBackend.helper_state`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Disabled => "Disabled",
+            Self::Enabled => "Enabled",
+            Self::EnabledFullDisk => "EnabledFullDisk",
+            Self::Outdated => "Outdated",
+            Self::Fatal => "Fatal",
+            Self::Unsupported => "Unsupported",
+        }
+    }
+
+    /// Case-insensitive.
+    pub fn from_str_opt(value: &str) -> Option<Self> {
+        let value = value.trim();
+        Self::ALL
+            .into_iter()
+            .find(|state| state.as_str().eq_ignore_ascii_case(value))
+    }
+}
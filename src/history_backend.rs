@@ -5,3 +5,11 @@ use serde::{Deserialize, Serialize};
 pub struct OrderHistoryBackend {
     pub history: Vec<MetricOrderResultBackend>,
 }
+
+impl OrderHistoryBackend {
+    /// Per-metric timeline of this history -- see
+    /// [`crate::order_timeline_backend`].
+    pub fn timeline(&self) -> crate::order_timeline_backend::OrderTimelineBackend {
+        crate::order_timeline_backend::build_order_timeline(self)
+    }
+}
@@ -0,0 +1,469 @@
+//! Banner fingerprinting for LAN ports.
+//!
+//! [`PortInfoBackend::banner`] is whatever the service greeted the scanner
+//! with: an SSH identification string, HTTP response headers, an FTP or SMTP
+//! greeting. [`fingerprint_banner`] reads the protocol, product, version and
+//! any OS hint out of it, and maps known products to a CPE 2.3 identifier so
+//! vulnerability matching does not have to parse free text.
+//!
+//! Known products come from a table; anything else falls back to a generic
+//! `product/version` reading with no CPE.
+//!
+//! [`PortInfoBackend::banner`]: crate::lanscan_port_info_backend::PortInfoBackend::banner
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum BannerProtocolBackend {
+    Ssh,
+    Http,
+    Ftp,
+    Smtp,
+    Redis,
+    Unknown,
+}
+
+impl BannerProtocolBackend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ssh => "ssh",
+            Self::Http => "http",
+            Self::Ftp => "ftp",
+            Self::Smtp => "smtp",
+            Self::Redis => "redis",
+            Self::Unknown => "unknown",
+        }
+    }
+
+    fn detect(banner: &str) -> Self {
+        let lower = banner.to_ascii_lowercase();
+        if lower.starts_with("ssh-") {
+            Self::Ssh
+        } else if lower.starts_with("http/") || lower.lines().any(|l| l.starts_with("server:")) {
+            Self::Http
+        } else if lower.contains("redis_version:") {
+            Self::Redis
+        } else if lower.starts_with("220") && lower.contains("smtp") {
+            Self::Smtp
+        } else if lower.starts_with("220") {
+            Self::Ftp
+        } else {
+            Self::Unknown
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BannerFingerprintBackend {
+    /// See [`BannerProtocolBackend`].
+    pub protocol: String,
+    /// Canonical lowercase product name, e.g. `openssh`, `apache`.
+    pub product: String,
+    pub version: Option<String>,
+    /// e.g. `ubuntu`, `windows`, `routeros`.
+    pub os: Option<String>,
+    /// CPE 2.3 for known products, version `*` when unknown.
+    pub cpe: Option<String>,
+}
+
+/// A known product: banner alias (lowercase), canonical name, CPE vendor and
+/// product (empty when there is none), and the OS the product implies.
+struct KnownProductBackend {
+    alias: &'static str,
+    product: &'static str,
+    cpe_vendor: &'static str,
+    cpe_product: &'static str,
+    os: Option<&'static str>,
+}
+
+const fn known(
+    alias: &'static str,
+    product: &'static str,
+    cpe_vendor: &'static str,
+    cpe_product: &'static str,
+    os: Option<&'static str>,
+) -> KnownProductBackend {
+    KnownProductBackend {
+        alias,
+        product,
+        cpe_vendor,
+        cpe_product,
+        os,
+    }
+}
+
+const KNOWN_PRODUCTS: &[KnownProductBackend] = &[
+    known("openssh", "openssh", "openbsd", "openssh", None),
+    known(
+        "dropbear",
+        "dropbear",
+        "dropbear_ssh_project",
+        "dropbear_ssh",
+        None,
+    ),
+    known(
+        "rosssh",
+        "routeros_ssh",
+        "mikrotik",
+        "routeros",
+        Some("routeros"),
+    ),
+    known("apache", "apache", "apache", "http_server", None),
+    known("nginx", "nginx", "nginx", "nginx", None),
+    known(
+        "microsoft-iis",
+        "iis",
+        "microsoft",
+        "internet_information_services",
+        Some("windows"),
+    ),
+    known("lighttpd", "lighttpd", "lighttpd", "lighttpd", None),
+    known("mini_httpd", "mini_httpd", "acme", "mini_httpd", None),
+    known("goahead-webs", "goahead", "embedthis", "goahead", None),
+    known("boa", "boa", "", "", None),
+    known("vsftpd", "vsftpd", "beasts", "vsftpd", None),
+    known("proftpd", "proftpd", "proftpd", "proftpd", None),
+    known("pure-ftpd", "pure-ftpd", "pureftpd", "pure-ftpd", None),
+    known(
+        "filezilla",
+        "filezilla_server",
+        "filezilla-project",
+        "filezilla_server",
+        None,
+    ),
+    known("postfix", "postfix", "postfix", "postfix", None),
+    known("exim", "exim", "exim", "exim", None),
+    known("sendmail", "sendmail", "sendmail", "sendmail", None),
+    known("redis", "redis", "redis", "redis", None),
+];
+
+/// Lowercase banner fragment → OS. Earlier entries win.
+const OS_HINTS: &[(&str, &str)] = &[
+    ("raspbian", "raspbian"),
+    ("ubuntu", "ubuntu"),
+    ("debian", "debian"),
+    ("centos", "centos"),
+    ("red hat", "rhel"),
+    ("fedora", "fedora"),
+    ("freebsd", "freebsd"),
+    ("win64", "windows"),
+    ("win32", "windows"),
+    ("windows", "windows"),
+    ("microsoft", "windows"),
+    ("mikrotik", "routeros"),
+    ("os:linux", "linux"),
+];
+
+/// Protocol names that precede a protocol version, `HTTP/1.1`.
+const PROTOCOL_TOKENS: &[&str] = &["http", "https", "ssh", "rtsp", "sip"];
+
+/// Leading version characters, `8.9p1 Ubuntu` → `8.9p1`; `None` unless it
+/// starts with a digit and has a dot.
+fn read_version(text: &str) -> Option<String> {
+    let text = text.trim_start_matches(['v', 'V']);
+    let version: String = text
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '.')
+        .collect();
+    (version.starts_with(|c: char| c.is_ascii_digit()) && version.contains('.')).then_some(version)
+}
+
+fn tokenize(banner: &str) -> Vec<&str> {
+    // `SSH-2.0-OpenSSH_8.9p1`: the software starts after the second dash.
+    let banner = match banner.strip_prefix("SSH-") {
+        Some(rest) => rest.split_once('-').map_or(rest, |(_, software)| software),
+        None => banner,
+    };
+    banner
+        .split(|c: char| c.is_whitespace() || matches!(c, '(' | ')' | ',' | ';' | '[' | ']'))
+        .filter(|token| !token.is_empty())
+        .collect()
+}
+
+/// A known product in `tokens`, with the version that follows it.
+fn find_known(tokens: &[&str]) -> Option<(&'static KnownProductBackend, Option<String>)> {
+    for (index, token) in tokens.iter().enumerate() {
+        let lower = token.to_ascii_lowercase();
+        for product in KNOWN_PRODUCTS {
+            let Some(rest) = lower.strip_prefix(product.alias) else {
+                continue;
+            };
+            let separated = rest.is_empty() || rest.starts_with(['/', '_', ':']);
+            if !separated {
+                continue;
+            }
+            let rest = rest.trim_start_matches(['/', '_', ':']);
+            let rest = rest
+                .strip_prefix("version")
+                .unwrap_or(rest)
+                .trim_start_matches(['/', '_', ':']);
+            // `FileZilla Server 0.9.60`: allow one word before the version.
+            let version = read_version(rest)
+                .or_else(|| tokens.get(index + 1).and_then(|next| read_version(next)))
+                .or_else(|| tokens.get(index + 2).and_then(|next| read_version(next)));
+            return Some((product, version));
+        }
+    }
+    None
+}
+
+/// `product/version`, `product_version` or `product version`, for products
+/// not in the table.
+fn find_generic(tokens: &[&str]) -> Option<(String, String)> {
+    let joined = tokens.iter().filter_map(|token| {
+        token
+            .split_once('/')
+            .or_else(|| token.split_once('_'))
+            .or_else(|| token.split_once(':'))
+    });
+    let adjacent = tokens.windows(2).map(|pair| (pair[0], pair[1]));
+    joined.chain(adjacent).find_map(|(product, version)| {
+        let product = product.to_ascii_lowercase();
+        let usable = product.chars().any(|c| c.is_ascii_alphabetic())
+            && !product.ends_with(':')
+            && !PROTOCOL_TOKENS.contains(&product.as_str());
+        usable.then(|| read_version(version).map(|version| (product, version)))?
+    })
+}
+
+fn os_hint(banner: &str) -> Option<String> {
+    let lower = banner.to_ascii_lowercase();
+    OS_HINTS
+        .iter()
+        .find(|(fragment, _)| lower.contains(fragment))
+        .map(|(_, os)| os.to_string())
+}
+
+/// `cpe:2.3:a:<vendor>:<product>:<version>:*:*:*:*:*:*:*`.
+pub fn application_cpe(vendor: &str, product: &str, version: Option<&str>) -> String {
+    format!(
+        "cpe:2.3:a:{vendor}:{product}:{}:*:*:*:*:*:*:*",
+        version.unwrap_or("*")
+    )
+}
+
+/// `None` when the banner names no product.
+pub fn fingerprint_banner(banner: &str) -> Option<BannerFingerprintBackend> {
+    let banner = banner.trim();
+    let protocol = BannerProtocolBackend::detect(banner);
+    // HTTP: only the Server header names the software.
+    let relevant = match protocol {
+        BannerProtocolBackend::Http => banner
+            .lines()
+            .find_map(|line| {
+                line.split_once(':')
+                    .filter(|(name, _)| name.trim().eq_ignore_ascii_case("server"))
+                    .map(|(_, value)| value)
+            })
+            .unwrap_or(""),
+        // `220-FileZilla Server`: drop the reply code.
+        BannerProtocolBackend::Ftp | BannerProtocolBackend::Smtp => banner
+            .trim_start_matches(|c: char| c.is_ascii_digit())
+            .trim_start_matches('-'),
+        _ => banner,
+    };
+    let tokens = tokenize(relevant);
+
+    let (product, version, os, cpe) = if let Some((known, version)) = find_known(&tokens) {
+        let cpe = (!known.cpe_vendor.is_empty())
+            .then(|| application_cpe(known.cpe_vendor, known.cpe_product, version.as_deref()));
+        let os = known.os.map(str::to_string).or_else(|| os_hint(banner));
+        (known.product.to_string(), version, os, cpe)
+    } else {
+        let (product, version) = find_generic(&tokens)?;
+        (product, Some(version), os_hint(banner), None)
+    };
+    Some(BannerFingerprintBackend {
+        protocol: protocol.as_str().to_string(),
+        product,
+        version,
+        os,
+        cpe,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// banner, protocol, product, version, os, cpe
+    type Sample = (
+        &'static str,
+        &'static str,
+        &'static str,
+        Option<&'static str>,
+        Option<&'static str>,
+        Option<&'static str>,
+    );
+
+    const CORPUS: &[Sample] = &[
+        (
+            "SSH-2.0-OpenSSH_8.9p1 Ubuntu-3ubuntu0.6",
+            "ssh",
+            "openssh",
+            Some("8.9p1"),
+            Some("ubuntu"),
+            Some("cpe:2.3:a:openbsd:openssh:8.9p1:*:*:*:*:*:*:*"),
+        ),
+        (
+            "SSH-2.0-OpenSSH_7.9p1 Raspbian-10+deb10u2",
+            "ssh",
+            "openssh",
+            Some("7.9p1"),
+            Some("raspbian"),
+            Some("cpe:2.3:a:openbsd:openssh:7.9p1:*:*:*:*:*:*:*"),
+        ),
+        (
+            "SSH-2.0-dropbear_2020.81",
+            "ssh",
+            "dropbear",
+            Some("2020.81"),
+            None,
+            Some("cpe:2.3:a:dropbear_ssh_project:dropbear_ssh:2020.81:*:*:*:*:*:*:*"),
+        ),
+        (
+            "SSH-2.0-ROSSSH",
+            "ssh",
+            "routeros_ssh",
+            None,
+            Some("routeros"),
+            Some("cpe:2.3:a:mikrotik:routeros:*:*:*:*:*:*:*:*"),
+        ),
+        (
+            "HTTP/1.1 200 OK\r\nDate: Mon, 01 Jan 2026 00:00:00 GMT\r\nServer: Apache/2.4.41 (Ubuntu)\r\n",
+            "http",
+            "apache",
+            Some("2.4.41"),
+            Some("ubuntu"),
+            Some("cpe:2.3:a:apache:http_server:2.4.41:*:*:*:*:*:*:*"),
+        ),
+        (
+            "HTTP/1.1 404 Not Found\r\nServer: nginx/1.18.0\r\n",
+            "http",
+            "nginx",
+            Some("1.18.0"),
+            None,
+            Some("cpe:2.3:a:nginx:nginx:1.18.0:*:*:*:*:*:*:*"),
+        ),
+        (
+            "HTTP/1.1 200 OK\r\nServer: Microsoft-IIS/10.0\r\n",
+            "http",
+            "iis",
+            Some("10.0"),
+            Some("windows"),
+            Some("cpe:2.3:a:microsoft:internet_information_services:10.0:*:*:*:*:*:*:*"),
+        ),
+        (
+            "HTTP/1.0 200 OK\r\nServer: lighttpd/1.4.59\r\n",
+            "http",
+            "lighttpd",
+            Some("1.4.59"),
+            None,
+            Some("cpe:2.3:a:lighttpd:lighttpd:1.4.59:*:*:*:*:*:*:*"),
+        ),
+        (
+            "HTTP/1.0 200 OK\r\nServer: Boa/0.94.14rc21\r\n",
+            "http",
+            "boa",
+            Some("0.94.14rc21"),
+            None,
+            None,
+        ),
+        (
+            "HTTP/1.1 200 OK\r\nServer: CherryPy/18.8.0\r\n",
+            "http",
+            "cherrypy",
+            Some("18.8.0"),
+            None,
+            None,
+        ),
+        (
+            "220 (vsFTPd 3.0.3)",
+            "ftp",
+            "vsftpd",
+            Some("3.0.3"),
+            None,
+            Some("cpe:2.3:a:beasts:vsftpd:3.0.3:*:*:*:*:*:*:*"),
+        ),
+        (
+            "220 ProFTPD 1.3.5e Server (Debian) [::ffff:192.168.1.2]",
+            "ftp",
+            "proftpd",
+            Some("1.3.5e"),
+            Some("debian"),
+            Some("cpe:2.3:a:proftpd:proftpd:1.3.5e:*:*:*:*:*:*:*"),
+        ),
+        (
+            "220-FileZilla Server 0.9.60 beta",
+            "ftp",
+            "filezilla_server",
+            Some("0.9.60"),
+            None,
+            Some("cpe:2.3:a:filezilla-project:filezilla_server:0.9.60:*:*:*:*:*:*:*"),
+        ),
+        (
+            "220 mail.example.com ESMTP Postfix (Ubuntu)",
+            "smtp",
+            "postfix",
+            None,
+            Some("ubuntu"),
+            Some("cpe:2.3:a:postfix:postfix:*:*:*:*:*:*:*:*"),
+        ),
+        (
+            "220 mx.example.com ESMTP Exim 4.94.2 Mon, 01 Jan 2026 00:00:00 +0000",
+            "smtp",
+            "exim",
+            Some("4.94.2"),
+            None,
+            Some("cpe:2.3:a:exim:exim:4.94.2:*:*:*:*:*:*:*"),
+        ),
+        (
+            "# Server\r\nredis_version:6.0.16\r\nredis_mode:standalone\r\nos:Linux 5.15.0-91-generic x86_64\r\n",
+            "redis",
+            "redis",
+            Some("6.0.16"),
+            Some("linux"),
+            Some("cpe:2.3:a:redis:redis:6.0.16:*:*:*:*:*:*:*"),
+        ),
+    ];
+
+    #[test]
+    fn fingerprints_banner_corpus() {
+        for (banner, protocol, product, version, os, cpe) in CORPUS {
+            let fingerprint = fingerprint_banner(banner).unwrap_or_else(|| panic!("{banner}"));
+            assert_eq!(
+                fingerprint,
+                BannerFingerprintBackend {
+                    protocol: protocol.to_string(),
+                    product: product.to_string(),
+                    version: version.map(str::to_string),
+                    os: os.map(str::to_string),
+                    cpe: cpe.map(str::to_string),
+                },
+                "{banner}"
+            );
+        }
+    }
+
+    #[test]
+    fn banners_without_a_product_yield_nothing() {
+        for banner in [
+            "",
+            "   ",
+            "HTTP/1.1 200 OK",
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\n",
+            "hello world",
+            "220 Welcome",
+        ] {
+            assert_eq!(fingerprint_banner(banner), None, "{banner:?}");
+        }
+    }
+
+    #[test]
+    fn products_need_a_separator() {
+        // `boat/1.0` is not `boa`.
+        let fingerprint = fingerprint_banner("Server: boat/1.0").unwrap();
+        assert_eq!(fingerprint.product, "boat");
+        assert_eq!(fingerprint.cpe, None);
+    }
+}
@@ -0,0 +1,788 @@
+//! Rule-based LAN device classification.
+//!
+//! A scan yields mDNS services, a MAC vendor, sometimes a hostname and a list
+//! of open ports, but no device type: today only a user filing a
+//! [`DislikeDeviceInfoBackend`] says what a device is. [`DeviceClassRulesBackend`]
+//! infers it from a table of weighted signals. Every rule that matches adds its
+//! weight to its device type; the type with the highest total wins, and the
+//! rules that voted for it are returned as evidence.
+//!
+//! The table is data: it serializes as-is so the Hub can ship an updated one
+//! without a release. [`evaluate_corpus`] replays user-labelled dislike
+//! reports against a table, so a table change that regresses known devices
+//! shows up before it ships.
+
+use crate::glob_backend::glob_matches;
+use crate::lanscan_device_info_backend::DeviceInfoBackend;
+use crate::lanscan_dislike_device_info_backend::DislikeDeviceInfoBackend;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DeviceTypeBackend {
+    Router,
+    Printer,
+    Camera,
+    Nas,
+    /// Phones and tablets.
+    Phone,
+    Computer,
+    /// Smart TVs and streaming sticks.
+    Tv,
+    Speaker,
+    GameConsole,
+    /// Plugs, bulbs, sensors, hubs.
+    Iot,
+    Unknown,
+}
+
+impl DeviceTypeBackend {
+    pub const ALL: [Self; 11] = [
+        Self::Router,
+        Self::Printer,
+        Self::Camera,
+        Self::Nas,
+        Self::Phone,
+        Self::Computer,
+        Self::Tv,
+        Self::Speaker,
+        Self::GameConsole,
+        Self::Iot,
+        Self::Unknown,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Router => "router",
+            Self::Printer => "printer",
+            Self::Camera => "camera",
+            Self::Nas => "nas",
+            Self::Phone => "phone",
+            Self::Computer => "computer",
+            Self::Tv => "tv",
+            Self::Speaker => "speaker",
+            Self::GameConsole => "game_console",
+            Self::Iot => "iot",
+            Self::Unknown => "unknown",
+        }
+    }
+
+    pub fn from_str_opt(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.as_str() == value)
+    }
+
+    /// Map a free-form, user-supplied label ("IP camera", "Smart-Plug").
+    /// `None` when the label says nothing usable.
+    pub fn from_label(label: &str) -> Option<Self> {
+        let label = label
+            .trim()
+            .to_lowercase()
+            .replace(['_', '-'], " ")
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        if let Some(device_type) = Self::from_str_opt(&label.replace(' ', "_")) {
+            return (device_type != Self::Unknown).then_some(device_type);
+        }
+        DEVICE_TYPE_LABELS
+            .iter()
+            .find(|(known, _)| *known == label)
+            .map(|(_, device_type)| *device_type)
+    }
+}
+
+/// User-facing spellings, normalized to lowercase and single spaces.
+const DEVICE_TYPE_LABELS: &[(&str, DeviceTypeBackend)] = {
+    use DeviceTypeBackend::*;
+    &[
+        ("gateway", Router),
+        ("modem", Router),
+        ("access point", Router),
+        ("mesh node", Router),
+        ("wifi extender", Router),
+        ("scanner", Printer),
+        ("multifunction printer", Printer),
+        ("ip camera", Camera),
+        ("webcam", Camera),
+        ("security camera", Camera),
+        ("doorbell", Camera),
+        ("storage", Nas),
+        ("network storage", Nas),
+        ("smartphone", Phone),
+        ("mobile", Phone),
+        ("tablet", Phone),
+        ("laptop", Computer),
+        ("desktop", Computer),
+        ("pc", Computer),
+        ("mac", Computer),
+        ("server", Computer),
+        ("television", Tv),
+        ("smart tv", Tv),
+        ("media player", Tv),
+        ("streaming device", Tv),
+        ("smart speaker", Speaker),
+        ("console", GameConsole),
+        ("smart plug", Iot),
+        ("smart home", Iot),
+        ("light bulb", Iot),
+        ("thermostat", Iot),
+        ("sensor", Iot),
+    ]
+};
+
+/// Which device attribute a rule inspects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DeviceSignalKindBackend {
+    /// One of `mdns_services`.
+    Mdns,
+    /// `device_vendor`.
+    Vendor,
+    /// `hostname`.
+    Hostname,
+    /// One open port, as its decimal string.
+    Port,
+}
+
+impl DeviceSignalKindBackend {
+    pub const ALL: [Self; 4] = [Self::Mdns, Self::Vendor, Self::Hostname, Self::Port];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Mdns => "mdns",
+            Self::Vendor => "vendor",
+            Self::Hostname => "hostname",
+            Self::Port => "port",
+        }
+    }
+
+    pub fn from_str_opt(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.as_str() == value)
+    }
+}
+
+/// One weighted signal: "`signal` matching `pattern` suggests `device_type`".
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DeviceClassRuleBackend {
+    /// See [`DeviceTypeBackend`].
+    pub device_type: String,
+    /// See [`DeviceSignalKindBackend`].
+    pub signal: String,
+    /// Case-insensitive glob (`*`, `?`), see [`glob_matches`].
+    pub pattern: String,
+    pub weight: u32,
+}
+
+impl DeviceClassRuleBackend {
+    pub fn new(
+        device_type: DeviceTypeBackend,
+        signal: DeviceSignalKindBackend,
+        pattern: impl Into<String>,
+        weight: u32,
+    ) -> Self {
+        Self {
+            device_type: device_type.as_str().to_string(),
+            signal: signal.as_str().to_string(),
+            pattern: pattern.into(),
+            weight,
+        }
+    }
+
+    /// First value of `signals` the rule matches. A rule with an unknown
+    /// signal kind never matches.
+    pub fn matched_value<'a>(&self, signals: &'a DeviceSignalsBackend) -> Option<&'a str> {
+        let mut values: Box<dyn Iterator<Item = &'a str>> =
+            match DeviceSignalKindBackend::from_str_opt(&self.signal)? {
+                DeviceSignalKindBackend::Mdns => {
+                    Box::new(signals.mdns_services.iter().map(String::as_str))
+                }
+                DeviceSignalKindBackend::Vendor => {
+                    Box::new(std::iter::once(signals.device_vendor.as_str()))
+                }
+                DeviceSignalKindBackend::Hostname => {
+                    Box::new(std::iter::once(signals.hostname.as_str()))
+                }
+                DeviceSignalKindBackend::Port => {
+                    Box::new(signals.open_ports.iter().map(String::as_str))
+                }
+            };
+        values.find(|value| !value.trim().is_empty() && glob_matches(&self.pattern, value, false))
+    }
+}
+
+/// What a classifier looks at, shared by scan results and dislike reports.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeviceSignalsBackend {
+    pub mdns_services: Vec<String>,
+    pub device_vendor: String,
+    pub hostname: String,
+    /// Open ports as decimal strings.
+    pub open_ports: Vec<String>,
+}
+
+impl From<&DeviceInfoBackend> for DeviceSignalsBackend {
+    fn from(device: &DeviceInfoBackend) -> Self {
+        Self {
+            mdns_services: device.mdns_services.clone(),
+            device_vendor: device.device_vendor.clone(),
+            hostname: String::new(),
+            open_ports: device
+                .open_ports
+                .iter()
+                .map(|port| port.port.to_string())
+                .collect(),
+        }
+    }
+}
+
+impl From<&DislikeDeviceInfoBackend> for DeviceSignalsBackend {
+    fn from(device: &DislikeDeviceInfoBackend) -> Self {
+        Self {
+            mdns_services: device.mdns_services.clone(),
+            device_vendor: device.device_vendor.clone(),
+            hostname: device.hostname.clone(),
+            open_ports: device
+                .open_ports
+                .iter()
+                .map(|port| port.port.to_string())
+                .collect(),
+        }
+    }
+}
+
+/// A rule that voted for the winning type, and the value it matched.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DeviceClassEvidenceBackend {
+    pub signal: String,
+    pub pattern: String,
+    pub value: String,
+    pub weight: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DeviceClassificationBackend {
+    /// See [`DeviceTypeBackend`]; `unknown` when no type reached the minimum
+    /// score.
+    pub device_type: String,
+    /// 0.0 to 1.0: the winner's share of all matched weight, scaled down while
+    /// its own total is below [`FULL_CONFIDENCE_SCORE`]. 0.0 when every
+    /// matched weight is 0.
+    pub confidence: f64,
+    /// Summed weight of the winning type.
+    pub score: u32,
+    /// Heaviest first.
+    pub evidence: Vec<DeviceClassEvidenceBackend>,
+}
+
+/// Total weight at which a unanimous classification is fully confident.
+pub const FULL_CONFIDENCE_SCORE: u32 = 100;
+
+/// Below this total weight, the device stays `unknown`.
+pub const DEFAULT_MIN_DEVICE_CLASS_SCORE: u32 = 30;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DeviceClassRulesBackend {
+    pub rules: Vec<DeviceClassRuleBackend>,
+    pub min_score: u32,
+}
+
+impl Default for DeviceClassRulesBackend {
+    fn default() -> Self {
+        Self {
+            rules: DEFAULT_DEVICE_CLASS_RULES
+                .iter()
+                .map(|(device_type, signal, pattern, weight)| {
+                    DeviceClassRuleBackend::new(*device_type, *signal, *pattern, *weight)
+                })
+                .collect(),
+            min_score: DEFAULT_MIN_DEVICE_CLASS_SCORE,
+        }
+    }
+}
+
+impl DeviceClassRulesBackend {
+    pub fn classify(&self, signals: &DeviceSignalsBackend) -> DeviceClassificationBackend {
+        // (type, total, evidence), in first-seen order; a handful of entries.
+        let mut totals: Vec<(DeviceTypeBackend, u32, Vec<DeviceClassEvidenceBackend>)> = Vec::new();
+        for rule in &self.rules {
+            let Some(device_type) = DeviceTypeBackend::from_str_opt(&rule.device_type) else {
+                continue;
+            };
+            let Some(value) = rule.matched_value(signals) else {
+                continue;
+            };
+            let evidence = DeviceClassEvidenceBackend {
+                signal: rule.signal.clone(),
+                pattern: rule.pattern.clone(),
+                value: value.to_string(),
+                weight: rule.weight,
+            };
+            match totals.iter_mut().find(|(t, _, _)| *t == device_type) {
+                Some((_, total, found)) => {
+                    *total = total.saturating_add(rule.weight);
+                    found.push(evidence);
+                }
+                None => totals.push((device_type, rule.weight, vec![evidence])),
+            }
+        }
+
+        let all: u64 = totals.iter().map(|(_, total, _)| u64::from(*total)).sum();
+        // Highest total wins; ties go to the type declared first in the enum.
+        let best = totals
+            .into_iter()
+            .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0)));
+        match best {
+            Some((device_type, score, mut evidence)) if score >= self.min_score => {
+                evidence.sort_by(|a, b| b.weight.cmp(&a.weight).then(a.cmp(b)));
+                // Zero-weight rules can win a zero minimum with nothing to share.
+                let share = if all == 0 {
+                    0.0
+                } else {
+                    score as f64 / all as f64
+                };
+                let saturation = (score as f64 / FULL_CONFIDENCE_SCORE as f64).min(1.0);
+                DeviceClassificationBackend {
+                    device_type: device_type.as_str().to_string(),
+                    confidence: share * saturation,
+                    score,
+                    evidence,
+                }
+            }
+            _ => DeviceClassificationBackend {
+                device_type: DeviceTypeBackend::Unknown.as_str().to_string(),
+                confidence: 0.0,
+                score: 0,
+                evidence: Vec::new(),
+            },
+        }
+    }
+
+    pub fn classify_device(&self, device: &DeviceInfoBackend) -> DeviceClassificationBackend {
+        self.classify(&device.into())
+    }
+}
+
+/// One labelled report the rules got wrong.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DeviceClassMismatchBackend {
+    /// Position in the corpus.
+    pub index: usize,
+    pub expected: String,
+    pub predicted: String,
+    pub confidence: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct DeviceClassCorpusReportBackend {
+    /// Reports whose `device_type` mapped to a known type.
+    pub evaluated: usize,
+    /// Reports with an empty or unrecognised `device_type`.
+    pub skipped: usize,
+    pub correct: usize,
+    pub mismatches: Vec<DeviceClassMismatchBackend>,
+}
+
+impl DeviceClassCorpusReportBackend {
+    /// `None` when nothing was evaluated.
+    pub fn accuracy(&self) -> Option<f64> {
+        (self.evaluated > 0).then(|| self.correct as f64 / self.evaluated as f64)
+    }
+}
+
+/// Replay user-labelled dislike reports against `rules`.
+pub fn evaluate_corpus(
+    rules: &DeviceClassRulesBackend,
+    reports: &[DislikeDeviceInfoBackend],
+) -> DeviceClassCorpusReportBackend {
+    let mut report = DeviceClassCorpusReportBackend::default();
+    for (index, dislike) in reports.iter().enumerate() {
+        let Some(expected) = DeviceTypeBackend::from_label(&dislike.device_type) else {
+            report.skipped += 1;
+            continue;
+        };
+        report.evaluated += 1;
+        let classification = rules.classify(&dislike.into());
+        if classification.device_type == expected.as_str() {
+            report.correct += 1;
+        } else {
+            report.mismatches.push(DeviceClassMismatchBackend {
+                index,
+                expected: expected.as_str().to_string(),
+                predicted: classification.device_type,
+                confidence: classification.confidence,
+            });
+        }
+    }
+    report
+}
+
+/// Built-in table. Weights are tuned so one strong signal (a printer-only
+/// mDNS service) classifies alone, while weak ones (a vendor that also makes
+/// laptops) need corroboration.
+const DEFAULT_DEVICE_CLASS_RULES: &[(DeviceTypeBackend, DeviceSignalKindBackend, &str, u32)] = {
+    use DeviceSignalKindBackend::*;
+    use DeviceTypeBackend::*;
+    &[
+        // mDNS service types.
+        (Printer, Mdns, "_ipp._tcp*", 90),
+        (Printer, Mdns, "_ipps._tcp*", 90),
+        (Printer, Mdns, "_printer._tcp*", 90),
+        (Printer, Mdns, "_pdl-datastream._tcp*", 90),
+        (Printer, Mdns, "_uscan._tcp*", 60),
+        (Camera, Mdns, "_axis-video._tcp*", 90),
+        (Camera, Mdns, "_rtsp._tcp*", 50),
+        (Nas, Mdns, "_adisk._tcp*", 70),
+        (Nas, Mdns, "_afpovertcp._tcp*", 40),
+        (Nas, Mdns, "_nfs._tcp*", 40),
+        (Nas, Mdns, "_smb._tcp*", 20),
+        (Computer, Mdns, "_smb._tcp*", 15),
+        (Computer, Mdns, "_rfb._tcp*", 40),
+        (Computer, Mdns, "_workstation._tcp*", 40),
+        (Computer, Mdns, "_ssh._tcp*", 20),
+        (Phone, Mdns, "_apple-mobdev2._tcp*", 80),
+        (Tv, Mdns, "_googlecast._tcp*", 60),
+        (Tv, Mdns, "_airplay._tcp*", 40),
+        (Speaker, Mdns, "_sonos._tcp*", 90),
+        (Speaker, Mdns, "_spotify-connect._tcp*", 40),
+        (Speaker, Mdns, "_raop._tcp*", 30),
+        (Iot, Mdns, "_hap._tcp*", 70),
+        (Iot, Mdns, "_hue._tcp*", 90),
+        (Iot, Mdns, "_matter._tcp*", 70),
+        (Iot, Mdns, "_matterc._udp*", 70),
+        (Iot, Mdns, "_shelly._tcp*", 90),
+        // MAC vendors.
+        (Printer, Vendor, "*brother*", 60),
+        (Printer, Vendor, "*seiko epson*", 60),
+        (Printer, Vendor, "*lexmark*", 70),
+        (Printer, Vendor, "*xerox*", 70),
+        (Printer, Vendor, "*canon*", 40),
+        (Camera, Vendor, "*hikvision*", 80),
+        (Camera, Vendor, "*dahua*", 80),
+        (Camera, Vendor, "*axis communications*", 80),
+        (Camera, Vendor, "*reolink*", 80),
+        (Nas, Vendor, "*synology*", 90),
+        (Nas, Vendor, "*qnap*", 90),
+        (Router, Vendor, "*mikrotik*", 80),
+        (Router, Vendor, "*ubiquiti*", 60),
+        (Router, Vendor, "*netgear*", 50),
+        (Router, Vendor, "*linksys*", 60),
+        (Router, Vendor, "*sagemcom*", 70),
+        (Router, Vendor, "*technicolor*", 60),
+        (Router, Vendor, "*arris*", 60),
+        (Router, Vendor, "avm*", 70),
+        (Router, Vendor, "*tp-link*", 30),
+        (Phone, Vendor, "apple*", 15),
+        (Computer, Vendor, "apple*", 15),
+        (Phone, Vendor, "samsung*", 20),
+        (Tv, Vendor, "samsung*", 15),
+        (Tv, Vendor, "*roku*", 90),
+        (Speaker, Vendor, "*sonos*", 90),
+        (Speaker, Vendor, "amazon technologies*", 30),
+        (GameConsole, Vendor, "*nintendo*", 90),
+        (GameConsole, Vendor, "*sony interactive*", 90),
+        (Iot, Vendor, "*espressif*", 70),
+        (Iot, Vendor, "*tuya*", 80),
+        (Iot, Vendor, "*shelly*", 80),
+        (Iot, Vendor, "*allterco*", 80),
+        // Hostnames.
+        (Printer, Hostname, "*printer*", 60),
+        (Printer, Hostname, "*laserjet*", 80),
+        (Printer, Hostname, "*officejet*", 80),
+        (Printer, Hostname, "brn*", 50),
+        (Printer, Hostname, "epson*", 60),
+        (Camera, Hostname, "*camera*", 60),
+        (Camera, Hostname, "*ipcam*", 70),
+        (Nas, Hostname, "*diskstation*", 90),
+        (Nas, Hostname, "*nas*", 40),
+        (Router, Hostname, "*router*", 70),
+        (Router, Hostname, "*gateway*", 50),
+        (Router, Hostname, "fritz.box", 90),
+        (Router, Hostname, "*openwrt*", 80),
+        (Phone, Hostname, "*iphone*", 90),
+        (Phone, Hostname, "*ipad*", 90),
+        (Phone, Hostname, "android*", 70),
+        (Phone, Hostname, "*galaxy*", 60),
+        (Phone, Hostname, "*pixel*", 60),
+        (Computer, Hostname, "*macbook*", 80),
+        (Computer, Hostname, "*imac*", 80),
+        (Computer, Hostname, "desktop-*", 80),
+        (Computer, Hostname, "laptop-*", 80),
+        (Tv, Hostname, "*chromecast*", 80),
+        (Tv, Hostname, "*apple-tv*", 80),
+        (Tv, Hostname, "*roku*", 80),
+        (Speaker, Hostname, "*sonos*", 70),
+        (Speaker, Hostname, "*homepod*", 80),
+        (Speaker, Hostname, "*echo*", 50),
+        (GameConsole, Hostname, "*xbox*", 80),
+        (GameConsole, Hostname, "*playstation*", 80),
+        (GameConsole, Hostname, "ps5*", 70),
+        (Iot, Hostname, "esp_*", 70),
+        (Iot, Hostname, "esp32*", 70),
+        (Iot, Hostname, "*shelly*", 80),
+        (Iot, Hostname, "*tasmota*", 80),
+        // Open ports.
+        (Printer, Port, "9100", 50),
+        (Printer, Port, "631", 40),
+        (Printer, Port, "515", 40),
+        (Camera, Port, "554", 50),
+        (Camera, Port, "8554", 40),
+        (Camera, Port, "37777", 70),
+        (Nas, Port, "5000", 25),
+        (Nas, Port, "5001", 25),
+        (Nas, Port, "548", 25),
+        (Nas, Port, "2049", 30),
+        (Nas, Port, "445", 15),
+        (Computer, Port, "445", 15),
+        (Computer, Port, "3389", 60),
+        (Computer, Port, "5900", 30),
+        (Computer, Port, "22", 10),
+        (Router, Port, "53", 40),
+        (Router, Port, "67", 30),
+        (Router, Port, "7547", 70),
+        (Router, Port, "1900", 15),
+        (Phone, Port, "62078", 90),
+        (Tv, Port, "8008", 40),
+        (Tv, Port, "8009", 40),
+        (Tv, Port, "8060", 70),
+        (Speaker, Port, "1400", 70),
+        (Iot, Port, "1883", 40),
+        (Iot, Port, "8883", 40),
+        (Iot, Port, "6668", 60),
+    ]
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lanscan_port_info_backend::PortInfoBackend;
+
+    fn port(port: u16) -> PortInfoBackend {
+        PortInfoBackend {
+            port,
+            protocol: "tcp".to_string(),
+            service: String::new(),
+            banner: String::new(),
+            vulnerabilities: Vec::new(),
+        }
+    }
+
+    fn dislike(
+        device_type: &str,
+        mdns: &[&str],
+        vendor: &str,
+        hostname: &str,
+        ports: &[u16],
+    ) -> DislikeDeviceInfoBackend {
+        DislikeDeviceInfoBackend {
+            device_type: device_type.to_string(),
+            open_ports: ports.iter().copied().map(port).collect(),
+            mdns_services: mdns.iter().map(|s| s.to_string()).collect(),
+            device_vendor: vendor.to_string(),
+            hostname: hostname.to_string(),
+            note: String::new(),
+        }
+    }
+
+    /// User-labelled reports the default table must keep getting right.
+    fn corpus() -> Vec<DislikeDeviceInfoBackend> {
+        vec![
+            dislike(
+                "Printer",
+                &["_ipp._tcp.local", "_uscan._tcp.local"],
+                "Hewlett Packard",
+                "HP-OfficeJet-Pro",
+                &[80, 631, 9100],
+            ),
+            dislike("IP camera", &[], "Hangzhou Hikvision", "", &[80, 554, 8000]),
+            dislike(
+                "NAS",
+                &["_smb._tcp.local", "_adisk._tcp.local"],
+                "Synology Incorporated",
+                "DiskStation",
+                &[445, 5000, 5001],
+            ),
+            dislike(
+                "router",
+                &[],
+                "Sagemcom Broadband SAS",
+                "livebox",
+                &[53, 80, 443, 7547],
+            ),
+            dislike(
+                "smartphone",
+                &["_apple-mobdev2._tcp.local"],
+                "Apple, Inc.",
+                "Alices-iPhone",
+                &[62078],
+            ),
+            dislike(
+                "laptop",
+                &["_smb._tcp.local", "_ssh._tcp.local"],
+                "Apple, Inc.",
+                "alices-macbook-pro",
+                &[22, 445],
+            ),
+            dislike(
+                "tv",
+                &["_googlecast._tcp.local"],
+                "Google, Inc.",
+                "Chromecast-Living",
+                &[8008, 8009],
+            ),
+            dislike(
+                "speaker",
+                &["_sonos._tcp.local", "_spotify-connect._tcp.local"],
+                "Sonos, Inc.",
+                "",
+                &[1400],
+            ),
+            dislike("game-console", &[], "Nintendo Co.,Ltd", "", &[]),
+            dislike(
+                "smart plug",
+                &["_hap._tcp.local"],
+                "Espressif Inc.",
+                "shelly-plug-s",
+                &[80],
+            ),
+            dislike("", &[], "Unknown", "", &[]),
+        ]
+    }
+
+    #[test]
+    fn default_rules_pass_the_regression_corpus() {
+        let report = evaluate_corpus(&DeviceClassRulesBackend::default(), &corpus());
+        assert_eq!(report.mismatches, vec![]);
+        assert_eq!(report.evaluated, 10);
+        assert_eq!(report.skipped, 1);
+        assert_eq!(report.accuracy(), Some(1.0));
+    }
+
+    #[test]
+    fn reports_evidence_and_confidence() {
+        let rules = DeviceClassRulesBackend::default();
+        let strong = rules.classify(&(&corpus()[0]).into());
+        assert_eq!(strong.device_type, "printer");
+        assert!(strong.confidence > 0.99);
+        assert_eq!(strong.evidence[0].weight, 90);
+        assert_eq!(strong.evidence[0].value, "_ipp._tcp.local");
+        assert!(strong
+            .evidence
+            .iter()
+            .any(|e| e.signal == "port" && e.value == "9100"));
+
+        // Apple vendor plus SSH: weak and contested.
+        let weak = rules.classify(&(&dislike("", &[], "Apple, Inc.", "", &[22, 445])).into());
+        assert_eq!(weak.device_type, "computer");
+        assert!(weak.confidence < 0.5);
+    }
+
+    #[test]
+    fn nothing_matched_is_unknown() {
+        let rules = DeviceClassRulesBackend::default();
+        let unknown = rules.classify(&DeviceSignalsBackend::default());
+        assert_eq!(unknown.device_type, "unknown");
+        assert_eq!(unknown.confidence, 0.0);
+        assert!(unknown.evidence.is_empty());
+
+        let below_min = rules.classify(&(&dislike("", &[], "", "", &[1900])).into());
+        assert_eq!(below_min.device_type, "unknown");
+    }
+
+    #[test]
+    fn classifies_scan_results() {
+        let device = DeviceInfoBackend {
+            mdns_services: vec![],
+            device_vendor: "Roku, Inc".to_string(),
+            vulnerabilities: vec![],
+            open_ports: vec![port(8060)],
+        };
+        let classification = DeviceClassRulesBackend::default().classify_device(&device);
+        assert_eq!(classification.device_type, "tv");
+        assert_eq!(classification.score, 160);
+    }
+
+    #[test]
+    fn rule_table_is_data() {
+        let mut rules = DeviceClassRulesBackend {
+            rules: vec![DeviceClassRuleBackend::new(
+                DeviceTypeBackend::Iot,
+                DeviceSignalKindBackend::Hostname,
+                "thermo-*",
+                50,
+            )],
+            min_score: 10,
+        };
+        rules.rules.push(DeviceClassRuleBackend {
+            device_type: "toaster".to_string(),
+            signal: "hostname".to_string(),
+            pattern: "*".to_string(),
+            weight: 1000,
+        });
+        let json = serde_json::to_string(&rules).unwrap();
+        let parsed: DeviceClassRulesBackend = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, rules);
+        let classification = parsed.classify(&(&dislike("", &[], "", "Thermo-Hall", &[])).into());
+        assert_eq!(classification.device_type, "iot");
+    }
+
+    #[test]
+    fn zero_weights_give_zero_confidence() {
+        let rules = DeviceClassRulesBackend {
+            rules: vec![DeviceClassRuleBackend::new(
+                DeviceTypeBackend::Iot,
+                DeviceSignalKindBackend::Hostname,
+                "*",
+                0,
+            )],
+            min_score: 0,
+        };
+        let dislike = dislike("router", &[], "", "plug", &[]);
+        let classification = rules.classify(&(&dislike).into());
+        assert_eq!(classification.device_type, "iot");
+        assert_eq!(classification.confidence, 0.0);
+        let report = evaluate_corpus(&rules, &[dislike]);
+        assert_eq!(report.mismatches[0].confidence, 0.0);
+    }
+
+    #[test]
+    fn huge_weights_saturate() {
+        let rule = |device_type, pattern| {
+            DeviceClassRuleBackend::new(
+                device_type,
+                DeviceSignalKindBackend::Hostname,
+                pattern,
+                u32::MAX,
+            )
+        };
+        let rules = DeviceClassRulesBackend {
+            rules: vec![
+                rule(DeviceTypeBackend::Camera, "cam-*"),
+                rule(DeviceTypeBackend::Camera, "*-door"),
+                rule(DeviceTypeBackend::Iot, "*"),
+            ],
+            min_score: 1,
+        };
+        let classification = rules.classify(&(&dislike("", &[], "", "cam-door", &[])).into());
+        assert_eq!(classification.device_type, "camera");
+        assert_eq!(classification.score, u32::MAX);
+        assert!(classification.confidence > 0.0 && classification.confidence <= 1.0);
+    }
+
+    #[test]
+    fn labels_map_to_types() {
+        assert_eq!(
+            DeviceTypeBackend::from_label(" Game_Console "),
+            Some(DeviceTypeBackend::GameConsole)
+        );
+        assert_eq!(
+            DeviceTypeBackend::from_label("Smart-TV"),
+            Some(DeviceTypeBackend::Tv)
+        );
+        assert_eq!(DeviceTypeBackend::from_label("unknown"), None);
+        assert_eq!(DeviceTypeBackend::from_label("toaster"), None);
+        for device_type in DeviceTypeBackend::ALL {
+            assert_eq!(
+                DeviceTypeBackend::from_str_opt(device_type.as_str()),
+                Some(device_type)
+            );
+        }
+    }
+}
@@ -3,7 +3,7 @@ use crate::lanscan_vulnerability_info_backend::VulnerabilityInfoBackend;
 use blake3::Hasher;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct DeviceInfoBackend {
     pub mdns_services: Vec<String>,
     pub device_vendor: String,
@@ -13,6 +13,21 @@ pub struct DeviceInfoBackend {
 
 impl DeviceInfoBackend {
     pub fn uid(&self, language: &str) -> String {
+        let mut hasher = crate::uid_backend::uid_hasher("device", language);
+        hasher.write_field("device_vendor", &self.device_vendor);
+        hasher.write_field("mdns_services", &self.mdns_services);
+        // Vulnerabilities have a possibility of change, so we include it
+        let mut sorted_vulnerabilities = self.vulnerabilities.clone();
+        sorted_vulnerabilities.sort_by(|a, b| a.name.cmp(&b.name));
+        hasher.write_field("vulnerabilities", &sorted_vulnerabilities);
+        let mut sorted_open_ports = self.open_ports.clone();
+        sorted_open_ports.sort_by_key(|a| a.port);
+        hasher.write_field("open_ports", &sorted_open_ports);
+        hasher.finish()
+    }
+
+    /// Scheme 1 uid, kept to rekey caches -- see [`crate::uid_backend`].
+    pub fn legacy_uid(&self, language: &str) -> String {
         let mut hasher = Hasher::new();
         hasher.update(language.as_bytes());
         hasher.update(self.device_vendor.as_bytes());
@@ -0,0 +1,443 @@
+//! Dislike reports turned into classifier training data.
+//!
+//! Every [`DislikeDeviceInfoBackend`] is a user saying "this device is a
+//! `device_type`". [`DislikeAggregatorBackend`] groups reports that describe
+//! the same kind of device -- same vendor, same mDNS services, same open
+//! ports, see [`DeviceFingerprintBackend`] -- and counts the corrected types
+//! in each group. A type that more than half of a group's labelled reports
+//! agree on is the group's majority.
+//!
+//! Majorities then vote per signal value: each vendor, mDNS service and port
+//! across all groups gets one candidate [`DeviceClassRuleBackend`] when
+//! enough votes agree. Its weight is the signal's base weight scaled by that
+//! agreement, so a candidate table can be replayed with
+//! [`crate::lanscan_device_classifier_backend::evaluate_corpus`] before the
+//! Hub ships it.
+
+use crate::lanscan_device_classifier_backend::{
+    DeviceClassRuleBackend, DeviceClassRulesBackend, DeviceSignalKindBackend, DeviceTypeBackend,
+};
+use crate::lanscan_dislike_device_info_backend::DislikeDeviceInfoBackend;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Fewest agreeing votes before a signal value becomes a candidate rule.
+pub const DEFAULT_MIN_RULE_VOTES: usize = 3;
+
+/// Smallest share of a signal value's votes its type must hold.
+pub const DEFAULT_MIN_RULE_AGREEMENT: f64 = 0.8;
+
+/// Weight of a unanimous candidate, per signal kind. Mirrors the built-in
+/// table: an mDNS service classifies alone, a vendor nearly does, a port
+/// needs corroboration. Hostnames are too personal to learn from.
+fn base_weight(signal: DeviceSignalKindBackend) -> u32 {
+    match signal {
+        DeviceSignalKindBackend::Mdns => 80,
+        DeviceSignalKindBackend::Vendor => 60,
+        DeviceSignalKindBackend::Port => 20,
+        DeviceSignalKindBackend::Hostname => 0,
+    }
+}
+
+/// What makes two reports "the same device": case-folded vendor, sorted
+/// mDNS services and the sorted set of open ports. Hostname and note are
+/// left out, they are per-user.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DeviceFingerprintBackend {
+    pub vendor: String,
+    pub mdns_services: Vec<String>,
+    pub open_ports: Vec<u16>,
+}
+
+impl From<&DislikeDeviceInfoBackend> for DeviceFingerprintBackend {
+    fn from(dislike: &DislikeDeviceInfoBackend) -> Self {
+        let mut mdns_services: Vec<String> = dislike
+            .mdns_services
+            .iter()
+            .map(|service| service.trim().to_lowercase())
+            .filter(|service| !service.is_empty())
+            .collect();
+        mdns_services.sort();
+        mdns_services.dedup();
+        let mut open_ports: Vec<u16> = dislike.open_ports.iter().map(|port| port.port).collect();
+        open_ports.sort_unstable();
+        open_ports.dedup();
+        Self {
+            vendor: dislike.device_vendor.trim().to_lowercase(),
+            mdns_services,
+            open_ports,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DeviceTypeVoteBackend {
+    /// See [`DeviceTypeBackend`].
+    pub device_type: String,
+    pub votes: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DislikeClusterBackend {
+    pub fingerprint: DeviceFingerprintBackend,
+    pub reports: usize,
+    /// Reports whose `device_type` maps to no known type.
+    pub unlabelled: usize,
+    /// Most votes first, ties in [`DeviceTypeBackend`] order.
+    pub votes: Vec<DeviceTypeVoteBackend>,
+    /// Type holding more than half of the labelled votes, if any.
+    pub majority: Option<String>,
+    /// Share of labelled votes held by the leading type, 0.0 when none.
+    pub agreement: f64,
+}
+
+impl DislikeClusterBackend {
+    pub fn labelled(&self) -> usize {
+        self.reports - self.unlabelled
+    }
+
+    pub fn majority_votes(&self) -> usize {
+        match &self.majority {
+            Some(_) => self.votes[0].votes,
+            None => 0,
+        }
+    }
+}
+
+/// Collects dislike reports; reading it never consumes them, so reports can
+/// keep arriving between exports.
+#[derive(Debug, Clone)]
+pub struct DislikeAggregatorBackend {
+    // Fingerprint -> (unlabelled, votes by type).
+    clusters: BTreeMap<DeviceFingerprintBackend, (usize, BTreeMap<DeviceTypeBackend, usize>)>,
+    min_rule_votes: usize,
+    min_rule_agreement: f64,
+}
+
+impl Default for DislikeAggregatorBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DislikeAggregatorBackend {
+    pub fn new() -> Self {
+        Self {
+            clusters: BTreeMap::new(),
+            min_rule_votes: DEFAULT_MIN_RULE_VOTES,
+            min_rule_agreement: DEFAULT_MIN_RULE_AGREEMENT,
+        }
+    }
+
+    pub fn with_min_rule_votes(mut self, min_rule_votes: usize) -> Self {
+        self.min_rule_votes = min_rule_votes.max(1);
+        self
+    }
+
+    /// Clamped to 0.5..=1.0: below half, two types could both qualify.
+    pub fn with_min_rule_agreement(mut self, min_rule_agreement: f64) -> Self {
+        self.min_rule_agreement = min_rule_agreement.clamp(0.5, 1.0);
+        self
+    }
+
+    pub fn from_reports<'a>(
+        reports: impl IntoIterator<Item = &'a DislikeDeviceInfoBackend>,
+    ) -> Self {
+        let mut aggregator = Self::new();
+        for report in reports {
+            aggregator.add(report);
+        }
+        aggregator
+    }
+
+    pub fn add(&mut self, report: &DislikeDeviceInfoBackend) {
+        let (unlabelled, votes) = self.clusters.entry(report.into()).or_default();
+        match DeviceTypeBackend::from_label(&report.device_type) {
+            Some(device_type) => *votes.entry(device_type).or_default() += 1,
+            None => *unlabelled += 1,
+        }
+    }
+
+    /// Distinct fingerprints seen.
+    pub fn len(&self) -> usize {
+        self.clusters.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.clusters.is_empty()
+    }
+
+    /// Sorted by fingerprint.
+    pub fn clusters(&self) -> Vec<DislikeClusterBackend> {
+        self.clusters
+            .iter()
+            .map(|(fingerprint, (unlabelled, votes))| {
+                let mut votes: Vec<(DeviceTypeBackend, usize)> =
+                    votes.iter().map(|(t, n)| (*t, *n)).collect();
+                votes.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+                let labelled: usize = votes.iter().map(|(_, n)| n).sum();
+                let leading = votes.first().map_or(0, |(_, n)| *n);
+                let majority = votes
+                    .first()
+                    .filter(|(_, n)| 2 * n > labelled)
+                    .map(|(t, _)| t.as_str().to_string());
+                DislikeClusterBackend {
+                    fingerprint: fingerprint.clone(),
+                    reports: labelled + unlabelled,
+                    unlabelled: *unlabelled,
+                    votes: votes
+                        .into_iter()
+                        .map(|(device_type, votes)| DeviceTypeVoteBackend {
+                            device_type: device_type.as_str().to_string(),
+                            votes,
+                        })
+                        .collect(),
+                    majority,
+                    agreement: if labelled == 0 {
+                        0.0
+                    } else {
+                        leading as f64 / labelled as f64
+                    },
+                }
+            })
+            .collect()
+    }
+
+    /// One rule per vendor, mDNS service or port whose clusters' majorities
+    /// agree. Each cluster votes with its majority count, for its majority
+    /// type; a cluster without a majority votes against every type. Sorted by
+    /// signal, then pattern.
+    pub fn candidate_rules(&self) -> Vec<DeviceClassRuleBackend> {
+        // (signal, pattern) -> (votes by type, all labelled votes).
+        let mut tallies: BTreeMap<
+            (DeviceSignalKindBackend, String),
+            (BTreeMap<DeviceTypeBackend, usize>, usize),
+        > = BTreeMap::new();
+        for cluster in self.clusters() {
+            let labelled = cluster.labelled();
+            if labelled == 0 {
+                continue;
+            }
+            let majority = cluster
+                .majority
+                .as_deref()
+                .and_then(DeviceTypeBackend::from_str_opt);
+            for key in signal_patterns(&cluster.fingerprint) {
+                let (by_type, total) = tallies.entry(key).or_default();
+                *total += labelled;
+                if let Some(device_type) = majority {
+                    *by_type.entry(device_type).or_default() += cluster.majority_votes();
+                }
+            }
+        }
+
+        let mut rules = Vec::new();
+        for ((signal, pattern), (by_type, total)) in tallies {
+            // BTreeMap order breaks ties towards the type declared first.
+            let Some((device_type, votes)) = by_type
+                .into_iter()
+                .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0)))
+            else {
+                continue;
+            };
+            let agreement = votes as f64 / total as f64;
+            if votes < self.min_rule_votes || agreement < self.min_rule_agreement {
+                continue;
+            }
+            let weight = (base_weight(signal) as f64 * agreement).round() as u32;
+            rules.push(DeviceClassRuleBackend::new(
+                device_type,
+                signal,
+                pattern,
+                weight,
+            ));
+        }
+        rules
+    }
+
+    /// `base` plus the candidates it does not already have for the same type,
+    /// signal and pattern.
+    pub fn candidate_table(&self, base: &DeviceClassRulesBackend) -> DeviceClassRulesBackend {
+        let mut table = base.clone();
+        for rule in self.candidate_rules() {
+            let known = table.rules.iter().any(|existing| {
+                existing.device_type == rule.device_type
+                    && existing.signal == rule.signal
+                    && existing.pattern.eq_ignore_ascii_case(&rule.pattern)
+            });
+            if !known {
+                table.rules.push(rule);
+            }
+        }
+        table
+    }
+}
+
+/// The patterns a fingerprint votes for. mDNS services drop their `.local`
+/// suffix and match any domain, like the built-in table; values holding glob
+/// characters are skipped rather than escaped.
+fn signal_patterns(
+    fingerprint: &DeviceFingerprintBackend,
+) -> Vec<(DeviceSignalKindBackend, String)> {
+    let mut patterns = Vec::new();
+    if !fingerprint.vendor.is_empty() {
+        patterns.push((DeviceSignalKindBackend::Vendor, fingerprint.vendor.clone()));
+    }
+    for service in &fingerprint.mdns_services {
+        let service = service.trim_end_matches('.');
+        let service = service.strip_suffix(".local").unwrap_or(service);
+        patterns.push((DeviceSignalKindBackend::Mdns, format!("{service}*")));
+    }
+    for port in &fingerprint.open_ports {
+        patterns.push((DeviceSignalKindBackend::Port, port.to_string()));
+    }
+    patterns.retain(|(signal, pattern)| {
+        let value = match signal {
+            DeviceSignalKindBackend::Mdns => pattern.trim_end_matches('*'),
+            _ => pattern.as_str(),
+        };
+        !value.contains(['*', '?'])
+    });
+    patterns.sort();
+    patterns.dedup();
+    patterns
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lanscan_device_classifier_backend::evaluate_corpus;
+    use crate::lanscan_port_info_backend::PortInfoBackend;
+
+    fn dislike(
+        device_type: &str,
+        mdns: &[&str],
+        vendor: &str,
+        ports: &[u16],
+    ) -> DislikeDeviceInfoBackend {
+        DislikeDeviceInfoBackend {
+            device_type: device_type.to_string(),
+            open_ports: ports
+                .iter()
+                .map(|port| PortInfoBackend {
+                    port: *port,
+                    protocol: "tcp".to_string(),
+                    service: String::new(),
+                    banner: String::new(),
+                    vulnerabilities: Vec::new(),
+                })
+                .collect(),
+            mdns_services: mdns.iter().map(|s| s.to_string()).collect(),
+            device_vendor: vendor.to_string(),
+            hostname: "someones-device".to_string(),
+            note: String::new(),
+        }
+    }
+
+    fn corpus() -> Vec<DislikeDeviceInfoBackend> {
+        let mut reports = Vec::new();
+        for _ in 0..3 {
+            reports.push(dislike(
+                "smart plug",
+                &["_tplink._tcp.local"],
+                "Kasa Inc",
+                &[9999],
+            ));
+        }
+        // Same device, ports and services listed in another order.
+        reports.push(dislike(
+            "iot",
+            &["_tplink._tcp.local"],
+            "KASA INC ",
+            &[9999, 9999],
+        ));
+        reports.push(dislike("tv", &["_tplink._tcp.local"], "Kasa Inc", &[9999]));
+        reports.push(dislike("", &["_tplink._tcp.local"], "Kasa Inc", &[9999]));
+        // A split cluster on a shared port.
+        reports.push(dislike("camera", &[], "Wyze Labs", &[80]));
+        reports.push(dislike("router", &[], "Wyze Labs", &[80]));
+        reports
+    }
+
+    #[test]
+    fn clusters_by_fingerprint() {
+        let aggregator = DislikeAggregatorBackend::from_reports(&corpus());
+        assert_eq!(aggregator.len(), 2);
+        let clusters = aggregator.clusters();
+
+        let kasa = &clusters[0];
+        assert_eq!(kasa.fingerprint.vendor, "kasa inc");
+        assert_eq!(kasa.fingerprint.open_ports, vec![9999]);
+        assert_eq!(kasa.reports, 6);
+        assert_eq!(kasa.unlabelled, 1);
+        assert_eq!(
+            kasa.votes,
+            vec![
+                DeviceTypeVoteBackend {
+                    device_type: "iot".to_string(),
+                    votes: 4
+                },
+                DeviceTypeVoteBackend {
+                    device_type: "tv".to_string(),
+                    votes: 1
+                },
+            ]
+        );
+        assert_eq!(kasa.majority.as_deref(), Some("iot"));
+        assert_eq!(kasa.majority_votes(), 4);
+        assert_eq!(kasa.agreement, 0.8);
+
+        let wyze = &clusters[1];
+        assert_eq!(wyze.majority, None);
+        assert_eq!(wyze.agreement, 0.5);
+    }
+
+    #[test]
+    fn exports_agreeing_signals_as_rules() {
+        let aggregator = DislikeAggregatorBackend::from_reports(&corpus());
+        assert_eq!(
+            aggregator.candidate_rules(),
+            vec![
+                DeviceClassRuleBackend::new(
+                    DeviceTypeBackend::Iot,
+                    DeviceSignalKindBackend::Mdns,
+                    "_tplink._tcp*",
+                    64
+                ),
+                DeviceClassRuleBackend::new(
+                    DeviceTypeBackend::Iot,
+                    DeviceSignalKindBackend::Vendor,
+                    "kasa inc",
+                    48
+                ),
+                DeviceClassRuleBackend::new(
+                    DeviceTypeBackend::Iot,
+                    DeviceSignalKindBackend::Port,
+                    "9999",
+                    16
+                ),
+            ]
+        );
+
+        let strict = DislikeAggregatorBackend::from_reports(&corpus()).with_min_rule_agreement(0.9);
+        assert!(strict.candidate_rules().is_empty());
+        let quorum = DislikeAggregatorBackend::from_reports(&corpus()).with_min_rule_votes(5);
+        assert!(quorum.candidate_rules().is_empty());
+    }
+
+    #[test]
+    fn candidate_table_fixes_the_corpus() {
+        let reports = corpus();
+        let base = DeviceClassRulesBackend::default();
+        let before = evaluate_corpus(&base, &reports);
+
+        let aggregator = DislikeAggregatorBackend::from_reports(&reports);
+        let table = aggregator.candidate_table(&base);
+        assert_eq!(table.rules.len(), base.rules.len() + 3);
+        // Nothing new the second time round.
+        assert_eq!(aggregator.candidate_table(&table), table);
+
+        let after = evaluate_corpus(&table, &reports);
+        assert!(after.correct > before.correct);
+    }
+}
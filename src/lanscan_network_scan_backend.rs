@@ -0,0 +1,570 @@
+//! Whole-network LAN scan snapshot, and the diff between two of them.
+//!
+//! [`DeviceInfoBackend`] describes one device but carries no address, so two
+//! scans could not be lined up. [`NetworkScanBackend`] wraps every device with
+//! its addresses and a stable key, under the identity of the network it was
+//! taken on. [`diff_network_scans`] compares two snapshots and reports devices
+//! that appeared or disappeared, ports that opened or closed, and
+//! vulnerabilities that were not there before or whose record changed: the
+//! input for "a new camera appeared on your network" alerts.
+
+use crate::lanscan_device_classifier_backend::{
+    DeviceClassRulesBackend, DeviceClassificationBackend, DeviceSignalsBackend, DeviceTypeBackend,
+};
+use crate::lanscan_device_info_backend::DeviceInfoBackend;
+use crate::lanscan_vulnerability_info_backend::VulnerabilityInfoBackend;
+use crate::stable_hash_backend::StableHash;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+/// Which network a scan was taken on.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NetworkIdentityBackend {
+    /// Gateway IP address.
+    pub gateway: String,
+    /// See [`hash_ssid`]; empty on wired networks.
+    pub ssid_hash: String,
+    /// e.g. `192.168.1.0/24`.
+    pub cidr: String,
+}
+
+/// SSIDs are not sent in clear; this is the digest the snapshot carries.
+///
+/// SSIDs are short and guessable, so a plain hash would fall to a dictionary
+/// of common names. The digest is an HMAC-SHA256 keyed with `key`, a secret
+/// the reporting side keeps (per device or per fleet): snapshots hashed with
+/// the same key still line up, and nobody without it can test guesses.
+pub fn hash_ssid(key: &str, ssid: &str) -> String {
+    if ssid.is_empty() {
+        return String::new();
+    }
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(key.as_bytes()).expect("HMAC can take key of any size");
+    mac.update(ssid.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Stable key for a device across scans: its MAC address when known (DHCP
+/// moves IPs around), else its hostname, else its IP address, else a digest
+/// of what was seen on it, so unaddressed devices are not merged into one.
+/// Prefixed with the kind so a hostname can never collide with an address.
+pub fn network_device_key(
+    mac_address: &str,
+    hostname: &str,
+    ip_addresses: &[String],
+    device: &DeviceInfoBackend,
+) -> String {
+    let mac = mac_address.trim().to_ascii_lowercase().replace('-', ":");
+    if !mac.is_empty() {
+        return format!("mac:{mac}");
+    }
+    let hostname = hostname.trim().trim_end_matches('.').to_ascii_lowercase();
+    if !hostname.is_empty() {
+        return format!("host:{hostname}");
+    }
+    let mut ips: Vec<&str> = ip_addresses
+        .iter()
+        .map(|ip| ip.trim())
+        .filter(|ip| !ip.is_empty())
+        .collect();
+    ips.sort();
+    match ips.first() {
+        Some(ip) => format!("ip:{ip}"),
+        None => format!("device:{}", device.stable_hash()),
+    }
+}
+
+/// An open port and its transport, lowercase.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NetworkScanPortBackend {
+    pub port: u16,
+    pub protocol: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct NetworkScanDeviceBackend {
+    /// See [`network_device_key`].
+    pub key: String,
+    pub mac_address: String,
+    pub hostname: String,
+    /// Sorted.
+    pub ip_addresses: Vec<String>,
+    pub device: DeviceInfoBackend,
+}
+
+impl NetworkScanDeviceBackend {
+    pub fn new(
+        mac_address: impl Into<String>,
+        hostname: impl Into<String>,
+        mut ip_addresses: Vec<String>,
+        device: DeviceInfoBackend,
+    ) -> Self {
+        let mac_address = mac_address.into();
+        let hostname = hostname.into();
+        ip_addresses.sort();
+        ip_addresses.dedup();
+        Self {
+            key: network_device_key(&mac_address, &hostname, &ip_addresses, &device),
+            mac_address,
+            hostname,
+            ip_addresses,
+            device,
+        }
+    }
+
+    /// Open ports with their transport, sorted and deduplicated.
+    pub fn open_ports(&self) -> Vec<NetworkScanPortBackend> {
+        let mut ports: Vec<NetworkScanPortBackend> = self
+            .device
+            .open_ports
+            .iter()
+            .map(|port| NetworkScanPortBackend {
+                port: port.port,
+                protocol: port.protocol.trim().to_ascii_lowercase(),
+            })
+            .collect();
+        ports.sort();
+        ports.dedup();
+        ports
+    }
+
+    /// Device-level and per-port vulnerabilities, sorted, exact duplicates
+    /// removed.
+    pub fn vulnerabilities(&self) -> Vec<VulnerabilityInfoBackend> {
+        let mut vulnerabilities: Vec<VulnerabilityInfoBackend> = self
+            .device
+            .vulnerabilities
+            .iter()
+            .chain(
+                self.device
+                    .open_ports
+                    .iter()
+                    .flat_map(|port| &port.vulnerabilities),
+            )
+            .cloned()
+            .collect();
+        vulnerabilities.sort();
+        vulnerabilities.dedup();
+        vulnerabilities
+    }
+
+    pub fn signals(&self) -> DeviceSignalsBackend {
+        DeviceSignalsBackend {
+            hostname: self.hostname.clone(),
+            ..DeviceSignalsBackend::from(&self.device)
+        }
+    }
+
+    pub fn classify(&self, rules: &DeviceClassRulesBackend) -> DeviceClassificationBackend {
+        rules.classify(&self.signals())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(from = "NetworkScanWireBackend")]
+pub struct NetworkScanBackend {
+    pub network: NetworkIdentityBackend,
+    pub scanned_at: DateTime<Utc>,
+    /// Sorted by key, one entry per key. Deserialization restores this, so
+    /// a payload listing devices in any order diffs the same.
+    pub devices: Vec<NetworkScanDeviceBackend>,
+}
+
+/// The same JSON as [`NetworkScanBackend`], before sorting.
+#[derive(Deserialize)]
+struct NetworkScanWireBackend {
+    network: NetworkIdentityBackend,
+    scanned_at: DateTime<Utc>,
+    devices: Vec<NetworkScanDeviceBackend>,
+}
+
+impl From<NetworkScanWireBackend> for NetworkScanBackend {
+    fn from(wire: NetworkScanWireBackend) -> Self {
+        Self::new(wire.network, wire.scanned_at, wire.devices)
+    }
+}
+
+impl NetworkScanBackend {
+    /// Devices are sorted by key; when several share a key, the first one
+    /// given is kept.
+    pub fn new(
+        network: NetworkIdentityBackend,
+        scanned_at: DateTime<Utc>,
+        mut devices: Vec<NetworkScanDeviceBackend>,
+    ) -> Self {
+        devices.sort_by(|a, b| a.key.cmp(&b.key));
+        devices.dedup_by(|a, b| a.key == b.key);
+        Self {
+            network,
+            scanned_at,
+            devices,
+        }
+    }
+
+    pub fn device(&self, key: &str) -> Option<&NetworkScanDeviceBackend> {
+        self.devices
+            .binary_search_by(|device| device.key.as_str().cmp(key))
+            .ok()
+            .map(|index| &self.devices[index])
+    }
+}
+
+/// What changed on a device present in both scans.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct NetworkDeviceChangeBackend {
+    pub key: String,
+    pub opened_ports: Vec<NetworkScanPortBackend>,
+    pub closed_ports: Vec<NetworkScanPortBackend>,
+    /// Vulnerabilities whose name the device did not have before.
+    pub new_vulnerabilities: Vec<VulnerabilityInfoBackend>,
+    /// Vulnerabilities known by name whose record changed (CVSS, EPSS,
+    /// references, …), as they are now.
+    pub updated_vulnerabilities: Vec<VulnerabilityInfoBackend>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct NetworkScanDiffBackend {
+    /// `false` when the two scans come from different networks; the lists are
+    /// still filled in, but are unlikely to mean much.
+    pub same_network: bool,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    /// Sorted by key.
+    pub new_devices: Vec<NetworkScanDeviceBackend>,
+    /// Sorted by key.
+    pub gone_devices: Vec<NetworkScanDeviceBackend>,
+    /// Devices in both scans with at least one change, sorted by key.
+    pub changed_devices: Vec<NetworkDeviceChangeBackend>,
+}
+
+impl NetworkScanDiffBackend {
+    pub fn is_empty(&self) -> bool {
+        self.new_devices.is_empty()
+            && self.gone_devices.is_empty()
+            && self.changed_devices.is_empty()
+    }
+
+    /// New devices `rules` classify as `device_type`.
+    pub fn new_devices_of_type<'a>(
+        &'a self,
+        rules: &DeviceClassRulesBackend,
+        device_type: DeviceTypeBackend,
+    ) -> Vec<&'a NetworkScanDeviceBackend> {
+        self.new_devices
+            .iter()
+            .filter(|device| device.classify(rules).device_type == device_type.as_str())
+            .collect()
+    }
+}
+
+pub fn diff_network_scans(
+    old: &NetworkScanBackend,
+    new: &NetworkScanBackend,
+) -> NetworkScanDiffBackend {
+    let mut diff = NetworkScanDiffBackend {
+        same_network: old.network == new.network,
+        from: old.scanned_at,
+        to: new.scanned_at,
+        new_devices: Vec::new(),
+        gone_devices: old
+            .devices
+            .iter()
+            .filter(|device| new.device(&device.key).is_none())
+            .cloned()
+            .collect(),
+        changed_devices: Vec::new(),
+    };
+    for device in &new.devices {
+        let Some(before) = old.device(&device.key) else {
+            diff.new_devices.push(device.clone());
+            continue;
+        };
+        let (ports_before, ports_after) = (before.open_ports(), device.open_ports());
+        let known = before.vulnerabilities();
+        let (updated_vulnerabilities, new_vulnerabilities) = device
+            .vulnerabilities()
+            .into_iter()
+            .filter(|vulnerability| !known.contains(vulnerability))
+            .partition(|vulnerability| known.iter().any(|old| old.name == vulnerability.name));
+        let change = NetworkDeviceChangeBackend {
+            key: device.key.clone(),
+            opened_ports: ports_after
+                .iter()
+                .filter(|port| !ports_before.contains(port))
+                .cloned()
+                .collect(),
+            closed_ports: ports_before
+                .iter()
+                .filter(|port| !ports_after.contains(port))
+                .cloned()
+                .collect(),
+            new_vulnerabilities,
+            updated_vulnerabilities,
+        };
+        if !change.opened_ports.is_empty()
+            || !change.closed_ports.is_empty()
+            || !change.new_vulnerabilities.is_empty()
+            || !change.updated_vulnerabilities.is_empty()
+        {
+            diff.changed_devices.push(change);
+        }
+    }
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lanscan_port_info_backend::PortInfoBackend;
+
+    fn port(port: u16, protocol: &str) -> NetworkScanPortBackend {
+        NetworkScanPortBackend {
+            port,
+            protocol: protocol.to_string(),
+        }
+    }
+
+    fn device(vendor: &str, ports: &[u16], vulns: &[&str]) -> DeviceInfoBackend {
+        DeviceInfoBackend {
+            mdns_services: vec![],
+            device_vendor: vendor.to_string(),
+            vulnerabilities: vulns
+                .iter()
+                .map(|name| VulnerabilityInfoBackend::new(*name, ""))
+                .collect(),
+            open_ports: ports
+                .iter()
+                .map(|port| PortInfoBackend {
+                    port: *port,
+                    protocol: "tcp".to_string(),
+                    service: String::new(),
+                    banner: String::new(),
+                    vulnerabilities: vec![],
+                })
+                .collect(),
+        }
+    }
+
+    fn network() -> NetworkIdentityBackend {
+        NetworkIdentityBackend {
+            gateway: "192.168.1.1".to_string(),
+            ssid_hash: hash_ssid("fleet-key", "home"),
+            cidr: "192.168.1.0/24".to_string(),
+        }
+    }
+
+    fn at(timestamp: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(timestamp)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn scan(timestamp: &str, devices: Vec<NetworkScanDeviceBackend>) -> NetworkScanBackend {
+        NetworkScanBackend::new(network(), at(timestamp), devices)
+    }
+
+    #[test]
+    fn device_keys_prefer_mac_then_hostname_then_ip() {
+        let ips = vec!["10.0.0.9".to_string(), "10.0.0.2".to_string()];
+        let nothing = device("x", &[], &[]);
+        assert_eq!(
+            network_device_key("AA-BB-CC-00-11-22", "nas", &ips, &nothing),
+            "mac:aa:bb:cc:00:11:22"
+        );
+        assert_eq!(
+            network_device_key("", "NAS.local.", &ips, &nothing),
+            "host:nas.local"
+        );
+        assert_eq!(network_device_key("", " ", &ips, &nothing), "ip:10.0.0.2");
+        let unaddressed = network_device_key("", "", &[], &nothing);
+        assert!(unaddressed.starts_with("device:"));
+        assert_ne!(
+            network_device_key("", "", &[], &device("y", &[], &[])),
+            unaddressed
+        );
+        assert_eq!(hash_ssid("fleet-key", ""), "");
+        assert_ne!(hash_ssid("fleet-key", "home"), "home");
+        assert_eq!(
+            hash_ssid("fleet-key", "home"),
+            hash_ssid("fleet-key", "home")
+        );
+        assert_ne!(
+            hash_ssid("fleet-key", "home"),
+            hash_ssid("other-key", "home")
+        );
+    }
+
+    #[test]
+    fn diff_reports_devices_ports_and_vulnerabilities() {
+        let router = |ports: &[u16], vulns: &[&str]| {
+            NetworkScanDeviceBackend::new(
+                "aa:aa:aa:aa:aa:01",
+                "router",
+                vec!["192.168.1.1".to_string()],
+                device("Sagemcom", ports, vulns),
+            )
+        };
+        let laptop = NetworkScanDeviceBackend::new(
+            "",
+            "laptop",
+            vec!["192.168.1.20".to_string()],
+            device("Apple", &[22], &[]),
+        );
+        let camera = NetworkScanDeviceBackend::new(
+            "aa:aa:aa:aa:aa:02",
+            "",
+            vec!["192.168.1.30".to_string()],
+            device("Hangzhou Hikvision", &[554, 8000], &[]),
+        );
+        let old = scan(
+            "2026-01-01T00:00:00Z",
+            vec![router(&[53, 80, 23], &["old-cve"]), laptop],
+        );
+        // The router moved IP: still the same device.
+        let mut moved = router(&[53, 80, 443], &["old-cve", "new-cve"]);
+        moved.ip_addresses = vec!["192.168.1.254".to_string()];
+        let new = scan("2026-01-02T00:00:00Z", vec![camera, moved]);
+
+        let diff = diff_network_scans(&old, &new);
+        assert!(diff.same_network);
+        assert_eq!(diff.new_devices.len(), 1);
+        assert_eq!(diff.new_devices[0].key, "mac:aa:aa:aa:aa:aa:02");
+        assert_eq!(diff.gone_devices.len(), 1);
+        assert_eq!(diff.gone_devices[0].key, "host:laptop");
+        assert_eq!(diff.changed_devices.len(), 1);
+        let change = &diff.changed_devices[0];
+        assert_eq!(change.opened_ports, vec![port(443, "tcp")]);
+        assert_eq!(change.closed_ports, vec![port(23, "tcp")]);
+        assert_eq!(change.new_vulnerabilities.len(), 1);
+        assert_eq!(change.new_vulnerabilities[0].name, "new-cve");
+
+        let cameras = diff.new_devices_of_type(
+            &DeviceClassRulesBackend::default(),
+            DeviceTypeBackend::Camera,
+        );
+        assert_eq!(cameras.len(), 1);
+    }
+
+    #[test]
+    fn identical_scans_have_empty_diff() {
+        let devices = vec![NetworkScanDeviceBackend::new(
+            "",
+            "",
+            vec!["192.168.1.5".to_string()],
+            device("x", &[80], &["a"]),
+        )];
+        let old = scan("2026-01-01T00:00:00Z", devices.clone());
+        let mut new = scan("2026-01-02T00:00:00Z", devices);
+        assert!(diff_network_scans(&old, &new).is_empty());
+
+        new.network.gateway = "10.0.0.1".to_string();
+        assert!(!diff_network_scans(&old, &new).same_network);
+    }
+
+    #[test]
+    fn per_port_vulnerabilities_count() {
+        let mut before = device("x", &[80], &[]);
+        let old = scan(
+            "2026-01-01T00:00:00Z",
+            vec![NetworkScanDeviceBackend::new(
+                "01",
+                "",
+                vec![],
+                before.clone(),
+            )],
+        );
+        before.open_ports[0]
+            .vulnerabilities
+            .push(VulnerabilityInfoBackend::new("http-vuln", ""));
+        let new = scan(
+            "2026-01-02T00:00:00Z",
+            vec![NetworkScanDeviceBackend::new("01", "", vec![], before)],
+        );
+        let diff = diff_network_scans(&old, &new);
+        assert_eq!(
+            diff.changed_devices[0].new_vulnerabilities[0].name,
+            "http-vuln"
+        );
+    }
+
+    #[test]
+    fn snapshot_round_trips_and_dedups_keys() {
+        let a = NetworkScanDeviceBackend::new("01", "", vec![], device("first", &[], &[]));
+        let b = NetworkScanDeviceBackend::new("01", "", vec![], device("second", &[], &[]));
+        let snapshot = scan("2026-01-01T00:00:00Z", vec![a, b]);
+        assert_eq!(snapshot.devices.len(), 1);
+        assert_eq!(snapshot.devices[0].device.device_vendor, "first");
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let parsed: NetworkScanBackend = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, snapshot);
+    }
+
+    #[test]
+    fn unaddressed_devices_are_all_kept() {
+        let devices: Vec<NetworkScanDeviceBackend> = ["a", "b", "c"]
+            .iter()
+            .map(|vendor| NetworkScanDeviceBackend::new("", "", vec![], device(vendor, &[], &[])))
+            .collect();
+        let snapshot = scan("2026-01-01T00:00:00Z", devices);
+        assert_eq!(snapshot.devices.len(), 3);
+    }
+
+    #[test]
+    fn port_diff_sees_the_transport() {
+        let mut dns = device("x", &[53], &[]);
+        dns.open_ports[0].protocol = "udp".to_string();
+        let old = scan(
+            "2026-01-01T00:00:00Z",
+            vec![NetworkScanDeviceBackend::new("01", "", vec![], dns.clone())],
+        );
+        dns.open_ports[0].protocol = "TCP".to_string();
+        let new = scan(
+            "2026-01-02T00:00:00Z",
+            vec![NetworkScanDeviceBackend::new("01", "", vec![], dns)],
+        );
+        let change = &diff_network_scans(&old, &new).changed_devices[0];
+        assert_eq!(change.opened_ports, vec![port(53, "tcp")]);
+        assert_eq!(change.closed_ports, vec![port(53, "udp")]);
+    }
+
+    #[test]
+    fn rescored_vulnerabilities_are_reported_as_updated() {
+        let mut before = device("x", &[], &["CVE-2024-0001"]);
+        let old = scan(
+            "2026-01-01T00:00:00Z",
+            vec![NetworkScanDeviceBackend::new(
+                "01",
+                "",
+                vec![],
+                before.clone(),
+            )],
+        );
+        before.vulnerabilities[0].epss = Some(0.9);
+        let new = scan(
+            "2026-01-02T00:00:00Z",
+            vec![NetworkScanDeviceBackend::new("01", "", vec![], before)],
+        );
+        let change = &diff_network_scans(&old, &new).changed_devices[0];
+        assert!(change.new_vulnerabilities.is_empty());
+        assert_eq!(change.updated_vulnerabilities.len(), 1);
+        assert_eq!(change.updated_vulnerabilities[0].epss, Some(0.9));
+    }
+
+    #[test]
+    fn unsorted_payload_is_sorted_on_deserialize() {
+        let devices: Vec<NetworkScanDeviceBackend> = ["03", "01", "02", "01"]
+            .iter()
+            .map(|mac| NetworkScanDeviceBackend::new(*mac, "", vec![], device(mac, &[], &[])))
+            .collect();
+        let sorted = scan("2026-01-01T00:00:00Z", devices.clone());
+        let mut unsorted = sorted.clone();
+        unsorted.devices = devices;
+        let json = serde_json::to_string(&unsorted).unwrap();
+        let parsed: NetworkScanBackend = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, sorted);
+        assert!(parsed.device("mac:02").is_some());
+        assert!(diff_network_scans(&sorted, &parsed).is_empty());
+    }
+}
@@ -9,3 +9,11 @@ pub struct PortInfoBackend {
     pub banner: String,
     pub vulnerabilities: Vec<VulnerabilityInfoBackend>,
 }
+
+impl PortInfoBackend {
+    /// Product, version and OS read from `banner` -- see
+    /// [`crate::lanscan_banner_backend`].
+    pub fn fingerprint(&self) -> Option<crate::lanscan_banner_backend::BannerFingerprintBackend> {
+        crate::lanscan_banner_backend::fingerprint_banner(&self.banner)
+    }
+}
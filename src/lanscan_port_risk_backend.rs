@@ -0,0 +1,597 @@
+//! Exposure risk of an open port.
+//!
+//! [`assess_port`] rates a [`PortInfoBackend`] from three sources and keeps
+//! every reason it found:
+//!
+//! - the service itself, from a curated table of services that should not be
+//!   reachable on a LAN (telnet, SMB, RDP, unauthenticated Redis, …);
+//! - the software version fingerprinted from the banner (see
+//!   [`crate::lanscan_banner_backend`]), against known-bad releases;
+//! - the vulnerabilities attached to the port, by CVSS severity.
+//!
+//! The port's level is the highest level among its reasons.
+//! [`PortRiskBackend::todo`] turns an assessment into the `NetworkPort`
+//! advisor todo, so every producer of those todos prioritizes the same way.
+
+use crate::advice_details_backend::AdviceDetailsBackend;
+use crate::advisor_todos_backend::{AdvicePriorityBackend, AdvisorTodoBackend};
+use crate::cvss_backend::CvssSeverityBackend;
+use crate::lanscan_banner_backend::fingerprint_banner;
+use crate::lanscan_port_info_backend::PortInfoBackend;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PortRiskLevelBackend {
+    None,
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl PortRiskLevelBackend {
+    pub const ALL: [Self; 5] = [
+        Self::None,
+        Self::Low,
+        Self::Medium,
+        Self::High,
+        Self::Critical,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+            Self::Critical => "critical",
+        }
+    }
+
+    pub fn from_str_opt(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|level| level.as_str() == value)
+    }
+
+    /// `None` for [`PortRiskLevelBackend::None`]: nothing to advise.
+    pub fn priority(&self) -> Option<AdvicePriorityBackend> {
+        match self {
+            Self::None => None,
+            Self::Low => Some(AdvicePriorityBackend::Low),
+            Self::Medium => Some(AdvicePriorityBackend::Medium),
+            Self::High => Some(AdvicePriorityBackend::High),
+            Self::Critical => Some(AdvicePriorityBackend::Critical),
+        }
+    }
+
+    fn from_severity(severity: CvssSeverityBackend) -> Self {
+        match severity {
+            CvssSeverityBackend::None => Self::None,
+            CvssSeverityBackend::Low => Self::Low,
+            CvssSeverityBackend::Medium => Self::Medium,
+            CvssSeverityBackend::High => Self::High,
+            CvssSeverityBackend::Critical => Self::Critical,
+        }
+    }
+}
+
+/// Where a reason came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PortRiskSourceBackend {
+    Service,
+    Banner,
+    Vulnerability,
+}
+
+impl PortRiskSourceBackend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Service => "service",
+            Self::Banner => "banner",
+            Self::Vulnerability => "vulnerability",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PortRiskReasonBackend {
+    /// See [`PortRiskSourceBackend`].
+    pub source: String,
+    /// See [`PortRiskLevelBackend`].
+    pub level: String,
+    pub message: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PortRiskBackend {
+    pub port: u16,
+    pub protocol: String,
+    /// Highest level among `rationale`; `none` when it is empty.
+    pub level: String,
+    /// Highest level first.
+    pub rationale: Vec<PortRiskReasonBackend>,
+}
+
+impl PortRiskBackend {
+    pub fn level(&self) -> PortRiskLevelBackend {
+        PortRiskLevelBackend::from_str_opt(&self.level).unwrap_or(PortRiskLevelBackend::None)
+    }
+
+    /// `NetworkPort` details for this port: number and transport. The
+    /// transport stays in the typed value; the wire carries the port only.
+    pub fn details(&self) -> AdviceDetailsBackend {
+        let protocol = self.protocol.trim().to_ascii_lowercase();
+        AdviceDetailsBackend::NetworkPort {
+            port: self.port,
+            protocol: (!protocol.is_empty()).then_some(protocol),
+        }
+    }
+
+    /// The `NetworkPort` todo for this port; `None` when there is no risk.
+    pub fn todo(&self) -> Option<AdvisorTodoBackend> {
+        Some(AdvisorTodoBackend {
+            advice: self.details().into(),
+            priority: self.level().priority()?,
+        })
+    }
+}
+
+/// A service that should not be exposed. Matches when the transport is
+/// `protocol` (or unreported) and the port is listed or the reported service
+/// name is.
+struct DangerousServiceBackend {
+    protocol: &'static str,
+    ports: &'static [u16],
+    services: &'static [&'static str],
+    level: PortRiskLevelBackend,
+    message: &'static str,
+}
+
+const DANGEROUS_SERVICES: &[DangerousServiceBackend] = {
+    use PortRiskLevelBackend::*;
+    &[
+        DangerousServiceBackend {
+            protocol: "tcp",
+            ports: &[23, 2323],
+            services: &["telnet"],
+            level: High,
+            message: "Telnet sends credentials in clear text",
+        },
+        DangerousServiceBackend {
+            protocol: "tcp",
+            ports: &[512, 513, 514],
+            services: &["exec", "login", "shell", "rsh", "rlogin"],
+            level: High,
+            message: "r-services authenticate by source address only",
+        },
+        DangerousServiceBackend {
+            protocol: "tcp",
+            ports: &[21],
+            services: &["ftp"],
+            level: Medium,
+            message: "FTP sends credentials in clear text",
+        },
+        DangerousServiceBackend {
+            protocol: "udp",
+            ports: &[69],
+            services: &["tftp"],
+            level: Medium,
+            message: "TFTP has no authentication",
+        },
+        DangerousServiceBackend {
+            protocol: "tcp",
+            ports: &[139, 445],
+            services: &["microsoft-ds", "netbios-ssn", "smb"],
+            level: High,
+            message: "SMB file sharing is a frequent ransomware entry point",
+        },
+        DangerousServiceBackend {
+            protocol: "tcp",
+            ports: &[3389],
+            services: &["ms-wbt-server", "rdp"],
+            level: High,
+            message: "Remote Desktop is a frequent brute-force target",
+        },
+        DangerousServiceBackend {
+            protocol: "tcp",
+            ports: &[5900, 5901, 5902, 5903],
+            services: &["vnc"],
+            level: High,
+            message: "VNC is often left with a weak or no password",
+        },
+        DangerousServiceBackend {
+            protocol: "tcp",
+            ports: &[6379],
+            services: &["redis"],
+            level: High,
+            message: "Redis has no authentication by default",
+        },
+        DangerousServiceBackend {
+            protocol: "tcp",
+            ports: &[27017],
+            services: &["mongodb", "mongod"],
+            level: High,
+            message: "MongoDB is often exposed without authentication",
+        },
+        DangerousServiceBackend {
+            protocol: "tcp",
+            ports: &[9200],
+            services: &["elasticsearch"],
+            level: High,
+            message: "Elasticsearch is often exposed without authentication",
+        },
+        DangerousServiceBackend {
+            protocol: "tcp",
+            ports: &[11211],
+            services: &["memcache", "memcached"],
+            level: High,
+            message: "Memcached has no authentication",
+        },
+        DangerousServiceBackend {
+            protocol: "tcp",
+            ports: &[2375],
+            services: &["docker"],
+            level: Critical,
+            message: "Unencrypted Docker API grants root on the host",
+        },
+        DangerousServiceBackend {
+            protocol: "tcp",
+            ports: &[10250],
+            services: &["kubelet"],
+            level: High,
+            message: "Kubelet API can run commands in containers",
+        },
+        DangerousServiceBackend {
+            protocol: "udp",
+            ports: &[161],
+            services: &["snmp"],
+            level: Medium,
+            message: "SNMP community strings are often left at defaults",
+        },
+        DangerousServiceBackend {
+            protocol: "tcp",
+            ports: &[1433, 3306, 5432],
+            services: &["ms-sql-s", "mysql", "postgresql"],
+            level: Medium,
+            message: "Database reachable from the network",
+        },
+        DangerousServiceBackend {
+            protocol: "tcp",
+            ports: &[1883],
+            services: &["mqtt"],
+            level: Medium,
+            message: "MQTT without TLS exposes device messages",
+        },
+        DangerousServiceBackend {
+            protocol: "udp",
+            ports: &[1900],
+            services: &["upnp", "ssdp"],
+            level: Low,
+            message: "UPnP lets devices open router ports",
+        },
+        DangerousServiceBackend {
+            protocol: "tcp",
+            ports: &[22],
+            services: &["ssh"],
+            level: Low,
+            message: "SSH reachable from the network",
+        },
+    ]
+};
+
+/// Banner content proving a service answered without authentication.
+const UNAUTHENTICATED_BANNERS: &[(&str, &str)] = &[
+    (
+        "redis_version:",
+        "Redis answered INFO without authentication",
+    ),
+    (
+        "\"cluster_name\"",
+        "Elasticsearch answered without authentication",
+    ),
+    (
+        "\"ApiVersion\"",
+        "Docker API answered without authentication",
+    ),
+];
+
+/// Numeric components, `7.4p1` → `[7, 4, 1]`.
+fn version_parts(version: &str) -> Vec<u64> {
+    version
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|part| !part.is_empty())
+        .filter_map(|part| part.parse().ok())
+        .collect()
+}
+
+/// Releases of `product` from `affected_min` to `affected_max`, both
+/// inclusive, carry a known issue. The upper bound covers every release it
+/// prefixes: a max of `7.7` includes `7.7p1`.
+struct VulnerableReleaseBackend {
+    product: &'static str,
+    affected_min: &'static str,
+    affected_max: &'static str,
+    level: PortRiskLevelBackend,
+    message: &'static str,
+}
+
+impl VulnerableReleaseBackend {
+    fn affects(&self, product: &str, version: &str) -> bool {
+        let found = version_parts(version);
+        let max = version_parts(self.affected_max);
+        let prefix = &found[..found.len().min(max.len())];
+        self.product == product && found >= version_parts(self.affected_min) && prefix <= &max[..]
+    }
+}
+
+const VULNERABLE_RELEASES: &[VulnerableReleaseBackend] = {
+    use PortRiskLevelBackend::*;
+    &[
+        VulnerableReleaseBackend {
+            product: "vsftpd",
+            affected_min: "2.3.4",
+            affected_max: "2.3.4",
+            level: Critical,
+            message: "vsFTPd 2.3.4 shipped a backdoor",
+        },
+        VulnerableReleaseBackend {
+            product: "exim",
+            affected_min: "4.87",
+            affected_max: "4.91",
+            level: Critical,
+            message: "Exim 4.87 to 4.91 allows remote command execution (CVE-2019-10149)",
+        },
+        VulnerableReleaseBackend {
+            product: "apache",
+            affected_min: "2.4.49",
+            affected_max: "2.4.50",
+            level: High,
+            message: "Apache 2.4.49 and 2.4.50 allow path traversal (CVE-2021-42013)",
+        },
+        VulnerableReleaseBackend {
+            product: "openssh",
+            affected_min: "0",
+            affected_max: "7.7",
+            level: Medium,
+            message: "OpenSSH through 7.7 allows user enumeration (CVE-2018-15473)",
+        },
+        VulnerableReleaseBackend {
+            product: "nginx",
+            affected_min: "0.6.18",
+            affected_max: "1.20.0",
+            level: Medium,
+            message: "nginx 0.6.18 to 1.20.0 has a resolver overflow (CVE-2021-23017)",
+        },
+    ]
+};
+
+fn service_matches(service: &DangerousServiceBackend, port: &PortInfoBackend) -> bool {
+    let protocol = port.protocol.trim();
+    if !protocol.is_empty() && !protocol.eq_ignore_ascii_case(service.protocol) {
+        return false;
+    }
+    let name = port.service.trim().to_ascii_lowercase();
+    service.ports.contains(&port.port)
+        || (!name.is_empty() && service.services.contains(&name.as_str()))
+}
+
+pub fn assess_port(port: &PortInfoBackend) -> PortRiskBackend {
+    let mut reasons: Vec<(PortRiskLevelBackend, PortRiskSourceBackend, String)> = Vec::new();
+
+    if let Some(service) = DANGEROUS_SERVICES
+        .iter()
+        .find(|service| service_matches(service, port))
+    {
+        reasons.push((
+            service.level,
+            PortRiskSourceBackend::Service,
+            service.message.to_string(),
+        ));
+    }
+
+    if let Some((_, message)) = UNAUTHENTICATED_BANNERS
+        .iter()
+        .find(|(marker, _)| port.banner.contains(marker))
+    {
+        reasons.push((
+            PortRiskLevelBackend::Critical,
+            PortRiskSourceBackend::Banner,
+            message.to_string(),
+        ));
+    }
+    // Without a version there is nothing to compare against.
+    if let Some((product, version)) = fingerprint_banner(&port.banner)
+        .and_then(|found| found.version.map(|version| (found.product, version)))
+    {
+        if let Some(release) = VULNERABLE_RELEASES
+            .iter()
+            .find(|release| release.affects(&product, &version))
+        {
+            reasons.push((
+                release.level,
+                PortRiskSourceBackend::Banner,
+                format!("{}; found {product} {version}", release.message),
+            ));
+        }
+    }
+
+    for vulnerability in &port.vulnerabilities {
+        let (level, score) = match vulnerability.effective_cvss_score() {
+            Some(score) => (
+                PortRiskLevelBackend::from_severity(CvssSeverityBackend::from_score(score)),
+                format!("CVSS {score:.1}"),
+            ),
+            // Someone flagged it; without a score, assume it matters.
+            None => (PortRiskLevelBackend::Medium, "unscored".to_string()),
+        };
+        let name = vulnerability
+            .cve_id
+            .as_deref()
+            .unwrap_or(&vulnerability.name);
+        reasons.push((
+            level,
+            PortRiskSourceBackend::Vulnerability,
+            format!("{name} ({score})"),
+        ));
+    }
+
+    reasons.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
+    let level = reasons
+        .first()
+        .map_or(PortRiskLevelBackend::None, |(level, _, _)| *level);
+    PortRiskBackend {
+        port: port.port,
+        protocol: port.protocol.clone(),
+        level: level.as_str().to_string(),
+        rationale: reasons
+            .into_iter()
+            .map(|(level, source, message)| PortRiskReasonBackend {
+                source: source.as_str().to_string(),
+                level: level.as_str().to_string(),
+                message,
+            })
+            .collect(),
+    }
+}
+
+/// Assess every port, riskiest first, ties by port number.
+pub fn assess_ports(ports: &[PortInfoBackend]) -> Vec<PortRiskBackend> {
+    let mut risks: Vec<PortRiskBackend> = ports.iter().map(assess_port).collect();
+    risks.sort_by(|a, b| b.level().cmp(&a.level()).then(a.port.cmp(&b.port)));
+    risks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::advisor_todos_backend::AdviceTypeBackend;
+    use crate::lanscan_vulnerability_info_backend::VulnerabilityInfoBackend;
+
+    fn port(number: u16, service: &str, banner: &str) -> PortInfoBackend {
+        PortInfoBackend {
+            port: number,
+            protocol: "tcp".to_string(),
+            service: service.to_string(),
+            banner: banner.to_string(),
+            vulnerabilities: vec![],
+        }
+    }
+
+    #[test]
+    fn curated_services_are_rated() {
+        let telnet = assess_port(&port(23, "", ""));
+        assert_eq!(telnet.level(), PortRiskLevelBackend::High);
+        assert_eq!(telnet.rationale[0].source, "service");
+
+        // Matched by service name on a non-standard port.
+        let rdp = assess_port(&port(13389, "ms-wbt-server", ""));
+        assert_eq!(rdp.level(), PortRiskLevelBackend::High);
+
+        let https = assess_port(&port(443, "https", ""));
+        assert_eq!(https.level(), PortRiskLevelBackend::None);
+        assert!(https.rationale.is_empty());
+        assert!(https.todo().is_none());
+    }
+
+    #[test]
+    fn unauthenticated_redis_is_critical() {
+        let redis = assess_port(&port(6379, "redis", "# Server\r\nredis_version:6.0.16\r\n"));
+        assert_eq!(redis.level(), PortRiskLevelBackend::Critical);
+        assert_eq!(redis.rationale.len(), 2);
+        assert_eq!(redis.rationale[0].source, "banner");
+    }
+
+    #[test]
+    fn outdated_releases_raise_the_level() {
+        let ssh = assess_port(&port(22, "ssh", "SSH-2.0-OpenSSH_7.4p1"));
+        assert_eq!(ssh.level(), PortRiskLevelBackend::Medium);
+        assert!(ssh.rationale[0].message.contains("openssh 7.4p1"));
+
+        let patched = assess_port(&port(22, "ssh", "SSH-2.0-OpenSSH_9.6"));
+        assert_eq!(patched.level(), PortRiskLevelBackend::Low);
+
+        let ftp = assess_port(&port(21, "ftp", "220 (vsFTPd 2.3.4)"));
+        assert_eq!(ftp.level(), PortRiskLevelBackend::Critical);
+    }
+
+    #[test]
+    fn releases_match_affected_ranges_only() {
+        let affected = |product: &str, version: &str| {
+            VULNERABLE_RELEASES
+                .iter()
+                .any(|release| release.affects(product, version))
+        };
+        assert!(affected("openssh", "7.7p1"));
+        assert!(affected("openssh", "7.7"));
+        assert!(!affected("openssh", "7.8"));
+        assert!(affected("vsftpd", "2.3.4"));
+        assert!(!affected("vsftpd", "2.3.2"));
+        assert!(!affected("vsftpd", "2.3.5"));
+        assert!(!affected("apache", "2.4.48"));
+        assert!(affected("apache", "2.4.49"));
+        assert!(affected("apache", "2.4.50"));
+        assert!(!affected("apache", "2.4.51"));
+        assert!(!affected("exim", "4.86"));
+        assert!(affected("exim", "4.91"));
+        assert!(!affected("exim", "4.92"));
+        assert!(affected("nginx", "1.20.0"));
+        assert!(!affected("nginx", "1.20.1"));
+
+        let old_ftp = assess_port(&port(21, "ftp", "220 (vsFTPd 2.0.5)"));
+        assert_eq!(old_ftp.level(), PortRiskLevelBackend::Medium);
+    }
+
+    #[test]
+    fn services_match_their_transport() {
+        let mut snmp = port(161, "", "");
+        assert_eq!(assess_port(&snmp).level(), PortRiskLevelBackend::None);
+        snmp.protocol = "UDP".to_string();
+        assert_eq!(assess_port(&snmp).level(), PortRiskLevelBackend::Medium);
+
+        let mut tftp = port(69, "tftp", "");
+        assert_eq!(assess_port(&tftp).level(), PortRiskLevelBackend::None);
+        tftp.protocol = "udp".to_string();
+        assert_eq!(assess_port(&tftp).level(), PortRiskLevelBackend::Medium);
+
+        let mut telnet = port(23, "", "");
+        telnet.protocol = "udp".to_string();
+        assert_eq!(assess_port(&telnet).level(), PortRiskLevelBackend::None);
+    }
+
+    #[test]
+    fn vulnerabilities_are_rated_by_cvss() {
+        let mut web = port(8080, "http", "");
+        let mut critical = VulnerabilityInfoBackend::new("log4shell", "");
+        critical.cve_id = Some("CVE-2021-44228".to_string());
+        critical.cvss_vector = Some("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:C/C:H/I:H/A:H".to_string());
+        web.vulnerabilities = vec![VulnerabilityInfoBackend::new("odd", ""), critical];
+        let risk = assess_port(&web);
+        assert_eq!(risk.level(), PortRiskLevelBackend::Critical);
+        assert_eq!(risk.rationale[0].message, "CVE-2021-44228 (CVSS 10.0)");
+        assert_eq!(risk.rationale[1].message, "odd (unscored)");
+        assert_eq!(risk.rationale[1].level, "medium");
+
+        assert_eq!(
+            risk.details(),
+            AdviceDetailsBackend::NetworkPort {
+                port: 8080,
+                protocol: Some("tcp".to_string()),
+            }
+        );
+        let todo = risk.todo().unwrap();
+        assert_eq!(todo.advice.advice_type, AdviceTypeBackend::NetworkPort);
+        assert_eq!(todo.advice.advice_details, "8080");
+        assert_eq!(todo.priority, AdvicePriorityBackend::Critical);
+    }
+
+    #[test]
+    fn assess_ports_sorts_riskiest_first() {
+        let risks = assess_ports(&[
+            port(443, "https", ""),
+            port(22, "ssh", ""),
+            port(2375, "", ""),
+            port(21, "", ""),
+        ]);
+        let order: Vec<u16> = risks.iter().map(|risk| risk.port).collect();
+        assert_eq!(order, vec![2375, 21, 22, 443]);
+    }
+}
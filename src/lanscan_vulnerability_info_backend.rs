@@ -1,7 +1,181 @@
+use crate::cvss_backend::{CvssSeverityBackend, CvssV3Backend};
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
 
-#[derive(Serialize, Deserialize, Debug, Clone, Ord, Eq, PartialEq, PartialOrd)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct VulnerabilityInfoBackend {
     pub name: String,
     pub description: String,
+    /// `CVE-YYYY-NNNN`.
+    #[serde(default)]
+    pub cve_id: Option<String>,
+    /// CVSS v3.x vector, e.g. `CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H`.
+    #[serde(default)]
+    pub cvss_vector: Option<String>,
+    /// Base score as claimed by the feed. Prefer
+    /// [`VulnerabilityInfoBackend::effective_cvss_score`].
+    #[serde(default)]
+    pub cvss_score: Option<f64>,
+    /// Probability of exploitation in the wild, 0.0 to 1.0 (EPSS).
+    #[serde(default)]
+    pub epss: Option<f64>,
+    /// Affected product and version, as a CPE 2.3 string.
+    #[serde(default)]
+    pub cpe: Option<String>,
+    /// Advisory URLs.
+    #[serde(default)]
+    pub references: Vec<String>,
+}
+
+impl VulnerabilityInfoBackend {
+    pub fn new(name: impl Into<String>, description: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            description: description.into(),
+            cve_id: None,
+            cvss_vector: None,
+            cvss_score: None,
+            epss: None,
+            cpe: None,
+            references: Vec::new(),
+        }
+    }
+
+    /// Base score computed from `cvss_vector`; `None` when absent or invalid.
+    pub fn computed_cvss_score(&self) -> Option<f64> {
+        let vector = self.cvss_vector.as_deref()?;
+        CvssV3Backend::parse(vector)
+            .ok()
+            .map(|cvss| cvss.base_score())
+    }
+
+    /// Computed score when the vector parses, else the claimed score.
+    pub fn effective_cvss_score(&self) -> Option<f64> {
+        self.computed_cvss_score()
+            .or(self.cvss_score.filter(|score| (0.0..=10.0).contains(score)))
+    }
+
+    pub fn severity(&self) -> Option<CvssSeverityBackend> {
+        self.effective_cvss_score()
+            .map(CvssSeverityBackend::from_score)
+    }
+}
+
+// Scores are floats, so ordering and equality are spelled out with
+// `total_cmp`; findings sort and dedup as before.
+fn cmp_score(a: &Option<f64>, b: &Option<f64>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => a.total_cmp(b),
+        _ => a.is_some().cmp(&b.is_some()),
+    }
+}
+
+impl Ord for VulnerabilityInfoBackend {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.name
+            .cmp(&other.name)
+            .then_with(|| self.description.cmp(&other.description))
+            .then_with(|| self.cve_id.cmp(&other.cve_id))
+            .then_with(|| self.cvss_vector.cmp(&other.cvss_vector))
+            .then_with(|| cmp_score(&self.cvss_score, &other.cvss_score))
+            .then_with(|| cmp_score(&self.epss, &other.epss))
+            .then_with(|| self.cpe.cmp(&other.cpe))
+            .then_with(|| self.references.cmp(&other.references))
+    }
+}
+
+impl PartialOrd for VulnerabilityInfoBackend {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for VulnerabilityInfoBackend {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for VulnerabilityInfoBackend {}
+
+// Unset fields are left out so a finding without CVE data prints exactly as
+// before: `DeviceInfoBackend::legacy_uid` hashes this output.
+impl std::fmt::Debug for VulnerabilityInfoBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut debug = f.debug_struct("VulnerabilityInfoBackend");
+        debug
+            .field("name", &self.name)
+            .field("description", &self.description);
+        if let Some(cve_id) = &self.cve_id {
+            debug.field("cve_id", cve_id);
+        }
+        if let Some(cvss_vector) = &self.cvss_vector {
+            debug.field("cvss_vector", cvss_vector);
+        }
+        if let Some(cvss_score) = &self.cvss_score {
+            debug.field("cvss_score", cvss_score);
+        }
+        if let Some(epss) = &self.epss {
+            debug.field("epss", epss);
+        }
+        if let Some(cpe) = &self.cpe {
+            debug.field("cpe", cpe);
+        }
+        if !self.references.is_empty() {
+            debug.field("references", &self.references);
+        }
+        debug.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn old_payload_still_parses() {
+        let vuln: VulnerabilityInfoBackend =
+            serde_json::from_str(r#"{"name":"weak-ssh","description":"Old cipher"}"#).unwrap();
+        assert_eq!(
+            vuln,
+            VulnerabilityInfoBackend::new("weak-ssh", "Old cipher")
+        );
+        assert!(vuln.effective_cvss_score().is_none());
+        assert_eq!(
+            format!("{vuln:?}"),
+            r#"VulnerabilityInfoBackend { name: "weak-ssh", description: "Old cipher" }"#
+        );
+    }
+
+    #[test]
+    fn computed_score_wins_over_claimed_score() {
+        let mut vuln = VulnerabilityInfoBackend::new("CVE-2021-44228", "Log4Shell");
+        vuln.cve_id = Some("CVE-2021-44228".to_string());
+        vuln.cvss_score = Some(5.0);
+        assert_eq!(vuln.effective_cvss_score(), Some(5.0));
+
+        vuln.cvss_vector = Some("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:C/C:H/I:H/A:H".to_string());
+        assert_eq!(vuln.effective_cvss_score(), Some(10.0));
+        assert_eq!(vuln.severity(), Some(CvssSeverityBackend::Critical));
+
+        vuln.cvss_vector = Some("garbage".to_string());
+        assert_eq!(vuln.computed_cvss_score(), None);
+        assert_eq!(vuln.effective_cvss_score(), Some(5.0));
+
+        vuln.cvss_score = Some(42.0);
+        assert_eq!(vuln.effective_cvss_score(), None);
+    }
+
+    #[test]
+    fn structured_fields_round_trip() {
+        let mut vuln = VulnerabilityInfoBackend::new("CVE-2023-1", "x");
+        vuln.cvss_score = Some(7.5);
+        vuln.epss = Some(0.12);
+        vuln.cpe = Some("cpe:2.3:a:openbsd:openssh:8.9:*:*:*:*:*:*:*".to_string());
+        vuln.references = vec!["https://nvd.nist.gov/vuln/detail/CVE-2023-1".to_string()];
+        let json = serde_json::to_string(&vuln).unwrap();
+        let parsed: VulnerabilityInfoBackend = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, vuln);
+        assert!(VulnerabilityInfoBackend::new("CVE-2023-1", "x") < vuln);
+    }
 }
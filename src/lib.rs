@@ -1,22 +1,50 @@
+pub mod advice_details_backend;
+pub mod advisor_generator_backend;
+pub mod advisor_lifecycle_backend;
 pub mod advisor_todos_backend;
 pub mod agent_inventory_backend;
 pub mod agentic_backend;
 pub mod agentic_dismissal_report_backend;
 pub mod ai_whitelist_backend;
+pub mod cvss_backend;
 pub mod detail_backend;
+pub mod feedback_bundle_backend;
 pub mod feedback_info_backend;
+pub mod feedback_scrub_backend;
+pub mod glob_backend;
 pub mod helper_state_backend;
 pub mod history_backend;
+pub mod lanscan_banner_backend;
+pub mod lanscan_device_classifier_backend;
 pub mod lanscan_device_info_backend;
+pub mod lanscan_dislike_aggregate_backend;
 pub mod lanscan_dislike_device_info_backend;
+pub mod lanscan_network_scan_backend;
 pub mod lanscan_port_info_backend;
+pub mod lanscan_port_risk_backend;
 pub mod lanscan_vulnerability_info_backend;
 pub mod order_backend;
+pub mod order_timeline_backend;
 pub mod order_type_backend;
 pub mod policy_backend;
 pub mod pwned_backend;
+pub mod pwned_criticality_backend;
+pub mod pwned_data_class_backend;
+pub mod pwned_merge_backend;
+pub mod remediation_analytics_backend;
+pub mod remediation_plan_backend;
 pub mod score_backend;
 pub mod session_info_backend;
+pub mod session_rule_backend;
+pub mod session_summary_backend;
+pub mod session_typed_backend;
 pub mod signature;
+pub mod stable_hash_backend;
+#[cfg(test)]
+mod test_fixtures;
 pub mod threat_backend;
+pub mod threat_lint_backend;
+pub mod threat_query_backend;
+pub mod triage_backend;
+pub mod uid_backend;
 pub mod version;
@@ -0,0 +1,416 @@
+//! Per-metric reading of an [`OrderHistoryBackend`].
+//!
+//! The history a client ships is a flat list of order results in whatever
+//! order the client kept them. [`build_order_timeline`] groups it by metric,
+//! orders each group by parsed timestamp, and derives the state transitions a
+//! human would read off it: remediated, rolled back, remediation failed, and
+//! remediation applied but never validated. Per-metric remediation statistics
+//! fall out of the same walk.
+//!
+//! Capture orders only observe a metric; they never move it, so they appear in
+//! `entries` and open a remediation episode but produce no transition.
+
+use crate::history_backend::OrderHistoryBackend;
+use crate::order_backend::MetricOrderResultBackend;
+use crate::order_type_backend::MetricOrderTypeBackend;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// What one remediate or rollback order did to its metric.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MetricTransitionBackend {
+    /// Remediation succeeded and the follow-up capture confirmed it.
+    Remediated,
+    /// Remediation reported success but was never validated.
+    RemediationUnvalidated,
+    /// Remediation ran and failed.
+    RemediationFailed,
+    /// Rollback succeeded; the metric is back to its pre-remediation state.
+    RolledBack,
+    /// Rollback ran and failed.
+    RollbackFailed,
+}
+
+impl MetricTransitionBackend {
+    pub fn from_order(order: &MetricOrderResultBackend) -> Option<Self> {
+        match (&order.ordertype, order.success, order.validated) {
+            (MetricOrderTypeBackend::Capture, _, _) => None,
+            (MetricOrderTypeBackend::Remediate, true, true) => Some(Self::Remediated),
+            (MetricOrderTypeBackend::Remediate, true, false) => Some(Self::RemediationUnvalidated),
+            (MetricOrderTypeBackend::Remediate, false, _) => Some(Self::RemediationFailed),
+            (MetricOrderTypeBackend::Rollback, true, _) => Some(Self::RolledBack),
+            (MetricOrderTypeBackend::Rollback, false, _) => Some(Self::RollbackFailed),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, PartialOrd)]
+pub struct MetricTransitionEventBackend {
+    pub timestamp: DateTime<Utc>,
+    pub transition: MetricTransitionBackend,
+}
+
+/// Everything the history says about one metric.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, PartialOrd)]
+pub struct MetricTimelineBackend {
+    pub metricname: String,
+    /// Orders with a parseable timestamp, oldest first. Ties keep their
+    /// original relative order.
+    pub entries: Vec<MetricOrderResultBackend>,
+    /// Orders whose timestamp could not be parsed. Excluded from every figure
+    /// below rather than guessed into place.
+    pub unparsed: Vec<MetricOrderResultBackend>,
+    pub transitions: Vec<MetricTransitionEventBackend>,
+    pub remediation_attempts: u32,
+    /// Remediations that reported success, validated or not.
+    pub remediation_successes: u32,
+    pub remediation_validated: u32,
+    pub rollbacks: u32,
+    /// `remediation_successes / remediation_attempts`; `None` with no attempt.
+    pub remediation_success_rate: Option<f64>,
+    /// Mean seconds from the start of a remediation episode to the successful
+    /// remediation closing it. An episode opens on the first order after the
+    /// previous successful remediation (or rollback, or the start of history),
+    /// so a failed first try and the capture that preceded it both count. A
+    /// successful remediation with no earlier order in its episode has no
+    /// known start and is left out. `None` when no episode ever closed.
+    pub mean_time_to_remediate_secs: Option<f64>,
+    /// At least one remediation succeeded and none was ever validated.
+    pub never_validated: bool,
+}
+
+impl MetricTimelineBackend {
+    /// Last transition, i.e. where the metric was left.
+    pub fn last_transition(&self) -> Option<MetricTransitionBackend> {
+        self.transitions.last().map(|event| event.transition)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, PartialOrd)]
+pub struct OrderTimelineBackend {
+    /// One timeline per metric name, sorted by name.
+    pub metrics: Vec<MetricTimelineBackend>,
+}
+
+impl OrderTimelineBackend {
+    pub fn metric(&self, metricname: &str) -> Option<&MetricTimelineBackend> {
+        self.metrics
+            .binary_search_by(|timeline| timeline.metricname.as_str().cmp(metricname))
+            .ok()
+            .map(|index| &self.metrics[index])
+    }
+}
+
+/// Parse an order timestamp. Clients write RFC3339; older ones wrote a naive
+/// `YYYY-MM-DD HH:MM:SS`, which is read as UTC.
+pub fn parse_order_timestamp(timestamp: &str) -> Option<DateTime<Utc>> {
+    let timestamp = timestamp.trim();
+    if let Ok(parsed) = DateTime::parse_from_rfc3339(timestamp) {
+        return Some(parsed.with_timezone(&Utc));
+    }
+    ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(timestamp, format).ok())
+        .map(|naive| naive.and_utc())
+}
+
+pub fn build_order_timeline(history: &OrderHistoryBackend) -> OrderTimelineBackend {
+    let mut grouped: BTreeMap<&str, Vec<&MetricOrderResultBackend>> = BTreeMap::new();
+    for order in &history.history {
+        grouped.entry(&order.metricname).or_default().push(order);
+    }
+    OrderTimelineBackend {
+        metrics: grouped
+            .into_iter()
+            .map(|(metricname, orders)| build_metric_timeline(metricname, orders))
+            .collect(),
+    }
+}
+
+fn build_metric_timeline(
+    metricname: &str,
+    orders: Vec<&MetricOrderResultBackend>,
+) -> MetricTimelineBackend {
+    let mut dated = Vec::new();
+    let mut unparsed = Vec::new();
+    for order in orders {
+        match parse_order_timestamp(&order.timestamp) {
+            Some(timestamp) => dated.push((timestamp, order)),
+            None => unparsed.push(order.clone()),
+        }
+    }
+    // Stable: equal timestamps keep the order the client recorded them in.
+    dated.sort_by_key(|(timestamp, _)| *timestamp);
+
+    let mut transitions = Vec::new();
+    let mut remediation_attempts = 0;
+    let mut remediation_successes = 0;
+    let mut remediation_validated = 0;
+    let mut rollbacks = 0;
+    let mut episode_start: Option<DateTime<Utc>> = None;
+    let mut remediation_durations = Vec::new();
+
+    for (timestamp, order) in &dated {
+        let transition = MetricTransitionBackend::from_order(order);
+        let closes_episode = matches!(
+            transition,
+            Some(
+                MetricTransitionBackend::Remediated
+                    | MetricTransitionBackend::RemediationUnvalidated
+            )
+        );
+        if !closes_episode {
+            episode_start.get_or_insert(*timestamp);
+        }
+        let Some(transition) = transition else {
+            continue;
+        };
+        match transition {
+            MetricTransitionBackend::Remediated
+            | MetricTransitionBackend::RemediationUnvalidated => {
+                remediation_attempts += 1;
+                remediation_successes += 1;
+                if transition == MetricTransitionBackend::Remediated {
+                    remediation_validated += 1;
+                }
+                if let Some(start) = episode_start.take() {
+                    remediation_durations.push((*timestamp - start).num_milliseconds() as f64);
+                }
+            }
+            MetricTransitionBackend::RemediationFailed => remediation_attempts += 1,
+            MetricTransitionBackend::RolledBack => {
+                rollbacks += 1;
+                // The threat is back; the next episode starts now.
+                episode_start = Some(*timestamp);
+            }
+            MetricTransitionBackend::RollbackFailed => rollbacks += 1,
+        }
+        transitions.push(MetricTransitionEventBackend {
+            timestamp: *timestamp,
+            transition,
+        });
+    }
+
+    let remediation_success_rate = (remediation_attempts > 0)
+        .then(|| remediation_successes as f64 / remediation_attempts as f64);
+    let mean_time_to_remediate_secs = (!remediation_durations.is_empty()).then(|| {
+        remediation_durations.iter().sum::<f64>() / remediation_durations.len() as f64 / 1000.0
+    });
+
+    MetricTimelineBackend {
+        metricname: metricname.to_string(),
+        entries: dated.into_iter().map(|(_, order)| order.clone()).collect(),
+        unparsed,
+        transitions,
+        remediation_attempts,
+        remediation_successes,
+        remediation_validated,
+        rollbacks,
+        remediation_success_rate,
+        mean_time_to_remediate_secs,
+        never_validated: remediation_successes > 0 && remediation_validated == 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(
+        metricname: &str,
+        ordertype: MetricOrderTypeBackend,
+        timestamp: &str,
+        success: bool,
+        validated: bool,
+    ) -> MetricOrderResultBackend {
+        MetricOrderResultBackend {
+            metricname: metricname.to_string(),
+            ordertype,
+            timestamp: timestamp.to_string(),
+            success,
+            validated,
+        }
+    }
+
+    #[test]
+    fn groups_by_metric_and_sorts_by_parsed_timestamp() {
+        let history = OrderHistoryBackend {
+            history: vec![
+                order(
+                    "b",
+                    MetricOrderTypeBackend::Capture,
+                    "2026-01-01T00:00:00Z",
+                    true,
+                    false,
+                ),
+                order(
+                    "a",
+                    MetricOrderTypeBackend::Remediate,
+                    "2026-01-01T10:00:00+02:00",
+                    true,
+                    true,
+                ),
+                order(
+                    "a",
+                    MetricOrderTypeBackend::Capture,
+                    "2026-01-01 07:00:00",
+                    true,
+                    false,
+                ),
+            ],
+        };
+        let timeline = build_order_timeline(&history);
+        let names: Vec<&str> = timeline
+            .metrics
+            .iter()
+            .map(|m| m.metricname.as_str())
+            .collect();
+        assert_eq!(names, vec!["a", "b"]);
+
+        let a = timeline.metric("a").unwrap();
+        assert_eq!(a.entries[0].ordertype, MetricOrderTypeBackend::Capture);
+        assert_eq!(a.entries[1].ordertype, MetricOrderTypeBackend::Remediate);
+        // 07:00Z capture to 08:00Z remediation.
+        assert_eq!(a.mean_time_to_remediate_secs, Some(3600.0));
+        assert_eq!(
+            a.last_transition(),
+            Some(MetricTransitionBackend::Remediated)
+        );
+        assert!(timeline.metric("missing").is_none());
+    }
+
+    #[test]
+    fn derives_transitions_and_rates() {
+        let history = OrderHistoryBackend {
+            history: vec![
+                order(
+                    "fw",
+                    MetricOrderTypeBackend::Remediate,
+                    "2026-01-01T00:00:00Z",
+                    false,
+                    false,
+                ),
+                order(
+                    "fw",
+                    MetricOrderTypeBackend::Remediate,
+                    "2026-01-01T00:10:00Z",
+                    true,
+                    true,
+                ),
+                order(
+                    "fw",
+                    MetricOrderTypeBackend::Rollback,
+                    "2026-01-02T00:00:00Z",
+                    true,
+                    false,
+                ),
+                order(
+                    "fw",
+                    MetricOrderTypeBackend::Remediate,
+                    "2026-01-02T00:30:00Z",
+                    true,
+                    false,
+                ),
+            ],
+        };
+        let fw = build_order_timeline(&history).metrics.remove(0);
+        let transitions: Vec<MetricTransitionBackend> =
+            fw.transitions.iter().map(|e| e.transition).collect();
+        assert_eq!(
+            transitions,
+            vec![
+                MetricTransitionBackend::RemediationFailed,
+                MetricTransitionBackend::Remediated,
+                MetricTransitionBackend::RolledBack,
+                MetricTransitionBackend::RemediationUnvalidated,
+            ]
+        );
+        assert_eq!(fw.remediation_attempts, 3);
+        assert_eq!(fw.remediation_successes, 2);
+        assert_eq!(fw.remediation_validated, 1);
+        assert_eq!(fw.rollbacks, 1);
+        assert_eq!(fw.remediation_success_rate, Some(2.0 / 3.0));
+        // Episodes of 600s and 1800s.
+        assert_eq!(fw.mean_time_to_remediate_secs, Some(1200.0));
+        assert!(!fw.never_validated);
+    }
+
+    #[test]
+    fn flags_remediations_never_validated() {
+        let history = OrderHistoryBackend {
+            history: vec![order(
+                "ssh",
+                MetricOrderTypeBackend::Remediate,
+                "2026-01-01T00:00:00Z",
+                true,
+                false,
+            )],
+        };
+        let ssh = &build_order_timeline(&history).metrics[0];
+        assert!(ssh.never_validated);
+        // Nothing came before the remediation, so there is no episode to time.
+        assert_eq!(ssh.mean_time_to_remediate_secs, None);
+    }
+
+    #[test]
+    fn back_to_back_remediations_time_only_opened_episodes() {
+        let history = OrderHistoryBackend {
+            history: vec![
+                order(
+                    "fw",
+                    MetricOrderTypeBackend::Capture,
+                    "2026-01-01T00:00:00Z",
+                    true,
+                    false,
+                ),
+                order(
+                    "fw",
+                    MetricOrderTypeBackend::Remediate,
+                    "2026-01-01T00:05:00Z",
+                    true,
+                    true,
+                ),
+                order(
+                    "fw",
+                    MetricOrderTypeBackend::Remediate,
+                    "2026-01-01T00:06:00Z",
+                    true,
+                    true,
+                ),
+            ],
+        };
+        let fw = &build_order_timeline(&history).metrics[0];
+        assert_eq!(fw.remediation_successes, 2);
+        // Only the capture-opened episode counts; the second remediation had no start.
+        assert_eq!(fw.mean_time_to_remediate_secs, Some(300.0));
+    }
+
+    #[test]
+    fn unparsed_timestamps_are_set_aside() {
+        let history = OrderHistoryBackend {
+            history: vec![
+                order(
+                    "fw",
+                    MetricOrderTypeBackend::Remediate,
+                    "yesterday",
+                    true,
+                    true,
+                ),
+                order(
+                    "fw",
+                    MetricOrderTypeBackend::Capture,
+                    "2026-01-01T00:00:00Z",
+                    true,
+                    false,
+                ),
+            ],
+        };
+        let fw = &build_order_timeline(&history).metrics[0];
+        assert_eq!(fw.entries.len(), 1);
+        assert_eq!(fw.unparsed.len(), 1);
+        assert_eq!(fw.remediation_attempts, 0);
+        assert_eq!(fw.remediation_success_rate, None);
+        assert_eq!(fw.mean_time_to_remediate_secs, None);
+    }
+}
@@ -1,4 +1,5 @@
 use blake3::Hasher;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 
@@ -41,6 +42,15 @@ pub struct BreachDetailBackend {
 
 impl BreachDetailBackend {
     pub fn uid(&self, language: &str) -> String {
+        let mut hasher = crate::uid_backend::uid_hasher("breach_detail", language);
+        hasher.write_field("name", &self.name);
+        // Description has a possibility of change, so we include it
+        hasher.write_field("description", &self.description);
+        hasher.finish()
+    }
+
+    /// Scheme 1 uid, kept to rekey caches -- see [`crate::uid_backend`].
+    pub fn legacy_uid(&self, language: &str) -> String {
         let mut hasher = Hasher::new();
         hasher.update(language.as_bytes());
         hasher.update(self.name.as_bytes());
@@ -48,6 +58,16 @@ impl BreachDetailBackend {
         hasher.update(self.description.as_bytes());
         hasher.finalize().to_hex().to_string()
     }
+
+    /// Criticality under the default rule table -- see
+    /// [`crate::pwned_criticality_backend`].
+    pub fn derive_criticality(
+        &self,
+        now: DateTime<Utc>,
+    ) -> crate::pwned_criticality_backend::PwnedCriticalityVerdictBackend {
+        crate::pwned_criticality_backend::PwnedCriticalityRulesBackend::default()
+            .classify(self, now)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Ord, PartialOrd, Eq, PartialEq)]
@@ -59,6 +79,13 @@ pub struct BreachInfoBackend {
 
 impl BreachInfoBackend {
     pub fn uid(&self, language: &str) -> String {
+        let mut hasher = crate::uid_backend::uid_hasher("breach_info", language);
+        hasher.write_field("name", &self.name);
+        hasher.finish()
+    }
+
+    /// Scheme 1 uid, kept to rekey caches -- see [`crate::uid_backend`].
+    pub fn legacy_uid(&self, language: &str) -> String {
         let mut hasher = Hasher::new();
         hasher.update(language.as_bytes());
         hasher.update(self.name.as_bytes());
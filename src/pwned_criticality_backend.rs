@@ -0,0 +1,275 @@
+//! Deterministic [`PwnedCriticalityBackend`] for a breach.
+//!
+//! `BreachDetailBackend.criticality` used to be whatever its producer decided,
+//! so two clients could disagree about the same breach. Every input the
+//! decision needs travels on the breach itself (`data_classes`, `is_verified`,
+//! `is_sensitive`, `is_stealer_log`, `breachdate`, `count`), so it is derived
+//! here from an ordered rule table instead: the first rule whose every
+//! condition holds sets the criticality, and the verdict names that rule.
+//!
+//! The table is serializable so the Hub can ship the one it uses, and clients
+//! classify with exactly the same rules.
+
+use crate::pwned_backend::{BreachDetailBackend, PwnedCriticalityBackend};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Rule name reported when no rule matched.
+pub const FALLBACK_CRITICALITY_RULE: &str = "fallback";
+
+/// One row of the rule table. Unset conditions always hold; set ones are
+/// ANDed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PwnedCriticalityRuleBackend {
+    /// Reported back in the verdict.
+    pub name: String,
+    pub criticality: PwnedCriticalityBackend,
+    /// Holds when the breach exposes at least one of these data classes
+    /// (case-insensitive). Empty means no data-class condition.
+    pub any_data_classes: Vec<String>,
+    pub is_stealer_log: Option<bool>,
+    pub is_verified: Option<bool>,
+    pub is_sensitive: Option<bool>,
+    /// Holds when the breach is at most this many days old. A breach whose
+    /// date does not parse never satisfies it.
+    pub max_age_days: Option<i64>,
+    /// Holds when at least this many accounts were exposed.
+    pub min_count: Option<u64>,
+}
+
+impl PwnedCriticalityRuleBackend {
+    pub fn new(name: impl Into<String>, criticality: PwnedCriticalityBackend) -> Self {
+        Self {
+            name: name.into(),
+            criticality,
+            any_data_classes: Vec::new(),
+            is_stealer_log: None,
+            is_verified: None,
+            is_sensitive: None,
+            max_age_days: None,
+            min_count: None,
+        }
+    }
+
+    pub fn with_any_data_classes(mut self, classes: &[&str]) -> Self {
+        self.any_data_classes = classes.iter().map(|c| c.to_string()).collect();
+        self
+    }
+
+    pub fn with_stealer_log(mut self, value: bool) -> Self {
+        self.is_stealer_log = Some(value);
+        self
+    }
+
+    pub fn with_verified(mut self, value: bool) -> Self {
+        self.is_verified = Some(value);
+        self
+    }
+
+    pub fn with_sensitive(mut self, value: bool) -> Self {
+        self.is_sensitive = Some(value);
+        self
+    }
+
+    pub fn with_max_age_days(mut self, days: i64) -> Self {
+        self.max_age_days = Some(days);
+        self
+    }
+
+    pub fn with_min_count(mut self, count: u64) -> Self {
+        self.min_count = Some(count);
+        self
+    }
+
+    pub fn matches(&self, breach: &BreachDetailBackend, now: DateTime<Utc>) -> bool {
+        (self.any_data_classes.is_empty()
+            || breach.data_classes.iter().any(|class| {
+                self.any_data_classes
+                    .iter()
+                    .any(|wanted| wanted.eq_ignore_ascii_case(class.trim()))
+            }))
+            && self
+                .is_stealer_log
+                .is_none_or(|v| v == breach.is_stealer_log)
+            && self.is_verified.is_none_or(|v| v == breach.is_verified)
+            && self.is_sensitive.is_none_or(|v| v == breach.is_sensitive)
+            && self.min_count.is_none_or(|min| breach.count >= min)
+            && self.max_age_days.is_none_or(|max| {
+                breach_age_days(&breach.breachdate, now).is_some_and(|age| age <= max)
+            })
+    }
+}
+
+/// Ordered rule table. Earlier rules win.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PwnedCriticalityRulesBackend {
+    pub rules: Vec<PwnedCriticalityRuleBackend>,
+    /// Criticality when no rule matches.
+    pub fallback: PwnedCriticalityBackend,
+}
+
+impl Default for PwnedCriticalityRulesBackend {
+    /// The table clients and the Hub use unless configured otherwise.
+    fn default() -> Self {
+        use PwnedCriticalityBackend::*;
+        Self {
+            rules: vec![
+                // Infostealer dumps carry live session material.
+                PwnedCriticalityRuleBackend::new("stealer_log", High).with_stealer_log(true),
+                PwnedCriticalityRuleBackend::new("recent_passwords", High)
+                    .with_any_data_classes(&["Passwords"])
+                    .with_max_age_days(730),
+                PwnedCriticalityRuleBackend::new("financial", High).with_any_data_classes(&[
+                    "Credit cards",
+                    "Bank account numbers",
+                    "Partial credit card data",
+                    "Social security numbers",
+                    "Government issued IDs",
+                ]),
+                PwnedCriticalityRuleBackend::new("passwords", Medium).with_any_data_classes(&[
+                    "Passwords",
+                    "Password hints",
+                    "Security questions and answers",
+                ]),
+                PwnedCriticalityRuleBackend::new("sensitive", Medium).with_sensitive(true),
+                PwnedCriticalityRuleBackend::new("auth_tokens", Medium)
+                    .with_any_data_classes(&["Auth tokens", "Private messages"]),
+            ],
+            fallback: Low,
+        }
+    }
+}
+
+/// Outcome of a classification, with the rule that produced it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PwnedCriticalityVerdictBackend {
+    pub criticality: PwnedCriticalityBackend,
+    /// Name of the matching rule, or [`FALLBACK_CRITICALITY_RULE`].
+    pub rule: String,
+}
+
+impl PwnedCriticalityRulesBackend {
+    pub fn classify(
+        &self,
+        breach: &BreachDetailBackend,
+        now: DateTime<Utc>,
+    ) -> PwnedCriticalityVerdictBackend {
+        match self.rules.iter().find(|rule| rule.matches(breach, now)) {
+            Some(rule) => PwnedCriticalityVerdictBackend {
+                criticality: rule.criticality.clone(),
+                rule: rule.name.clone(),
+            },
+            None => PwnedCriticalityVerdictBackend {
+                criticality: self.fallback.clone(),
+                rule: FALLBACK_CRITICALITY_RULE.to_string(),
+            },
+        }
+    }
+}
+
+/// Whole days between `breachdate` and `now`. Accepts the `YYYY-MM-DD` form
+/// breach feeds use, or RFC3339.
+pub fn breach_age_days(breachdate: &str, now: DateTime<Utc>) -> Option<i64> {
+    let breachdate = breachdate.trim();
+    let date = NaiveDate::parse_from_str(breachdate, "%Y-%m-%d")
+        .ok()
+        .or_else(|| {
+            DateTime::parse_from_rfc3339(breachdate)
+                .ok()
+                .map(|parsed| parsed.with_timezone(&Utc).date_naive())
+        })?;
+    Some((now.date_naive() - date).num_days())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures as fixtures;
+
+    fn breach(data_classes: &[&str], breachdate: &str) -> BreachDetailBackend {
+        BreachDetailBackend {
+            breachdate: breachdate.to_string(),
+            data_classes: data_classes.iter().map(|c| c.to_string()).collect(),
+            ..fixtures::breach("Example")
+        }
+    }
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2026-10-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn stealer_log_is_always_high() {
+        let mut b = breach(&["Email addresses"], "2010-01-01");
+        b.is_stealer_log = true;
+        let verdict = b.derive_criticality(now());
+        assert_eq!(verdict.criticality, PwnedCriticalityBackend::High);
+        assert_eq!(verdict.rule, "stealer_log");
+    }
+
+    #[test]
+    fn password_age_decides_between_high_and_medium() {
+        let recent = breach(&["Email addresses", "passwords"], "2025-06-01");
+        let verdict = recent.derive_criticality(now());
+        assert_eq!(verdict.criticality, PwnedCriticalityBackend::High);
+        assert_eq!(verdict.rule, "recent_passwords");
+
+        let old = breach(&["Passwords"], "2015-06-01");
+        let verdict = old.derive_criticality(now());
+        assert_eq!(verdict.criticality, PwnedCriticalityBackend::Medium);
+        assert_eq!(verdict.rule, "passwords");
+
+        // No date, no recency: falls through to the age-free rule.
+        let undated = breach(&["Passwords"], "unknown");
+        assert_eq!(undated.derive_criticality(now()).rule, "passwords");
+    }
+
+    #[test]
+    fn nothing_matching_falls_back() {
+        let verdict = breach(&["Email addresses"], "2025-06-01").derive_criticality(now());
+        assert_eq!(verdict.criticality, PwnedCriticalityBackend::Low);
+        assert_eq!(verdict.rule, FALLBACK_CRITICALITY_RULE);
+    }
+
+    #[test]
+    fn custom_table_is_honoured_in_order() {
+        let rules = PwnedCriticalityRulesBackend {
+            rules: vec![
+                PwnedCriticalityRuleBackend::new("unverified", PwnedCriticalityBackend::Low)
+                    .with_verified(false),
+                PwnedCriticalityRuleBackend::new("huge", PwnedCriticalityBackend::Medium)
+                    .with_min_count(1_000_000),
+            ],
+            fallback: PwnedCriticalityBackend::Unknown,
+        };
+        let mut b = breach(&["Passwords"], "2025-06-01");
+        b.count = 5_000_000;
+        assert_eq!(rules.classify(&b, now()).rule, "huge");
+        b.is_verified = false;
+        assert_eq!(rules.classify(&b, now()).rule, "unverified");
+        b.is_verified = true;
+        b.count = 10;
+        assert_eq!(
+            rules.classify(&b, now()).criticality,
+            PwnedCriticalityBackend::Unknown
+        );
+    }
+
+    #[test]
+    fn rule_table_round_trips() {
+        let rules = PwnedCriticalityRulesBackend::default();
+        let json = serde_json::to_string(&rules).expect("serialize");
+        let parsed: PwnedCriticalityRulesBackend =
+            serde_json::from_str(&json).expect("deserialize");
+        assert_eq!(parsed, rules);
+    }
+
+    #[test]
+    fn breach_age_accepts_feed_and_rfc3339_dates() {
+        assert_eq!(breach_age_days("2026-09-30", now()), Some(1));
+        assert_eq!(breach_age_days("2026-09-30T12:00:00Z", now()), Some(1));
+        assert_eq!(breach_age_days("", now()), None);
+    }
+}
@@ -0,0 +1,282 @@
+//! Normalized vocabulary for breach data classes.
+//!
+//! `BreachDetailBackend.data_classes` are free strings copied from upstream
+//! feeds ("Passwords", "Email addresses", …). [`BreachDataClassBackend`] is a
+//! closed set of categories with a risk weight each, and
+//! [`normalize_data_classes`] maps a breach's labels onto it, reporting every
+//! label it could not place so the mapping table can grow.
+
+use crate::pwned_backend::BreachDetailBackend;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum BreachDataClassBackend {
+    /// Passwords, hints, security answers, usernames.
+    Credentials,
+    /// Session or API tokens that authenticate without a password.
+    AuthTokens,
+    /// Card, bank, and transaction data.
+    Financial,
+    /// Social security, passport, and other government identifiers.
+    GovernmentId,
+    /// Medical and insurance data.
+    Health,
+    /// Message bodies and chat logs.
+    Communications,
+    /// Names, birth dates, demographics.
+    Identity,
+    /// Email addresses, phone numbers, postal addresses.
+    Contact,
+    /// Geographic location and time zone.
+    Location,
+    /// IP and MAC addresses.
+    Network,
+    /// Employer, job title, education.
+    Employment,
+    /// Device identifiers and user agents.
+    Device,
+    /// Social profiles and site activity.
+    Social,
+}
+
+impl BreachDataClassBackend {
+    pub const ALL: [Self; 13] = [
+        Self::Credentials,
+        Self::AuthTokens,
+        Self::Financial,
+        Self::GovernmentId,
+        Self::Health,
+        Self::Communications,
+        Self::Identity,
+        Self::Contact,
+        Self::Location,
+        Self::Network,
+        Self::Employment,
+        Self::Device,
+        Self::Social,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Credentials => "credentials",
+            Self::AuthTokens => "auth_tokens",
+            Self::Financial => "financial",
+            Self::GovernmentId => "government_id",
+            Self::Health => "health",
+            Self::Communications => "communications",
+            Self::Identity => "identity",
+            Self::Contact => "contact",
+            Self::Location => "location",
+            Self::Network => "network",
+            Self::Employment => "employment",
+            Self::Device => "device",
+            Self::Social => "social",
+        }
+    }
+
+    pub fn from_str_opt(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|class| class.as_str() == value)
+    }
+
+    /// Relative harm of exposure, 1 (nuisance) to 10 (account takeover).
+    pub fn risk_weight(&self) -> u8 {
+        match self {
+            Self::Credentials => 10,
+            Self::AuthTokens => 9,
+            Self::Financial => 9,
+            Self::GovernmentId => 9,
+            Self::Health => 8,
+            Self::Communications => 6,
+            Self::Identity => 5,
+            Self::Location => 4,
+            Self::Contact => 3,
+            Self::Network => 3,
+            Self::Employment => 3,
+            Self::Device => 2,
+            Self::Social => 2,
+        }
+    }
+
+    /// Map one upstream label (case-insensitive). Already-normalized tokens
+    /// map to themselves, so normalizing twice is harmless.
+    pub fn from_upstream_label(label: &str) -> Option<Self> {
+        let label = label.trim().to_lowercase();
+        Self::from_str_opt(&label).or_else(|| {
+            UPSTREAM_LABELS
+                .iter()
+                .find(|(known, _)| *known == label)
+                .map(|(_, class)| *class)
+        })
+    }
+}
+
+/// Known upstream labels, lowercased.
+const UPSTREAM_LABELS: &[(&str, BreachDataClassBackend)] = {
+    use BreachDataClassBackend::*;
+    &[
+        ("passwords", Credentials),
+        ("password hints", Credentials),
+        ("historical passwords", Credentials),
+        ("security questions and answers", Credentials),
+        ("usernames", Credentials),
+        ("pins", Credentials),
+        ("auth tokens", AuthTokens),
+        ("session tokens", AuthTokens),
+        ("api keys", AuthTokens),
+        ("credit cards", Financial),
+        ("credit card cvv", Financial),
+        ("partial credit card data", Financial),
+        ("bank account numbers", Financial),
+        ("financial transactions", Financial),
+        ("financial investments", Financial),
+        ("payment histories", Financial),
+        ("payment methods", Financial),
+        ("purchases", Financial),
+        ("income levels", Financial),
+        ("credit status information", Financial),
+        ("social security numbers", GovernmentId),
+        ("government issued ids", GovernmentId),
+        ("passport numbers", GovernmentId),
+        ("driver's licenses", GovernmentId),
+        ("tax file numbers", GovernmentId),
+        ("national identification numbers", GovernmentId),
+        ("health insurance information", Health),
+        ("medical conditions", Health),
+        ("personal health data", Health),
+        ("drug habits", Health),
+        ("private messages", Communications),
+        ("chat logs", Communications),
+        ("email messages", Communications),
+        ("sms messages", Communications),
+        ("names", Identity),
+        ("dates of birth", Identity),
+        ("genders", Identity),
+        ("ages", Identity),
+        ("ethnicities", Identity),
+        ("marital statuses", Identity),
+        ("nationalities", Identity),
+        ("profile photos", Identity),
+        ("spoken languages", Identity),
+        ("email addresses", Contact),
+        ("phone numbers", Contact),
+        ("physical addresses", Contact),
+        ("geographic locations", Location),
+        ("time zones", Location),
+        ("gps coordinates", Location),
+        ("ip addresses", Network),
+        ("mac addresses", Network),
+        ("employers", Employment),
+        ("job titles", Employment),
+        ("occupations", Employment),
+        ("education levels", Employment),
+        ("device information", Device),
+        ("device serial numbers", Device),
+        ("imei numbers", Device),
+        ("browser user agent details", Device),
+        ("social media profiles", Social),
+        ("website activity", Social),
+        ("instant messenger identities", Social),
+    ]
+};
+
+/// A breach's data classes, normalized.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct BreachDataClassesReportBackend {
+    /// Normalized categories, see [`BreachDataClassBackend`]. Sorted by
+    /// descending risk weight, deduplicated.
+    pub classes: Vec<String>,
+    /// Upstream labels with no mapping, as received (trimmed), deduplicated.
+    pub unmapped: Vec<String>,
+    /// Highest risk weight among `classes`; 0 when none mapped.
+    pub max_risk_weight: u8,
+}
+
+impl BreachDataClassesReportBackend {
+    pub fn contains(&self, class: BreachDataClassBackend) -> bool {
+        self.classes.iter().any(|c| c == class.as_str())
+    }
+}
+
+/// Normalize upstream labels.
+pub fn normalize_data_class_labels<S: AsRef<str>>(labels: &[S]) -> BreachDataClassesReportBackend {
+    let mut classes: Vec<BreachDataClassBackend> = Vec::new();
+    let mut unmapped: Vec<String> = Vec::new();
+    for label in labels {
+        let label = label.as_ref().trim();
+        if label.is_empty() {
+            continue;
+        }
+        match BreachDataClassBackend::from_upstream_label(label) {
+            Some(class) => classes.push(class),
+            None => {
+                if !unmapped.iter().any(|u| u.eq_ignore_ascii_case(label)) {
+                    unmapped.push(label.to_string());
+                }
+            }
+        }
+    }
+    classes.sort_by(|a, b| b.risk_weight().cmp(&a.risk_weight()).then(a.cmp(b)));
+    classes.dedup();
+    BreachDataClassesReportBackend {
+        max_risk_weight: classes.first().map_or(0, |class| class.risk_weight()),
+        classes: classes
+            .iter()
+            .map(|class| class.as_str().to_string())
+            .collect(),
+        unmapped,
+    }
+}
+
+/// Normalize `breach.data_classes`. `short_data_classes` are display
+/// abbreviations of the same labels and are not consulted.
+pub fn normalize_data_classes(breach: &BreachDetailBackend) -> BreachDataClassesReportBackend {
+    normalize_data_class_labels(&breach.data_classes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_known_labels_and_reports_unknown_ones() {
+        let report = normalize_data_class_labels(&[
+            "Email addresses",
+            "PASSWORDS",
+            " Password hints ",
+            "Names",
+            "Favourite colours",
+            "favourite colours",
+            "",
+        ]);
+        assert_eq!(report.classes, vec!["credentials", "identity", "contact"]);
+        assert_eq!(report.unmapped, vec!["Favourite colours"]);
+        assert_eq!(report.max_risk_weight, 10);
+        assert!(report.contains(BreachDataClassBackend::Contact));
+        assert!(!report.contains(BreachDataClassBackend::Financial));
+    }
+
+    #[test]
+    fn normalizing_is_idempotent() {
+        let first = normalize_data_class_labels(&["Credit cards", "IP addresses"]);
+        let second = normalize_data_class_labels(&first.classes);
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn every_class_round_trips_through_its_token() {
+        for class in BreachDataClassBackend::ALL {
+            assert_eq!(
+                BreachDataClassBackend::from_str_opt(class.as_str()),
+                Some(class)
+            );
+            assert!((1..=10).contains(&class.risk_weight()));
+        }
+    }
+
+    #[test]
+    fn empty_breach_has_zero_weight() {
+        let report = normalize_data_class_labels::<&str>(&[]);
+        assert!(report.classes.is_empty());
+        assert_eq!(report.max_risk_weight, 0);
+    }
+}
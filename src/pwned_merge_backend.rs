@@ -0,0 +1,163 @@
+//! One record per breach across every monitored identity.
+//!
+//! A user monitoring several emails receives the same breach once per email,
+//! and [`BreachDetailBackend::uid`] hashes the locale and description, so the
+//! same breach described in two locales alerts twice. [`merge_breaches`]
+//! folds them into one [`MergedBreachBackend`] per breach, keyed by
+//! [`breach_merge_key`], which depends on the breach name only.
+
+use crate::pwned_backend::BreachDetailBackend;
+use blake3::Hasher;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Stable identity of a breach: the upstream name, trimmed and lowercased.
+/// Locale, description, and data-class drift do not move it.
+pub fn breach_merge_key(name: &str) -> String {
+    let mut hasher = Hasher::new();
+    hasher.update(name.trim().to_lowercase().as_bytes());
+    hasher.finalize().to_hex().to_string()
+}
+
+/// One breach seen through any number of identities.
+#[derive(Debug, Clone, Serialize, Deserialize, Ord, PartialOrd, Eq, PartialEq)]
+pub struct MergedBreachBackend {
+    /// See [`breach_merge_key`].
+    pub key: String,
+    /// The first occurrence, with the union of every occurrence's data
+    /// classes, the maximum criticality and count, and each flag ORed.
+    pub breach: BreachDetailBackend,
+    /// Identities (emails, usernames) the breach was reported for, sorted and
+    /// deduplicated.
+    pub identities: Vec<String>,
+}
+
+/// Merge `(identity, breach)` pairs into one record per breach. Records come
+/// out most critical first, then by breach name.
+pub fn merge_breaches<'a, I>(reports: I) -> Vec<MergedBreachBackend>
+where
+    I: IntoIterator<Item = (&'a str, &'a BreachDetailBackend)>,
+{
+    let mut merged: BTreeMap<String, MergedBreachBackend> = BTreeMap::new();
+    for (identity, breach) in reports {
+        let key = breach_merge_key(&breach.name);
+        let entry = merged
+            .entry(key.clone())
+            .or_insert_with(|| MergedBreachBackend {
+                key,
+                breach: breach.clone(),
+                identities: Vec::new(),
+            });
+        let target = &mut entry.breach;
+        union_classes(&mut target.data_classes, &breach.data_classes);
+        union_classes(&mut target.short_data_classes, &breach.short_data_classes);
+        target.criticality = target.criticality.clone().max(breach.criticality.clone());
+        target.count = target.count.max(breach.count);
+        target.is_verified |= breach.is_verified;
+        target.is_sensitive |= breach.is_sensitive;
+        target.is_stealer_log |= breach.is_stealer_log;
+        entry.identities.push(identity.to_string());
+    }
+
+    let mut merged: Vec<MergedBreachBackend> = merged
+        .into_values()
+        .map(|mut entry| {
+            entry.identities.sort();
+            entry.identities.dedup();
+            entry
+        })
+        .collect();
+    merged.sort_by(|a, b| {
+        b.breach
+            .criticality
+            .cmp(&a.breach.criticality)
+            .then_with(|| a.breach.name.cmp(&b.breach.name))
+    });
+    merged
+}
+
+/// Add the classes of `other` missing from `target`, compared
+/// case-insensitively, and sort the result.
+fn union_classes(target: &mut Vec<String>, other: &[String]) {
+    for class in other {
+        let class = class.trim();
+        if !target.iter().any(|known| known.eq_ignore_ascii_case(class)) {
+            target.push(class.to_string());
+        }
+    }
+    target.sort_by_key(|class| class.to_lowercase());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pwned_backend::PwnedCriticalityBackend;
+    use crate::test_fixtures as fixtures;
+
+    fn breach(
+        name: &str,
+        description: &str,
+        data_classes: &[&str],
+        criticality: PwnedCriticalityBackend,
+    ) -> BreachDetailBackend {
+        BreachDetailBackend {
+            count: 100,
+            description: description.to_string(),
+            data_classes: data_classes.iter().map(|c| c.to_string()).collect(),
+            criticality,
+            ..fixtures::breach(name)
+        }
+    }
+
+    #[test]
+    fn merges_across_identities_and_locales() {
+        let english = breach(
+            "Adobe",
+            "In October 2013...",
+            &["Email addresses", "Passwords"],
+            PwnedCriticalityBackend::Medium,
+        );
+        let mut french = breach(
+            "adobe ",
+            "En octobre 2013...",
+            &["passwords", "Password hints"],
+            PwnedCriticalityBackend::High,
+        );
+        french.is_sensitive = true;
+        let other = breach(
+            "LinkedIn",
+            "In 2012...",
+            &["Email addresses"],
+            PwnedCriticalityBackend::Low,
+        );
+        assert_ne!(english.uid("EN"), french.uid("FR"));
+
+        let merged = merge_breaches(vec![
+            ("b@example.com", &english),
+            ("a@example.com", &french),
+            ("a@example.com", &other),
+            ("b@example.com", &english),
+        ]);
+        assert_eq!(merged.len(), 2);
+
+        let adobe = &merged[0];
+        assert_eq!(adobe.key, breach_merge_key("Adobe"));
+        assert_eq!(adobe.breach.description, "In October 2013...");
+        assert_eq!(adobe.breach.criticality, PwnedCriticalityBackend::High);
+        assert!(adobe.breach.is_sensitive);
+        assert_eq!(
+            adobe.breach.data_classes,
+            vec!["Email addresses", "Password hints", "Passwords"]
+        );
+        assert_eq!(adobe.identities, vec!["a@example.com", "b@example.com"]);
+
+        assert_eq!(merged[1].breach.name, "LinkedIn");
+        assert_eq!(merged[1].identities, vec!["a@example.com"]);
+    }
+
+    #[test]
+    fn key_ignores_description_and_case() {
+        assert_eq!(breach_merge_key("Adobe"), breach_merge_key(" ADOBE"));
+        assert_ne!(breach_merge_key("Adobe"), breach_merge_key("Adobe2"));
+    }
+}
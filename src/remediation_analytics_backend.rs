@@ -0,0 +1,377 @@
+//! Fleet-level remediation statistics, aggregated from the order history every
+//! device ships inside its [`ScoreBackend`].
+//!
+//! One [`MetricRemediationStatsBackend`] row per (metric, platform): how often
+//! the remediation ran, how often it reported success, how often that success
+//! was validated, and how often it was rolled back. A row also counts
+//! *regressions*: devices that successfully remediated a metric, did not roll
+//! it back, and still report it Active afterwards. A remediation script that
+//! regresses across many devices is broken, whatever its success rate says.
+//!
+//! Per-device history is read through
+//! [`crate::order_timeline_backend::build_order_timeline`], so timestamp
+//! parsing and the meaning of each order type are shared with the timeline.
+
+use crate::order_timeline_backend::{
+    build_order_timeline, parse_order_timestamp, MetricTransitionBackend,
+};
+use crate::score_backend::{DetailedScoreBackend, ScoreBackend};
+use crate::threat_backend::ThreatStatusBackend;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Remediation figures for one metric on one platform, across every device
+/// fed to the builder.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, PartialOrd, Default)]
+pub struct MetricRemediationStatsBackend {
+    pub metricname: String,
+    /// Platform as reported by the device (`os_name`).
+    pub platform: String,
+    /// Devices whose history mentions the metric at all.
+    pub devices: u32,
+    pub remediation_attempts: u32,
+    pub remediation_successes: u32,
+    pub remediation_validated: u32,
+    pub rollbacks: u32,
+    /// Devices where a successful remediation was followed by the threat
+    /// being reported Active again with no rollback in between.
+    pub regressions: u32,
+}
+
+impl MetricRemediationStatsBackend {
+    /// `remediation_successes / remediation_attempts`.
+    pub fn success_rate(&self) -> Option<f64> {
+        ratio(self.remediation_successes, self.remediation_attempts)
+    }
+
+    /// `remediation_validated / remediation_attempts`. The gap to
+    /// [`Self::success_rate`] is remediations that claim success but never
+    /// verify.
+    pub fn validated_rate(&self) -> Option<f64> {
+        ratio(self.remediation_validated, self.remediation_attempts)
+    }
+
+    /// `rollbacks / remediation_successes`.
+    pub fn rollback_rate(&self) -> Option<f64> {
+        ratio(self.rollbacks, self.remediation_successes)
+    }
+
+    /// `regressions / devices`.
+    pub fn regression_rate(&self) -> Option<f64> {
+        ratio(self.regressions, self.devices)
+    }
+}
+
+fn ratio(numerator: u32, denominator: u32) -> Option<f64> {
+    (denominator > 0).then(|| numerator as f64 / denominator as f64)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, PartialOrd, Default)]
+pub struct RemediationAnalyticsBackend {
+    /// Devices fed to the builder.
+    pub devices: u32,
+    /// Sorted by metric name, then platform.
+    pub metrics: Vec<MetricRemediationStatsBackend>,
+}
+
+impl RemediationAnalyticsBackend {
+    /// Rows with at least one regression, worst first.
+    pub fn regressing(&self) -> Vec<&MetricRemediationStatsBackend> {
+        let mut rows: Vec<&MetricRemediationStatsBackend> = self
+            .metrics
+            .iter()
+            .filter(|row| row.regressions > 0)
+            .collect();
+        rows.sort_by(|a, b| {
+            b.regressions
+                .cmp(&a.regressions)
+                .then_with(|| a.metricname.cmp(&b.metricname))
+                .then_with(|| a.platform.cmp(&b.platform))
+        });
+        rows
+    }
+
+    pub fn get(&self, metricname: &str, platform: &str) -> Option<&MetricRemediationStatsBackend> {
+        self.metrics
+            .iter()
+            .find(|row| row.metricname == metricname && row.platform == platform)
+    }
+}
+
+/// Accumulates device reports one at a time, so the Hub can stream a fleet
+/// through it without holding every report.
+#[derive(Debug, Clone, Default)]
+pub struct RemediationAnalyticsBuilderBackend {
+    devices: u32,
+    rows: BTreeMap<(String, String), MetricRemediationStatsBackend>,
+}
+
+impl RemediationAnalyticsBuilderBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add one device's report, keyed by its `os_name`.
+    pub fn add_report(&mut self, report: &DetailedScoreBackend) -> &mut Self {
+        self.add_score(&report.os_name, &report.score)
+    }
+
+    /// Add one device's score under `platform`.
+    pub fn add_score(&mut self, platform: &str, score: &ScoreBackend) -> &mut Self {
+        self.devices += 1;
+        for timeline in build_order_timeline(&score.history).metrics {
+            let row = self
+                .rows
+                .entry((timeline.metricname.clone(), platform.to_string()))
+                .or_insert_with(|| MetricRemediationStatsBackend {
+                    metricname: timeline.metricname.clone(),
+                    platform: platform.to_string(),
+                    ..Default::default()
+                });
+            row.devices += 1;
+            row.remediation_attempts += timeline.remediation_attempts;
+            row.remediation_successes += timeline.remediation_successes;
+            row.remediation_validated += timeline.remediation_validated;
+            row.rollbacks += timeline.rollbacks;
+
+            // Where the history leaves the metric: the last successful
+            // remediation, unless a successful rollback came after it.
+            let last_fix = timeline.transitions.iter().rev().find(|event| {
+                matches!(
+                    event.transition,
+                    MetricTransitionBackend::Remediated
+                        | MetricTransitionBackend::RemediationUnvalidated
+                        | MetricTransitionBackend::RolledBack
+                )
+            });
+            let Some(last_fix) = last_fix else {
+                continue;
+            };
+            if last_fix.transition == MetricTransitionBackend::RolledBack {
+                continue;
+            }
+            let regressed = score.metrics.metrics.iter().any(|metric| {
+                metric.metric.name == timeline.metricname
+                    && metric.status == ThreatStatusBackend::Active
+                    // A status observed before the fix says nothing about it.
+                    // An empty or unparseable timestamp is taken as current.
+                    && parse_order_timestamp(&metric.timestamp)
+                        .is_none_or(|observed| observed >= last_fix.timestamp)
+            });
+            if regressed {
+                row.regressions += 1;
+            }
+        }
+        self
+    }
+
+    pub fn build(&self) -> RemediationAnalyticsBackend {
+        RemediationAnalyticsBackend {
+            devices: self.devices,
+            metrics: self.rows.values().cloned().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history_backend::OrderHistoryBackend;
+    use crate::order_backend::MetricOrderResultBackend;
+    use crate::order_type_backend::MetricOrderTypeBackend;
+    use crate::test_fixtures as fixtures;
+    use crate::threat_backend::ThreatMetricBackend;
+
+    fn status(name: &str, status: ThreatStatusBackend, timestamp: &str) -> ThreatMetricBackend {
+        ThreatMetricBackend {
+            timestamp: timestamp.to_string(),
+            ..fixtures::metric(name, status)
+        }
+    }
+
+    fn order(
+        name: &str,
+        ordertype: MetricOrderTypeBackend,
+        timestamp: &str,
+        success: bool,
+        validated: bool,
+    ) -> MetricOrderResultBackend {
+        MetricOrderResultBackend {
+            metricname: name.to_string(),
+            ordertype,
+            timestamp: timestamp.to_string(),
+            success,
+            validated,
+        }
+    }
+
+    fn score(
+        history: Vec<MetricOrderResultBackend>,
+        metrics: Vec<ThreatMetricBackend>,
+    ) -> ScoreBackend {
+        ScoreBackend {
+            history: OrderHistoryBackend { history },
+            ..fixtures::score(metrics)
+        }
+    }
+
+    #[test]
+    fn aggregates_per_metric_and_platform() {
+        let mut builder = RemediationAnalyticsBuilderBackend::new();
+        builder
+            .add_score(
+                "macOS",
+                &score(
+                    vec![
+                        order(
+                            "fw",
+                            MetricOrderTypeBackend::Remediate,
+                            "2026-01-01T00:00:00Z",
+                            false,
+                            false,
+                        ),
+                        order(
+                            "fw",
+                            MetricOrderTypeBackend::Remediate,
+                            "2026-01-01T01:00:00Z",
+                            true,
+                            true,
+                        ),
+                    ],
+                    Vec::new(),
+                ),
+            )
+            .add_score(
+                "macOS",
+                &score(
+                    vec![
+                        order(
+                            "fw",
+                            MetricOrderTypeBackend::Remediate,
+                            "2026-01-01T00:00:00Z",
+                            true,
+                            false,
+                        ),
+                        order(
+                            "fw",
+                            MetricOrderTypeBackend::Rollback,
+                            "2026-01-02T00:00:00Z",
+                            true,
+                            false,
+                        ),
+                    ],
+                    Vec::new(),
+                ),
+            )
+            .add_score(
+                "Windows",
+                &score(
+                    vec![order(
+                        "fw",
+                        MetricOrderTypeBackend::Remediate,
+                        "2026-01-01T00:00:00Z",
+                        true,
+                        true,
+                    )],
+                    Vec::new(),
+                ),
+            );
+        let analytics = builder.build();
+        assert_eq!(analytics.devices, 3);
+        assert_eq!(analytics.metrics.len(), 2);
+
+        let mac = analytics.get("fw", "macOS").unwrap();
+        assert_eq!(mac.devices, 2);
+        assert_eq!(mac.remediation_attempts, 3);
+        assert_eq!(mac.success_rate(), Some(2.0 / 3.0));
+        assert_eq!(mac.validated_rate(), Some(1.0 / 3.0));
+        assert_eq!(mac.rollback_rate(), Some(0.5));
+
+        let windows = analytics.get("fw", "Windows").unwrap();
+        assert_eq!(windows.success_rate(), Some(1.0));
+        assert_eq!(windows.rollback_rate(), Some(0.0));
+    }
+
+    #[test]
+    fn active_after_successful_remediation_is_a_regression() {
+        let fixed = vec![order(
+            "fw",
+            MetricOrderTypeBackend::Remediate,
+            "2026-01-01T00:00:00Z",
+            true,
+            true,
+        )];
+        let mut builder = RemediationAnalyticsBuilderBackend::new();
+        // Active again after the fix.
+        builder.add_score(
+            "macOS",
+            &score(
+                fixed.clone(),
+                vec![status(
+                    "fw",
+                    ThreatStatusBackend::Active,
+                    "2026-01-03T00:00:00Z",
+                )],
+            ),
+        );
+        // Active, but observed before the fix: stale, not a regression.
+        builder.add_score(
+            "macOS",
+            &score(
+                fixed.clone(),
+                vec![status(
+                    "fw",
+                    ThreatStatusBackend::Active,
+                    "2025-12-31T00:00:00Z",
+                )],
+            ),
+        );
+        // Fix held.
+        builder.add_score(
+            "macOS",
+            &score(fixed, vec![status("fw", ThreatStatusBackend::Inactive, "")]),
+        );
+        // Rolled back on purpose, so Active is expected.
+        builder.add_score(
+            "macOS",
+            &score(
+                vec![
+                    order(
+                        "fw",
+                        MetricOrderTypeBackend::Remediate,
+                        "2026-01-01T00:00:00Z",
+                        true,
+                        true,
+                    ),
+                    order(
+                        "fw",
+                        MetricOrderTypeBackend::Rollback,
+                        "2026-01-02T00:00:00Z",
+                        true,
+                        false,
+                    ),
+                ],
+                vec![status("fw", ThreatStatusBackend::Active, "")],
+            ),
+        );
+        let analytics = builder.build();
+        let fw = analytics.get("fw", "macOS").unwrap();
+        assert_eq!(fw.devices, 4);
+        assert_eq!(fw.regressions, 1);
+        assert_eq!(fw.regression_rate(), Some(0.25));
+
+        let regressing = analytics.regressing();
+        assert_eq!(regressing.len(), 1);
+        assert_eq!(regressing[0].metricname, "fw");
+    }
+
+    #[test]
+    fn empty_fleet_has_no_rates() {
+        let analytics = RemediationAnalyticsBuilderBackend::new().build();
+        assert_eq!(analytics.devices, 0);
+        assert!(analytics.metrics.is_empty());
+        let row = MetricRemediationStatsBackend::default();
+        assert_eq!(row.success_rate(), None);
+        assert_eq!(row.rollback_rate(), None);
+    }
+}
@@ -0,0 +1,460 @@
+//! Ordered remediation plan built from the Active metrics of a threat model.
+//!
+//! The Hub pushes "fix these things in this order" to a device and later
+//! reconciles the plan against the [`OrderHistoryBackend`] the device reports.
+//! [`RemediationPlanBackend::build`] picks every Active metric whose
+//! remediation applies to the target platform, orders them by severity and
+//! then score impact, and batches them by required elevation so a device asks
+//! for privileges once per batch. Each step carries its rollback so the plan is
+//! undoable as shipped.
+//!
+//! Score impact is the number of compliance tags a metric carries: each tag is
+//! one more compliance ratio the fix moves, on top of the dimension score that
+//! severity already weighs.
+
+use crate::history_backend::OrderHistoryBackend;
+use crate::order_timeline_backend::{parse_order_timestamp, MetricTransitionBackend};
+use crate::order_type_backend::MetricOrderTypeBackend;
+use crate::threat_backend::{
+    ThreatMetricBackend, ThreatMetricImplementationJSONBackend, ThreatMetricsBackend,
+    ThreatStatusBackend,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Platform a plan is built for.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct RemediationPlatformBackend {
+    /// Matched case-insensitively against the implementation `system`
+    /// (`macOS`, `Windows`, `Linux`, …).
+    pub system: String,
+    /// Major OS version, compared against `minversion`/`maxversion`.
+    pub version: i32,
+}
+
+/// Why an Active metric did not make it into the plan.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RemediationSkipReasonBackend {
+    /// The metric has no remediation target to run.
+    NoRemediation,
+    /// The remediation is written for another system.
+    WrongSystem,
+    /// The platform version is outside `minversion..=maxversion`.
+    UnsupportedVersion,
+}
+
+impl RemediationSkipReasonBackend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::NoRemediation => "no_remediation",
+            Self::WrongSystem => "wrong_system",
+            Self::UnsupportedVersion => "unsupported_version",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct RemediationSkipBackend {
+    pub metricname: String,
+    /// See [`RemediationSkipReasonBackend`].
+    pub reason: String,
+}
+
+/// One metric to fix.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, PartialOrd)]
+pub struct RemediationStepBackend {
+    /// 1-based position in the plan's priority order.
+    pub sequence: u32,
+    pub metricname: String,
+    pub severity: i32,
+    /// Compliance tags the metric carries -- see the module docs.
+    pub score_impact: u32,
+    pub elevation: String,
+    pub remediation: ThreatMetricImplementationJSONBackend,
+    pub rollback: ThreatMetricImplementationJSONBackend,
+    /// Orders to issue to apply the step: remediate, then capture to validate.
+    pub orders: Vec<MetricOrderTypeBackend>,
+    /// Orders to issue to undo the step: rollback, then capture.
+    pub rollback_orders: Vec<MetricOrderTypeBackend>,
+}
+
+/// Steps sharing one elevation, in plan order.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, PartialOrd)]
+pub struct RemediationGroupBackend {
+    pub elevation: String,
+    pub steps: Vec<RemediationStepBackend>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, PartialOrd)]
+pub struct RemediationPlanBackend {
+    /// Reconciliation only counts orders at or after this instant.
+    pub created: DateTime<Utc>,
+    pub platform: RemediationPlatformBackend,
+    // Copied from the threat model
+    pub threat_model: String,
+    pub threat_model_date: String,
+    /// Groups ordered by their most urgent step; steps within a group keep
+    /// plan order.
+    pub groups: Vec<RemediationGroupBackend>,
+    pub skipped: Vec<RemediationSkipBackend>,
+}
+
+/// Where a plan step stands against the device's reported history.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RemediationStepStateBackend {
+    /// No remediate or rollback order since the plan was created.
+    Pending,
+    Remediated,
+    RemediationUnvalidated,
+    RemediationFailed,
+    RolledBack,
+    RollbackFailed,
+}
+
+impl From<MetricTransitionBackend> for RemediationStepStateBackend {
+    fn from(transition: MetricTransitionBackend) -> Self {
+        match transition {
+            MetricTransitionBackend::Remediated => Self::Remediated,
+            MetricTransitionBackend::RemediationUnvalidated => Self::RemediationUnvalidated,
+            MetricTransitionBackend::RemediationFailed => Self::RemediationFailed,
+            MetricTransitionBackend::RolledBack => Self::RolledBack,
+            MetricTransitionBackend::RollbackFailed => Self::RollbackFailed,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct RemediationStepStatusBackend {
+    pub sequence: u32,
+    pub metricname: String,
+    pub state: RemediationStepStateBackend,
+}
+
+impl RemediationPlanBackend {
+    /// Plan every Active metric of `metrics` applicable to `platform`.
+    pub fn build(
+        metrics: &ThreatMetricsBackend,
+        platform: &RemediationPlatformBackend,
+        created: DateTime<Utc>,
+    ) -> Self {
+        let mut candidates: Vec<&ThreatMetricBackend> = Vec::new();
+        let mut skipped = Vec::new();
+        for metric in metrics
+            .metrics
+            .iter()
+            .filter(|metric| metric.status == ThreatStatusBackend::Active)
+        {
+            match applicability(&metric.metric.remediation, platform) {
+                Ok(()) => candidates.push(metric),
+                Err(reason) => skipped.push(RemediationSkipBackend {
+                    metricname: metric.metric.name.clone(),
+                    reason: reason.as_str().to_string(),
+                }),
+            }
+        }
+        candidates.sort_by(|a, b| {
+            b.metric
+                .severity
+                .cmp(&a.metric.severity)
+                .then_with(|| b.metric.tags.len().cmp(&a.metric.tags.len()))
+                .then_with(|| a.metric.name.cmp(&b.metric.name))
+        });
+        skipped.sort();
+
+        let mut groups: Vec<RemediationGroupBackend> = Vec::new();
+        for (index, metric) in candidates.into_iter().enumerate() {
+            let json = &metric.metric;
+            let step = RemediationStepBackend {
+                sequence: index as u32 + 1,
+                metricname: json.name.clone(),
+                severity: json.severity,
+                score_impact: json.tags.len() as u32,
+                elevation: json.remediation.elevation.clone(),
+                remediation: json.remediation.clone(),
+                rollback: json.rollback.clone(),
+                orders: vec![
+                    MetricOrderTypeBackend::Remediate,
+                    MetricOrderTypeBackend::Capture,
+                ],
+                rollback_orders: vec![
+                    MetricOrderTypeBackend::Rollback,
+                    MetricOrderTypeBackend::Capture,
+                ],
+            };
+            match groups
+                .iter_mut()
+                .find(|group| group.elevation.eq_ignore_ascii_case(&step.elevation))
+            {
+                Some(group) => group.steps.push(step),
+                None => groups.push(RemediationGroupBackend {
+                    elevation: step.elevation.clone(),
+                    steps: vec![step],
+                }),
+            }
+        }
+
+        Self {
+            created,
+            platform: platform.clone(),
+            threat_model: metrics.name.clone(),
+            threat_model_date: metrics.date.clone(),
+            groups,
+            skipped,
+        }
+    }
+
+    /// Every step in plan order, across groups.
+    pub fn steps(&self) -> Vec<&RemediationStepBackend> {
+        let mut steps: Vec<&RemediationStepBackend> =
+            self.groups.iter().flat_map(|group| &group.steps).collect();
+        steps.sort_by_key(|step| step.sequence);
+        steps
+    }
+
+    /// State of each step, in plan order, from the latest remediate or
+    /// rollback order at or after [`Self::created`]. Orders with an
+    /// unparseable timestamp are ignored.
+    pub fn reconcile(&self, history: &OrderHistoryBackend) -> Vec<RemediationStepStatusBackend> {
+        self.steps()
+            .into_iter()
+            .map(|step| {
+                let state = history
+                    .history
+                    .iter()
+                    .filter(|order| order.metricname == step.metricname)
+                    .filter_map(|order| {
+                        let timestamp = parse_order_timestamp(&order.timestamp)?;
+                        let transition = MetricTransitionBackend::from_order(order)?;
+                        (timestamp >= self.created).then_some((timestamp, transition))
+                    })
+                    // Last of equal timestamps wins, as recorded.
+                    .fold(
+                        None,
+                        |latest: Option<(DateTime<Utc>, _)>, candidate| match latest {
+                            Some(latest) if latest.0 > candidate.0 => Some(latest),
+                            _ => Some(candidate),
+                        },
+                    )
+                    .map_or(RemediationStepStateBackend::Pending, |(_, transition)| {
+                        transition.into()
+                    });
+                RemediationStepStatusBackend {
+                    sequence: step.sequence,
+                    metricname: step.metricname.clone(),
+                    state,
+                }
+            })
+            .collect()
+    }
+}
+
+fn applicability(
+    remediation: &ThreatMetricImplementationJSONBackend,
+    platform: &RemediationPlatformBackend,
+) -> Result<(), RemediationSkipReasonBackend> {
+    if remediation.target.trim().is_empty() {
+        return Err(RemediationSkipReasonBackend::NoRemediation);
+    }
+    if !remediation.system.eq_ignore_ascii_case(&platform.system) {
+        return Err(RemediationSkipReasonBackend::WrongSystem);
+    }
+    if platform.version < remediation.minversion
+        || (remediation.maxversion != 0 && platform.version > remediation.maxversion)
+    {
+        return Err(RemediationSkipReasonBackend::UnsupportedVersion);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order_backend::MetricOrderResultBackend;
+    use crate::test_fixtures::{self as fixtures, metrics};
+
+    fn implementation(elevation: &str, target: &str) -> ThreatMetricImplementationJSONBackend {
+        ThreatMetricImplementationJSONBackend {
+            elevation: elevation.to_string(),
+            target: target.to_string(),
+            ..fixtures::implementation()
+        }
+    }
+
+    fn metric(
+        name: &str,
+        severity: i32,
+        tags: usize,
+        elevation: &str,
+        status: ThreatStatusBackend,
+    ) -> ThreatMetricBackend {
+        let mut metric = fixtures::metric(name, status);
+        metric.metric.severity = severity;
+        metric.metric.tags = (0..tags).map(|i| format!("CIS,{i}")).collect();
+        metric.metric.implementation = implementation(elevation, "check");
+        metric.metric.remediation = implementation(elevation, "fix");
+        metric.metric.rollback = implementation(elevation, "undo");
+        metric
+    }
+
+    fn platform() -> RemediationPlatformBackend {
+        RemediationPlatformBackend {
+            system: "macos".to_string(),
+            version: 14,
+        }
+    }
+
+    fn created() -> DateTime<Utc> {
+        parse_order_timestamp("2026-10-01T00:00:00Z").unwrap()
+    }
+
+    fn sample_plan() -> RemediationPlanBackend {
+        RemediationPlanBackend::build(
+            &metrics(vec![
+                metric("low user", 2, 0, "user", ThreatStatusBackend::Active),
+                metric("high admin", 5, 0, "admin", ThreatStatusBackend::Active),
+                metric("mid user few", 4, 1, "user", ThreatStatusBackend::Active),
+                metric("mid user many", 4, 3, "user", ThreatStatusBackend::Active),
+                metric("fixed", 5, 0, "user", ThreatStatusBackend::Inactive),
+            ]),
+            &platform(),
+            created(),
+        )
+    }
+
+    #[test]
+    fn orders_by_severity_then_impact_and_groups_by_elevation() {
+        let plan = sample_plan();
+        let order: Vec<&str> = plan.steps().iter().map(|s| s.metricname.as_str()).collect();
+        assert_eq!(
+            order,
+            vec!["high admin", "mid user many", "mid user few", "low user"]
+        );
+        let groups: Vec<(&str, Vec<u32>)> = plan
+            .groups
+            .iter()
+            .map(|g| {
+                (
+                    g.elevation.as_str(),
+                    g.steps.iter().map(|s| s.sequence).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(groups, vec![("admin", vec![1]), ("user", vec![2, 3, 4])]);
+        let first = plan.steps()[0];
+        assert_eq!(first.rollback.target, "undo");
+        assert_eq!(
+            first.orders,
+            vec![
+                MetricOrderTypeBackend::Remediate,
+                MetricOrderTypeBackend::Capture
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_inapplicable_remediations() {
+        let mut windows = metric("windows only", 5, 0, "user", ThreatStatusBackend::Active);
+        windows.metric.remediation.system = "Windows".to_string();
+        let mut too_new = metric("too new", 5, 0, "user", ThreatStatusBackend::Active);
+        too_new.metric.remediation.minversion = 15;
+        let mut too_old = metric("too old", 5, 0, "user", ThreatStatusBackend::Active);
+        too_old.metric.remediation.maxversion = 13;
+        let mut manual = metric("manual", 5, 0, "user", ThreatStatusBackend::Active);
+        manual.metric.remediation.target = String::new();
+        let plan = RemediationPlanBackend::build(
+            &metrics(vec![windows, too_new, too_old, manual]),
+            &platform(),
+            created(),
+        );
+        assert!(plan.groups.is_empty());
+        let skipped: Vec<(&str, &str)> = plan
+            .skipped
+            .iter()
+            .map(|s| (s.metricname.as_str(), s.reason.as_str()))
+            .collect();
+        assert_eq!(
+            skipped,
+            vec![
+                ("manual", "no_remediation"),
+                ("too new", "unsupported_version"),
+                ("too old", "unsupported_version"),
+                ("windows only", "wrong_system"),
+            ]
+        );
+    }
+
+    #[test]
+    fn reconciles_against_history_since_creation() {
+        let plan = sample_plan();
+        let order =
+            |name: &str, ordertype, timestamp: &str, success, validated| MetricOrderResultBackend {
+                metricname: name.to_string(),
+                ordertype,
+                timestamp: timestamp.to_string(),
+                success,
+                validated,
+            };
+        let history = OrderHistoryBackend {
+            history: vec![
+                // Before the plan: ignored.
+                order(
+                    "low user",
+                    MetricOrderTypeBackend::Remediate,
+                    "2026-09-01T00:00:00Z",
+                    true,
+                    true,
+                ),
+                order(
+                    "high admin",
+                    MetricOrderTypeBackend::Remediate,
+                    "2026-10-02T00:00:00Z",
+                    true,
+                    true,
+                ),
+                order(
+                    "mid user many",
+                    MetricOrderTypeBackend::Remediate,
+                    "2026-10-03T00:00:00Z",
+                    true,
+                    true,
+                ),
+                order(
+                    "mid user many",
+                    MetricOrderTypeBackend::Rollback,
+                    "2026-10-04T00:00:00Z",
+                    true,
+                    false,
+                ),
+                order(
+                    "mid user few",
+                    MetricOrderTypeBackend::Remediate,
+                    "2026-10-02T00:00:00Z",
+                    false,
+                    false,
+                ),
+            ],
+        };
+        let states: Vec<RemediationStepStateBackend> = plan
+            .reconcile(&history)
+            .into_iter()
+            .map(|s| s.state)
+            .collect();
+        assert_eq!(
+            states,
+            vec![
+                RemediationStepStateBackend::Remediated,
+                RemediationStepStateBackend::RolledBack,
+                RemediationStepStateBackend::RemediationFailed,
+                RemediationStepStateBackend::Pending,
+            ]
+        );
+    }
+
+    #[test]
+    fn plan_round_trips() {
+        let plan = sample_plan();
+        let json = serde_json::to_string(&plan).expect("serialize");
+        let parsed: RemediationPlanBackend = serde_json::from_str(&json).expect("deserialize");
+        assert_eq!(parsed, plan);
+    }
+}
@@ -1,7 +1,8 @@
+use crate::stable_hash_backend::StableHash;
 use blake3::Hasher;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct SessionInfoBackend {
     pub ip: String,
     pub port: u16,
@@ -19,6 +20,13 @@ pub struct SessionInfoBackend {
 
 impl SessionInfoBackend {
     pub fn uid(&self, language: &str) -> String {
+        let mut hasher = crate::uid_backend::uid_hasher("session", language);
+        self.stable_hash_into(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Scheme 1 uid, kept to rekey caches -- see [`crate::uid_backend`].
+    pub fn legacy_uid(&self, language: &str) -> String {
         let mut hasher = Hasher::new();
         hasher.update(language.as_bytes());
         hasher.update(self.ip.as_bytes());
@@ -52,3 +60,11 @@ impl SessionInfoBackend {
         hasher.finalize().to_hex().to_string()
     }
 }
+
+impl SessionInfoBackend {
+    /// Typed view with a parsed address and enum protocol/criticality -- see
+    /// [`crate::session_typed_backend`].
+    pub fn typed(&self) -> anyhow::Result<crate::session_typed_backend::TypedSessionInfoBackend> {
+        crate::session_typed_backend::TypedSessionInfoBackend::try_from(self)
+    }
+}
@@ -0,0 +1,330 @@
+//! Allow/deny rules over [`SessionInfoBackend`].
+//!
+//! A [`SessionRuleBackend`] states what a session looks like ("chrome talking
+//! to the Google ASN on 443") and whether that is fine. Every criterion a rule
+//! leaves unset matches anything; set criteria are ANDed. A set criterion never
+//! matches a session that lacks the field, so a rule naming a process does not
+//! silently match unattributed traffic.
+//!
+//! [`SessionRuleSetBackend::evaluate`] classifies a session as allowed, denied
+//! or unknown and names the rule that decided. Deny overrides allow: a session
+//! matching both is denied, so a broad allow can never mask a targeted deny.
+//! Among rules of the same action, the first in the list is reported.
+//!
+//! Rule sets serialize as-is so the Hub can distribute them to devices.
+
+use crate::glob_backend::glob_matches;
+use crate::session_info_backend::SessionInfoBackend;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SessionRuleActionBackend {
+    Allow,
+    Deny,
+}
+
+/// Inclusive port range. Deserialization goes through
+/// [`SessionPortRangeBackend::new`], so a rule set with an inverted range
+/// fails to load instead of carrying a rule that never matches.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(try_from = "SessionPortRangeWireBackend")]
+pub struct SessionPortRangeBackend {
+    pub start: u16,
+    pub end: u16,
+}
+
+/// The same JSON as [`SessionPortRangeBackend`], before validation.
+#[derive(Deserialize)]
+struct SessionPortRangeWireBackend {
+    start: u16,
+    end: u16,
+}
+
+impl TryFrom<SessionPortRangeWireBackend> for SessionPortRangeBackend {
+    type Error = anyhow::Error;
+
+    fn try_from(wire: SessionPortRangeWireBackend) -> Result<Self> {
+        Self::new(wire.start, wire.end)
+    }
+}
+
+impl SessionPortRangeBackend {
+    /// Fails when `start > end`.
+    pub fn new(start: u16, end: u16) -> Result<Self> {
+        if start > end {
+            return Err(anyhow!("inverted port range {start}-{end}"));
+        }
+        Ok(Self { start, end })
+    }
+
+    pub fn single(port: u16) -> Self {
+        Self {
+            start: port,
+            end: port,
+        }
+    }
+
+    pub fn contains(&self, port: u16) -> bool {
+        self.start <= port && port <= self.end
+    }
+}
+
+/// One rule. String criteria are globs (`*` any run, `?` one character),
+/// case-insensitive except `process_path`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SessionRuleBackend {
+    /// Reported back when the rule decides a session.
+    pub name: String,
+    pub action: SessionRuleActionBackend,
+    pub process_name: Option<String>,
+    pub process_path: Option<String>,
+    pub process_user: Option<String>,
+    /// `*.google.com` matches `www.google.com` but not `google.com`.
+    pub domain: Option<String>,
+    pub asn_number: Option<u32>,
+    pub asn_owner: Option<String>,
+    pub asn_country: Option<String>,
+    pub ports: Option<SessionPortRangeBackend>,
+    pub protocol: Option<String>,
+    pub service: Option<String>,
+}
+
+impl SessionRuleBackend {
+    pub fn new(name: impl Into<String>, action: SessionRuleActionBackend) -> Self {
+        Self {
+            name: name.into(),
+            action,
+            process_name: None,
+            process_path: None,
+            process_user: None,
+            domain: None,
+            asn_number: None,
+            asn_owner: None,
+            asn_country: None,
+            ports: None,
+            protocol: None,
+            service: None,
+        }
+    }
+
+    pub fn with_process_name(mut self, glob: impl Into<String>) -> Self {
+        self.process_name = Some(glob.into());
+        self
+    }
+
+    pub fn with_process_path(mut self, glob: impl Into<String>) -> Self {
+        self.process_path = Some(glob.into());
+        self
+    }
+
+    pub fn with_process_user(mut self, glob: impl Into<String>) -> Self {
+        self.process_user = Some(glob.into());
+        self
+    }
+
+    pub fn with_domain(mut self, glob: impl Into<String>) -> Self {
+        self.domain = Some(glob.into());
+        self
+    }
+
+    pub fn with_asn_number(mut self, asn_number: u32) -> Self {
+        self.asn_number = Some(asn_number);
+        self
+    }
+
+    pub fn with_asn_owner(mut self, glob: impl Into<String>) -> Self {
+        self.asn_owner = Some(glob.into());
+        self
+    }
+
+    pub fn with_asn_country(mut self, glob: impl Into<String>) -> Self {
+        self.asn_country = Some(glob.into());
+        self
+    }
+
+    pub fn with_ports(mut self, ports: SessionPortRangeBackend) -> Self {
+        self.ports = Some(ports);
+        self
+    }
+
+    pub fn with_protocol(mut self, glob: impl Into<String>) -> Self {
+        self.protocol = Some(glob.into());
+        self
+    }
+
+    pub fn with_service(mut self, glob: impl Into<String>) -> Self {
+        self.service = Some(glob.into());
+        self
+    }
+
+    pub fn matches(&self, session: &SessionInfoBackend) -> bool {
+        let text = |criterion: &Option<String>, value: Option<&str>, case_sensitive: bool| {
+            criterion.as_ref().is_none_or(|pattern| {
+                value.is_some_and(|value| glob_matches(pattern, value.trim(), case_sensitive))
+            })
+        };
+        text(
+            &self.process_name,
+            session.l7_process_name.as_deref(),
+            false,
+        ) && text(&self.process_path, session.l7_process_path.as_deref(), true)
+            && text(
+                &self.process_user,
+                session.l7_process_user.as_deref(),
+                false,
+            )
+            && text(&self.domain, session.domain.as_deref(), false)
+            && text(&self.asn_owner, session.asn_owner.as_deref(), false)
+            && text(&self.asn_country, session.asn_country.as_deref(), false)
+            && text(&self.protocol, Some(&session.protocol), false)
+            && text(&self.service, session.service.as_deref(), false)
+            && self
+                .asn_number
+                .is_none_or(|asn| session.asn_number == Some(asn))
+            && self.ports.is_none_or(|ports| ports.contains(session.port))
+    }
+}
+
+/// How a rule set classified a session.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SessionClassBackend {
+    Allowed,
+    Denied,
+    /// No rule matched.
+    Unknown,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SessionVerdictBackend {
+    pub class: SessionClassBackend,
+    /// Name of the deciding rule; `None` when `Unknown`.
+    pub rule: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SessionRuleSetBackend {
+    pub rules: Vec<SessionRuleBackend>,
+}
+
+impl SessionRuleSetBackend {
+    pub fn evaluate(&self, session: &SessionInfoBackend) -> SessionVerdictBackend {
+        let first = |action| {
+            self.rules
+                .iter()
+                .find(|rule| rule.action == action && rule.matches(session))
+        };
+        if let Some(rule) = first(SessionRuleActionBackend::Deny) {
+            return SessionVerdictBackend {
+                class: SessionClassBackend::Denied,
+                rule: Some(rule.name.clone()),
+            };
+        }
+        if let Some(rule) = first(SessionRuleActionBackend::Allow) {
+            return SessionVerdictBackend {
+                class: SessionClassBackend::Allowed,
+                rule: Some(rule.name.clone()),
+            };
+        }
+        SessionVerdictBackend {
+            class: SessionClassBackend::Unknown,
+            rule: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures as fixtures;
+
+    fn session(process: &str, domain: &str, asn_owner: &str, port: u16) -> SessionInfoBackend {
+        SessionInfoBackend {
+            port,
+            domain: Some(domain.to_string()),
+            asn_owner: Some(asn_owner.to_string()),
+            l7_process_name: Some(process.to_string()),
+            l7_process_path: Some(format!("/Applications/{process}.app")),
+            ..fixtures::session("142.250.0.1")
+        }
+    }
+
+    fn rules() -> SessionRuleSetBackend {
+        SessionRuleSetBackend {
+            rules: vec![
+                SessionRuleBackend::new("chrome-google", SessionRuleActionBackend::Allow)
+                    .with_process_name("chrome")
+                    .with_asn_owner("GOOGLE*")
+                    .with_ports(SessionPortRangeBackend::single(443)),
+                SessionRuleBackend::new("no-pastebin", SessionRuleActionBackend::Deny)
+                    .with_domain("*.pastebin.com"),
+                SessionRuleBackend::new("ephemeral", SessionRuleActionBackend::Allow)
+                    .with_ports(SessionPortRangeBackend::new(49152, 65535).unwrap()),
+            ],
+        }
+    }
+
+    #[test]
+    fn allows_matching_session() {
+        let verdict = rules().evaluate(&session("Chrome", "www.google.com", "Google LLC", 443));
+        assert_eq!(verdict.class, SessionClassBackend::Allowed);
+        assert_eq!(verdict.rule.as_deref(), Some("chrome-google"));
+    }
+
+    #[test]
+    fn deny_overrides_allow() {
+        let verdict = rules().evaluate(&session("chrome", "eu.pastebin.com", "GOOGLE", 443));
+        assert_eq!(verdict.class, SessionClassBackend::Denied);
+        assert_eq!(verdict.rule.as_deref(), Some("no-pastebin"));
+    }
+
+    #[test]
+    fn unmatched_session_is_unknown() {
+        let verdict = rules().evaluate(&session("curl", "example.com", "EXAMPLE", 443));
+        assert_eq!(verdict.class, SessionClassBackend::Unknown);
+        assert!(verdict.rule.is_none());
+    }
+
+    #[test]
+    fn set_criterion_does_not_match_missing_field() {
+        let mut unattributed = session("chrome", "www.google.com", "GOOGLE", 443);
+        unattributed.l7_process_name = None;
+        assert_eq!(
+            rules().evaluate(&unattributed).class,
+            SessionClassBackend::Unknown
+        );
+    }
+
+    #[test]
+    fn numeric_and_path_criteria() {
+        let rule = SessionRuleBackend::new("asn", SessionRuleActionBackend::Allow)
+            .with_asn_number(15169)
+            .with_process_path("/Applications/*.app")
+            .with_protocol("tcp");
+        assert!(rule.matches(&session("chrome", "a.com", "GOOGLE", 80)));
+        let case = rule.clone().with_process_path("/applications/*.app");
+        assert!(!case.matches(&session("chrome", "a.com", "GOOGLE", 80)));
+        let other_asn = rule.with_asn_number(1);
+        assert!(!other_asn.matches(&session("chrome", "a.com", "GOOGLE", 80)));
+    }
+
+    #[test]
+    fn rule_set_round_trips() {
+        let set = rules();
+        let json = serde_json::to_string(&set).unwrap();
+        let parsed: SessionRuleSetBackend = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, set);
+    }
+
+    #[test]
+    fn inverted_port_ranges_are_rejected() {
+        assert!(SessionPortRangeBackend::new(443, 80).is_err());
+        assert_eq!(
+            SessionPortRangeBackend::new(80, 80).unwrap(),
+            SessionPortRangeBackend::single(80)
+        );
+        let mut json = serde_json::to_value(rules()).unwrap();
+        json["rules"][2]["ports"]["end"] = 1024.into();
+        assert!(serde_json::from_value::<SessionRuleSetBackend>(json).is_err());
+    }
+}
@@ -0,0 +1,335 @@
+//! Compress individual sessions into per-flow summaries.
+//!
+//! A device sees thousands of [`SessionInfoBackend`] records; most of them are
+//! the same process talking to the same destination on the same port.
+//! [`SessionSummaryBuilderBackend`] groups sessions by process, destination and port
+//! and emits one [`SessionFlowSummaryBackend`] per group, so a device uploads
+//! a [`SessionSummariesBackend`] instead of every session.
+//!
+//! The destination is the domain when known, else the ASN owner, else the raw
+//! IP: the most stable name available for where the traffic goes.
+
+use crate::session_info_backend::SessionInfoBackend;
+use crate::session_typed_backend::SessionCriticalityBackend;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// What a flow's destination was named after.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SessionDestinationKindBackend {
+    Domain,
+    AsnOwner,
+    Ip,
+}
+
+impl SessionDestinationKindBackend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Domain => "domain",
+            Self::AsnOwner => "asn_owner",
+            Self::Ip => "ip",
+        }
+    }
+}
+
+/// Grouping key of a flow.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SessionFlowKeyBackend {
+    /// `l7_process_name`, empty when not attributed.
+    pub process_name: String,
+    /// `l7_process_path`, empty when not attributed.
+    pub process_path: String,
+    /// See [`SessionDestinationKindBackend`].
+    pub destination_kind: String,
+    pub destination: String,
+    pub port: u16,
+    pub protocol: String,
+}
+
+impl SessionFlowKeyBackend {
+    pub fn from_session(session: &SessionInfoBackend) -> Self {
+        let non_empty = |value: &Option<String>| {
+            value
+                .as_deref()
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+        };
+        let (destination_kind, destination) = if let Some(domain) = non_empty(&session.domain) {
+            (SessionDestinationKindBackend::Domain, domain.to_lowercase())
+        } else if let Some(owner) = non_empty(&session.asn_owner) {
+            (SessionDestinationKindBackend::AsnOwner, owner)
+        } else {
+            (
+                SessionDestinationKindBackend::Ip,
+                session.ip.trim().to_string(),
+            )
+        };
+        Self {
+            process_name: non_empty(&session.l7_process_name).unwrap_or_default(),
+            process_path: non_empty(&session.l7_process_path).unwrap_or_default(),
+            destination_kind: destination_kind.as_str().to_string(),
+            destination,
+            port: session.port,
+            protocol: session.protocol.trim().to_ascii_uppercase(),
+        }
+    }
+}
+
+/// Everything observed for one flow.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SessionFlowSummaryBackend {
+    pub key: SessionFlowKeyBackend,
+    /// Sessions folded into this summary.
+    pub count: u64,
+    /// Distinct remote addresses, sorted.
+    pub ips: Vec<String>,
+    /// Distinct `asn_country` values, sorted.
+    pub countries: Vec<String>,
+    /// Distinct process users, sorted.
+    pub users: Vec<String>,
+    /// Highest criticality seen by [`SessionCriticalityBackend::rank`], ties
+    /// broken by value so input order does not matter. Known levels use
+    /// their canonical spelling; anomaly, blacklist and unknown values are
+    /// copied verbatim. Empty when nothing was flagged.
+    pub max_criticality: String,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+/// Upload payload replacing a batch of raw sessions.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SessionSummariesBackend {
+    /// Sessions folded in across every flow.
+    pub sessions: u64,
+    /// Sorted by key.
+    pub flows: Vec<SessionFlowSummaryBackend>,
+}
+
+#[derive(Debug, Clone)]
+struct FlowAccumulator {
+    count: u64,
+    ips: BTreeSet<String>,
+    countries: BTreeSet<String>,
+    users: BTreeSet<String>,
+    max_criticality: SessionCriticalityBackend,
+    first_seen: DateTime<Utc>,
+    last_seen: DateTime<Utc>,
+}
+
+/// Streams sessions into flow summaries. Sessions carry no timestamp, so the
+/// caller says when each one was observed.
+#[derive(Debug, Clone, Default)]
+pub struct SessionSummaryBuilderBackend {
+    sessions: u64,
+    flows: BTreeMap<SessionFlowKeyBackend, FlowAccumulator>,
+}
+
+impl SessionSummaryBuilderBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, session: &SessionInfoBackend, seen: DateTime<Utc>) -> &mut Self {
+        self.sessions += 1;
+        let criticality = SessionCriticalityBackend::parse(&session.criticality);
+        let flow = self
+            .flows
+            .entry(SessionFlowKeyBackend::from_session(session))
+            .or_insert_with(|| FlowAccumulator {
+                count: 0,
+                ips: BTreeSet::new(),
+                countries: BTreeSet::new(),
+                users: BTreeSet::new(),
+                max_criticality: criticality.clone(),
+                first_seen: seen,
+                last_seen: seen,
+            });
+        flow.count += 1;
+        flow.ips.insert(session.ip.trim().to_string());
+        if let Some(country) = &session.asn_country {
+            if !country.trim().is_empty() {
+                flow.countries.insert(country.trim().to_ascii_uppercase());
+            }
+        }
+        if let Some(user) = &session.l7_process_user {
+            if !user.trim().is_empty() {
+                flow.users.insert(user.trim().to_string());
+            }
+        }
+        if (criticality.rank(), &criticality) > (flow.max_criticality.rank(), &flow.max_criticality)
+        {
+            flow.max_criticality = criticality;
+        }
+        flow.first_seen = flow.first_seen.min(seen);
+        flow.last_seen = flow.last_seen.max(seen);
+        self
+    }
+
+    pub fn build(&self) -> SessionSummariesBackend {
+        SessionSummariesBackend {
+            sessions: self.sessions,
+            flows: self
+                .flows
+                .iter()
+                .map(|(key, flow)| SessionFlowSummaryBackend {
+                    key: key.clone(),
+                    count: flow.count,
+                    ips: flow.ips.iter().cloned().collect(),
+                    countries: flow.countries.iter().cloned().collect(),
+                    users: flow.users.iter().cloned().collect(),
+                    max_criticality: flow.max_criticality.as_str().to_string(),
+                    first_seen: flow.first_seen,
+                    last_seen: flow.last_seen,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Summarize a batch of sessions all observed at `seen`.
+pub fn summarize_sessions(
+    sessions: &[SessionInfoBackend],
+    seen: DateTime<Utc>,
+) -> SessionSummariesBackend {
+    let mut builder = SessionSummaryBuilderBackend::new();
+    for session in sessions {
+        builder.add(session, seen);
+    }
+    builder.build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures as fixtures;
+
+    fn session(
+        ip: &str,
+        domain: Option<&str>,
+        asn_owner: Option<&str>,
+        country: &str,
+        criticality: &str,
+    ) -> SessionInfoBackend {
+        SessionInfoBackend {
+            domain: domain.map(str::to_string),
+            asn_country: Some(country.to_string()),
+            asn_owner: asn_owner.map(str::to_string),
+            criticality: criticality.to_string(),
+            ..fixtures::session(ip)
+        }
+    }
+
+    fn at(timestamp: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(timestamp)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn folds_sessions_into_flows() {
+        let mut builder = SessionSummaryBuilderBackend::new();
+        builder
+            .add(
+                &session("1.1.1.1", Some("Google.com"), Some("GOOGLE"), "us", ""),
+                at("2026-01-01T10:00:00Z"),
+            )
+            .add(
+                &session("1.1.1.2", Some("google.com"), Some("GOOGLE"), "IE", "High"),
+                at("2026-01-01T09:00:00Z"),
+            )
+            .add(
+                &session("1.1.1.3", None, Some("GOOGLE"), "US", "low"),
+                at("2026-01-01T11:00:00Z"),
+            )
+            .add(
+                &session("9.9.9.9", None, None, "", "anomaly:odd"),
+                at("2026-01-01T12:00:00Z"),
+            );
+        let summaries = builder.build();
+        assert_eq!(summaries.sessions, 4);
+        assert_eq!(summaries.flows.len(), 3);
+
+        let google = summaries
+            .flows
+            .iter()
+            .find(|f| f.key.destination == "google.com")
+            .unwrap();
+        assert_eq!(google.key.destination_kind, "domain");
+        assert_eq!(google.key.protocol, "TCP");
+        assert_eq!(google.count, 2);
+        assert_eq!(google.ips, vec!["1.1.1.1", "1.1.1.2"]);
+        assert_eq!(google.countries, vec!["IE", "US"]);
+        assert_eq!(google.max_criticality, "High");
+        assert_eq!(google.first_seen, at("2026-01-01T09:00:00Z"));
+        assert_eq!(google.last_seen, at("2026-01-01T10:00:00Z"));
+
+        let asn = summaries
+            .flows
+            .iter()
+            .find(|f| f.key.destination_kind == "asn_owner")
+            .unwrap();
+        assert_eq!(asn.key.destination, "GOOGLE");
+        assert_eq!(asn.max_criticality, "Low");
+
+        let raw = summaries
+            .flows
+            .iter()
+            .find(|f| f.key.destination_kind == "ip")
+            .unwrap();
+        assert_eq!(raw.key.destination, "9.9.9.9");
+        assert!(raw.countries.is_empty());
+        assert_eq!(raw.max_criticality, "anomaly:odd");
+    }
+
+    #[test]
+    fn flagged_criticality_outranks_normal() {
+        let seen = at("2026-01-01T00:00:00Z");
+        let summaries = summarize_sessions(
+            &[
+                session("9.9.9.9", None, None, "", "anomaly:odd"),
+                session("9.9.9.9", None, None, "", ""),
+            ],
+            seen,
+        );
+        assert_eq!(summaries.flows[0].max_criticality, "anomaly:odd");
+
+        let summaries = summarize_sessions(
+            &[
+                session("9.9.9.9", None, None, "", "anomaly:odd"),
+                session("9.9.9.9", None, None, "", "blacklist:firehol_level1"),
+                session("9.9.9.9", None, None, "", "Medium"),
+            ],
+            seen,
+        );
+        assert_eq!(
+            summaries.flows[0].max_criticality,
+            "blacklist:firehol_level1"
+        );
+    }
+
+    #[test]
+    fn equal_rank_criticalities_do_not_depend_on_order() {
+        let seen = at("2026-01-01T00:00:00Z");
+        let mut sessions = vec![
+            session("9.9.9.9", None, None, "", "anomaly:suspicious"),
+            session("9.9.9.9", None, None, "", "anomaly:abnormal"),
+        ];
+        let forward = summarize_sessions(&sessions, seen);
+        sessions.reverse();
+        assert_eq!(summarize_sessions(&sessions, seen), forward);
+        assert_eq!(forward.flows[0].max_criticality, "anomaly:suspicious");
+    }
+
+    #[test]
+    fn payload_round_trips() {
+        let summaries = summarize_sessions(
+            &[session("1.1.1.1", Some("a.com"), None, "US", "")],
+            at("2026-01-01T00:00:00Z"),
+        );
+        let json = serde_json::to_string(&summaries).unwrap();
+        let parsed: SessionSummariesBackend = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, summaries);
+    }
+}
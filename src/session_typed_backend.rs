@@ -0,0 +1,440 @@
+//! Typed view of a [`SessionInfoBackend`].
+//!
+//! The wire struct keeps `ip`, `protocol` and `criticality` as strings, so
+//! every consumer re-parsed them and disagreed on casing. [`TypedSessionInfoBackend`]
+//! carries an [`IpAddr`] and two enums instead. Conversion validates both
+//! ways: wire to typed rejects an address that does not parse, and typed to
+//! wire rejects a fallback variant that smuggles a value the enum names, so a
+//! round trip always emits the canonical spelling.
+//!
+//! That includes the address: typed to wire writes the [`IpAddr`] back in
+//! its standard text form, so `2001:DB8::0001` comes back as `2001:db8::1`.
+//! An IPv6 zone id (`fe80::1%en0`) has no room in an [`IpAddr`] and is kept
+//! beside it in [`TypedSessionInfoBackend::ip_zone`].
+//!
+//! The wire JSON does not change; this is a view, not a replacement. The
+//! enums serialize as their wire spelling.
+
+use crate::session_info_backend::SessionInfoBackend;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::Display;
+use std::net::IpAddr;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SessionProtocolBackend {
+    Tcp,
+    Udp,
+    Icmp,
+    /// Anything else, verbatim.
+    Other(String),
+}
+
+impl SessionProtocolBackend {
+    /// Case-insensitive. Never fails: unknown values land in `Other`.
+    pub fn parse(value: &str) -> Self {
+        let value = value.trim();
+        match value.to_ascii_uppercase().as_str() {
+            "TCP" => Self::Tcp,
+            "UDP" => Self::Udp,
+            "ICMP" => Self::Icmp,
+            _ => Self::Other(value.to_string()),
+        }
+    }
+
+    /// Canonical wire spelling.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Tcp => "TCP",
+            Self::Udp => "UDP",
+            Self::Icmp => "ICMP",
+            Self::Other(value) => value,
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        if let Self::Other(value) = self {
+            if value.trim().is_empty() {
+                return Err(anyhow!("empty session protocol"));
+            }
+            if !matches!(Self::parse(value), Self::Other(_)) {
+                return Err(anyhow!("non-canonical session protocol: {value:?}"));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// As its wire spelling, like `SessionInfoBackend::protocol`.
+impl Serialize for SessionProtocolBackend {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for SessionProtocolBackend {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        Ok(Self::parse(&String::deserialize(deserializer)?))
+    }
+}
+
+impl Display for SessionProtocolBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SessionCriticalityBackend {
+    /// Nothing flagged. Empty on the wire.
+    Normal,
+    Low,
+    Medium,
+    High,
+    Critical,
+    /// Flagged by the anomaly detector: one or more comma-separated
+    /// `anomaly:<reason>` tags and no blacklist tag, verbatim.
+    Anomaly(String),
+    /// On at least one blacklist: one or more comma-separated
+    /// `blacklist:<list>` tags, possibly alongside anomaly tags, verbatim.
+    Blacklist(String),
+    /// A value this build does not know, verbatim.
+    Unknown(String),
+}
+
+impl SessionCriticalityBackend {
+    /// Case-insensitive. Never fails: unknown values land in `Unknown`.
+    pub fn parse(value: &str) -> Self {
+        let value = value.trim();
+        match value.to_ascii_lowercase().as_str() {
+            "" | "normal" => Self::Normal,
+            "low" => Self::Low,
+            "medium" => Self::Medium,
+            "high" => Self::High,
+            "critical" => Self::Critical,
+            lower => {
+                let has_tag =
+                    |prefix: &str| lower.split(',').any(|tag| tag.trim().starts_with(prefix));
+                if has_tag("blacklist:") {
+                    Self::Blacklist(value.to_string())
+                } else if has_tag("anomaly:") {
+                    Self::Anomaly(value.to_string())
+                } else {
+                    Self::Unknown(value.to_string())
+                }
+            }
+        }
+    }
+
+    /// Canonical wire spelling.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Normal => "",
+            Self::Low => "Low",
+            Self::Medium => "Medium",
+            Self::High => "High",
+            Self::Critical => "Critical",
+            Self::Anomaly(value) | Self::Blacklist(value) | Self::Unknown(value) => value,
+        }
+    }
+
+    /// Ordering rank. Any non-empty value is a flag, so even an `Unknown`
+    /// one ranks above `Normal`; an anomaly sits between `Medium` and
+    /// `High`, a blacklist hit between `High` and `Critical`.
+    pub fn rank(&self) -> u8 {
+        match self {
+            Self::Normal => 0,
+            Self::Unknown(_) => 1,
+            Self::Low => 2,
+            Self::Medium => 3,
+            Self::Anomaly(_) => 4,
+            Self::High => 5,
+            Self::Blacklist(_) => 6,
+            Self::Critical => 7,
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        let reparsed = match self {
+            Self::Anomaly(value) | Self::Blacklist(value) | Self::Unknown(value) => {
+                Self::parse(value)
+            }
+            _ => return Ok(()),
+        };
+        if std::mem::discriminant(&reparsed) != std::mem::discriminant(self) {
+            return Err(anyhow!(
+                "non-canonical session criticality: {:?}",
+                self.as_str()
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// As its wire spelling, like `SessionInfoBackend::criticality`.
+impl Serialize for SessionCriticalityBackend {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for SessionCriticalityBackend {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        Ok(Self::parse(&String::deserialize(deserializer)?))
+    }
+}
+
+impl Display for SessionCriticalityBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TypedSessionInfoBackend {
+    pub ip: IpAddr,
+    /// IPv6 zone id, the part after `%` in `fe80::1%en0`.
+    pub ip_zone: Option<String>,
+    pub port: u16,
+    pub protocol: SessionProtocolBackend,
+    pub domain: Option<String>,
+    pub asn_number: Option<u32>,
+    pub asn_country: Option<String>,
+    pub asn_owner: Option<String>,
+    pub criticality: SessionCriticalityBackend,
+    pub service: Option<String>,
+    pub l7_process_name: Option<String>,
+    pub l7_process_path: Option<String>,
+    pub l7_process_user: Option<String>,
+}
+
+impl TryFrom<&SessionInfoBackend> for TypedSessionInfoBackend {
+    type Error = anyhow::Error;
+
+    fn try_from(session: &SessionInfoBackend) -> Result<Self> {
+        let (ip, ip_zone) = parse_session_ip(&session.ip)?;
+        Ok(Self {
+            ip,
+            ip_zone,
+            port: session.port,
+            protocol: SessionProtocolBackend::parse(&session.protocol),
+            domain: session.domain.clone(),
+            asn_number: session.asn_number,
+            asn_country: session.asn_country.clone(),
+            asn_owner: session.asn_owner.clone(),
+            criticality: SessionCriticalityBackend::parse(&session.criticality),
+            service: session.service.clone(),
+            l7_process_name: session.l7_process_name.clone(),
+            l7_process_path: session.l7_process_path.clone(),
+            l7_process_user: session.l7_process_user.clone(),
+        })
+    }
+}
+
+impl TryFrom<&TypedSessionInfoBackend> for SessionInfoBackend {
+    type Error = anyhow::Error;
+
+    fn try_from(session: &TypedSessionInfoBackend) -> Result<Self> {
+        session.protocol.validate()?;
+        session.criticality.validate()?;
+        let ip = match &session.ip_zone {
+            None => session.ip.to_string(),
+            Some(zone) if session.ip.is_ipv6() && valid_zone(zone) => {
+                format!("{}%{zone}", session.ip)
+            }
+            Some(zone) => return Err(anyhow!("invalid zone {zone:?} for {}", session.ip)),
+        };
+        Ok(Self {
+            ip,
+            port: session.port,
+            protocol: session.protocol.as_str().to_string(),
+            domain: session.domain.clone(),
+            asn_number: session.asn_number,
+            asn_country: session.asn_country.clone(),
+            asn_owner: session.asn_owner.clone(),
+            criticality: session.criticality.as_str().to_string(),
+            service: session.service.clone(),
+            l7_process_name: session.l7_process_name.clone(),
+            l7_process_path: session.l7_process_path.clone(),
+            l7_process_user: session.l7_process_user.clone(),
+        })
+    }
+}
+
+/// `ip` with its IPv6 zone id, if any, split off.
+fn parse_session_ip(value: &str) -> Result<(IpAddr, Option<String>)> {
+    let trimmed = value.trim();
+    let (address, zone) = match trimmed.split_once('%') {
+        Some((address, zone)) => (address, Some(zone)),
+        None => (trimmed, None),
+    };
+    let ip = address
+        .parse::<IpAddr>()
+        .map_err(|e| anyhow!("invalid session ip {value:?}: {e}"))?;
+    match zone {
+        None => Ok((ip, None)),
+        Some(zone) if ip.is_ipv6() && valid_zone(zone) => Ok((ip, Some(zone.to_string()))),
+        Some(_) => Err(anyhow!("invalid zone in session ip {value:?}")),
+    }
+}
+
+fn valid_zone(zone: &str) -> bool {
+    !zone.is_empty() && !zone.contains(|c: char| c.is_whitespace() || c == '%')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures as fixtures;
+
+    fn session(ip: &str, protocol: &str, criticality: &str) -> SessionInfoBackend {
+        SessionInfoBackend {
+            protocol: protocol.to_string(),
+            criticality: criticality.to_string(),
+            ..fixtures::session(ip)
+        }
+    }
+
+    #[test]
+    fn parses_wire_values_case_insensitively() {
+        let typed = session("10.0.0.1", "tcp", "HIGH").typed().unwrap();
+        assert_eq!(typed.ip, "10.0.0.1".parse::<IpAddr>().unwrap());
+        assert_eq!(typed.protocol, SessionProtocolBackend::Tcp);
+        assert_eq!(typed.criticality, SessionCriticalityBackend::High);
+
+        let typed = session("::1", "SCTP", "blacklist:firehol").typed().unwrap();
+        assert!(typed.ip.is_ipv6());
+        assert_eq!(
+            typed.protocol,
+            SessionProtocolBackend::Other("SCTP".to_string())
+        );
+        assert_eq!(
+            typed.criticality,
+            SessionCriticalityBackend::Blacklist("blacklist:firehol".to_string())
+        );
+
+        let typed = session(
+            "10.0.0.1",
+            "TCP",
+            "anomaly:abnormal, blacklist:firehol_level1",
+        )
+        .typed()
+        .unwrap();
+        assert!(matches!(
+            typed.criticality,
+            SessionCriticalityBackend::Blacklist(_)
+        ));
+        let typed = session("10.0.0.1", "TCP", "anomaly:suspicious")
+            .typed()
+            .unwrap();
+        assert_eq!(
+            typed.criticality,
+            SessionCriticalityBackend::Anomaly("anomaly:suspicious".to_string())
+        );
+        let typed = session("10.0.0.1", "TCP", "greylist:x").typed().unwrap();
+        assert_eq!(
+            typed.criticality,
+            SessionCriticalityBackend::Unknown("greylist:x".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_unparseable_ip() {
+        assert!(session("10.0.0.256", "TCP", "").typed().is_err());
+        assert!(session("", "TCP", "").typed().is_err());
+        assert!(session("10.0.0.1%en0", "TCP", "").typed().is_err());
+        assert!(session("fe80::1%", "TCP", "").typed().is_err());
+    }
+
+    #[test]
+    fn keeps_the_ipv6_zone() {
+        let typed = session("fe80::1%en0", "UDP", "").typed().unwrap();
+        assert_eq!(typed.ip, "fe80::1".parse::<IpAddr>().unwrap());
+        assert_eq!(typed.ip_zone.as_deref(), Some("en0"));
+        let back = SessionInfoBackend::try_from(&typed).unwrap();
+        assert_eq!(back.ip, "fe80::1%en0");
+
+        let mut typed = session("10.0.0.1", "TCP", "").typed().unwrap();
+        assert_eq!(typed.ip_zone, None);
+        typed.ip_zone = Some("en0".to_string());
+        assert!(SessionInfoBackend::try_from(&typed).is_err());
+    }
+
+    #[test]
+    fn round_trip_writes_the_standard_address_text() {
+        let typed = session("2001:DB8::0001", "TCP", "").typed().unwrap();
+        let back = SessionInfoBackend::try_from(&typed).unwrap();
+        assert_eq!(back.ip, "2001:db8::1");
+    }
+
+    #[test]
+    fn typed_view_serializes_with_wire_spellings() {
+        let typed = session("fe80::1%en0", "tcp", "anomaly:odd")
+            .typed()
+            .unwrap();
+        let json = serde_json::to_value(&typed).unwrap();
+        assert_eq!(json["ip"], "fe80::1");
+        assert_eq!(json["ip_zone"], "en0");
+        assert_eq!(json["protocol"], "TCP");
+        assert_eq!(json["criticality"], "anomaly:odd");
+        let parsed: TypedSessionInfoBackend = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, typed);
+
+        let normal: SessionCriticalityBackend = serde_json::from_str(r#""""#).unwrap();
+        assert_eq!(normal, SessionCriticalityBackend::Normal);
+    }
+
+    #[test]
+    fn round_trip_canonicalizes_casing() {
+        let wire = session("10.0.0.1", "udp", "medium");
+        let typed = wire.typed().unwrap();
+        let back = SessionInfoBackend::try_from(&typed).unwrap();
+        assert_eq!(back.protocol, "UDP");
+        assert_eq!(back.criticality, "Medium");
+        assert_eq!(back.ip, "10.0.0.1");
+        assert_eq!(back.domain, wire.domain);
+
+        let unknown = session("10.0.0.1", "GRE", "anomaly:odd").typed().unwrap();
+        let back = SessionInfoBackend::try_from(&unknown).unwrap();
+        assert_eq!(back.protocol, "GRE");
+        assert_eq!(back.criticality, "anomaly:odd");
+    }
+
+    #[test]
+    fn typed_to_wire_rejects_smuggled_known_values() {
+        let mut typed = session("10.0.0.1", "TCP", "").typed().unwrap();
+        typed.protocol = SessionProtocolBackend::Other("tcp".to_string());
+        assert!(SessionInfoBackend::try_from(&typed).is_err());
+
+        typed.protocol = SessionProtocolBackend::Other(String::new());
+        assert!(SessionInfoBackend::try_from(&typed).is_err());
+
+        typed.protocol = SessionProtocolBackend::Tcp;
+        typed.criticality = SessionCriticalityBackend::Unknown("High".to_string());
+        assert!(SessionInfoBackend::try_from(&typed).is_err());
+        typed.criticality = SessionCriticalityBackend::Unknown("anomaly:odd".to_string());
+        assert!(SessionInfoBackend::try_from(&typed).is_err());
+        typed.criticality = SessionCriticalityBackend::Anomaly("blacklist:x".to_string());
+        assert!(SessionInfoBackend::try_from(&typed).is_err());
+        typed.criticality = SessionCriticalityBackend::Blacklist("blacklist:x".to_string());
+        assert!(SessionInfoBackend::try_from(&typed).is_ok());
+    }
+
+    #[test]
+    fn wire_json_is_unchanged() {
+        let wire = session("10.0.0.1", "TCP", "High");
+        let before = serde_json::to_value(&wire).unwrap();
+        let back = SessionInfoBackend::try_from(&wire.typed().unwrap()).unwrap();
+        assert_eq!(serde_json::to_value(&back).unwrap(), before);
+    }
+
+    #[test]
+    fn criticality_rank_puts_any_flag_above_normal() {
+        let rank = |value: &str| SessionCriticalityBackend::parse(value).rank();
+        assert!(rank("Critical") > rank("High"));
+        assert!(rank("x") > rank(""));
+        assert!(rank("anomaly:odd") > rank("Medium"));
+        assert!(rank("blacklist:firehol") > rank("anomaly:odd"));
+        assert!(rank("blacklist:firehol") > rank("High"));
+    }
+}
@@ -0,0 +1,978 @@
+//! Canonical, versioned content hashing for backend types.
+//!
+//! Several `uid()`s hash `format!("{:?}", …)`, so a derive change, a field
+//! reorder or a new `Debug` impl silently changes every uid and busts the Hub
+//! cache. [`StableHash`] instead feeds a [`StableHasher`] an explicit
+//! encoding that only changes when [`STABLE_HASH_VERSION`] does:
+//!
+//! - every digest starts with a domain string and the encoding version;
+//! - strings, byte strings and sequences are prefixed with their length
+//!   (u64 little-endian), so `("ab", "c")` and `("a", "bc")` differ;
+//! - integers are fixed-width little-endian, `usize` widened to u64;
+//! - floats are their IEEE bits, with `-0.0` folded into `0.0` and every NaN
+//!   into one canonical NaN;
+//! - `None` is a `0` marker, `Some(v)` a `1` marker followed by `v`;
+//! - struct fields are written as (name, value) pairs in a fixed order, after
+//!   the field count; enum values as their variant name, then their fields.
+//!
+//! Every type exchanged with the Hub implements it. The implementations
+//! below destructure every struct and match every enum exhaustively: adding
+//! a field or variant does not compile until someone decides how it hashes.
+//! The golden tests pin the digests of fixed samples; if one fails, the
+//! encoding changed and [`STABLE_HASH_VERSION`] must be bumped alongside a
+//! uid migration.
+
+use crate::advisor_todos_backend::*;
+use crate::agent_inventory_backend::*;
+use crate::agentic_backend::*;
+use crate::agentic_dismissal_report_backend::*;
+use crate::ai_whitelist_backend::*;
+use crate::detail_backend::*;
+use crate::feedback_info_backend::*;
+use crate::helper_state_backend::*;
+use crate::history_backend::*;
+use crate::lanscan_device_info_backend::*;
+use crate::lanscan_dislike_device_info_backend::*;
+use crate::lanscan_port_info_backend::*;
+use crate::lanscan_vulnerability_info_backend::*;
+use crate::order_backend::*;
+use crate::order_type_backend::*;
+use crate::policy_backend::*;
+use crate::pwned_backend::*;
+use crate::score_backend::*;
+use crate::session_info_backend::*;
+use crate::threat_backend::*;
+use blake3::Hasher;
+use chrono::{DateTime, Utc};
+
+/// Bump whenever the encoding of any type changes.
+pub const STABLE_HASH_VERSION: u8 = 1;
+
+const STABLE_HASH_DOMAIN: &str = "edamame-stable-hash";
+
+/// blake3 over the canonical encoding.
+pub struct StableHasher {
+    hasher: Hasher,
+}
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StableHasher {
+    pub fn new() -> Self {
+        let mut hasher = Self {
+            hasher: Hasher::new(),
+        };
+        hasher.write_str(STABLE_HASH_DOMAIN);
+        hasher.write_u8(STABLE_HASH_VERSION);
+        hasher
+    }
+
+    pub fn write_u8(&mut self, value: u8) {
+        self.hasher.update(&[value]);
+    }
+
+    pub fn write_u16(&mut self, value: u16) {
+        self.hasher.update(&value.to_le_bytes());
+    }
+
+    pub fn write_u32(&mut self, value: u32) {
+        self.hasher.update(&value.to_le_bytes());
+    }
+
+    pub fn write_u64(&mut self, value: u64) {
+        self.hasher.update(&value.to_le_bytes());
+    }
+
+    pub fn write_i32(&mut self, value: i32) {
+        self.hasher.update(&value.to_le_bytes());
+    }
+
+    pub fn write_i64(&mut self, value: i64) {
+        self.hasher.update(&value.to_le_bytes());
+    }
+
+    pub fn write_bool(&mut self, value: bool) {
+        self.write_u8(value as u8);
+    }
+
+    pub fn write_f64(&mut self, value: f64) {
+        let value = if value == 0.0 {
+            0.0
+        } else if value.is_nan() {
+            f64::NAN
+        } else {
+            value
+        };
+        self.write_u64(value.to_bits());
+    }
+
+    /// Length, then bytes.
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.write_len(bytes.len());
+        self.hasher.update(bytes);
+    }
+
+    pub fn write_str(&mut self, value: &str) {
+        self.write_bytes(value.as_bytes());
+    }
+
+    /// Element or field count.
+    pub fn write_len(&mut self, len: usize) {
+        self.write_u64(len as u64);
+    }
+
+    /// Field name, then value.
+    pub fn write_field<T: StableHash + ?Sized>(&mut self, name: &str, value: &T) {
+        self.write_str(name);
+        value.stable_hash_into(self);
+    }
+
+    /// Hex digest.
+    pub fn finish(&self) -> String {
+        self.hasher.finalize().to_hex().to_string()
+    }
+}
+
+pub trait StableHash {
+    fn stable_hash_into(&self, hasher: &mut StableHasher);
+
+    /// Hex digest of this value alone.
+    fn stable_hash(&self) -> String {
+        let mut hasher = StableHasher::new();
+        self.stable_hash_into(&mut hasher);
+        hasher.finish()
+    }
+}
+
+impl<T: StableHash + ?Sized> StableHash for &T {
+    fn stable_hash_into(&self, hasher: &mut StableHasher) {
+        (**self).stable_hash_into(hasher);
+    }
+}
+
+impl StableHash for str {
+    fn stable_hash_into(&self, hasher: &mut StableHasher) {
+        hasher.write_str(self);
+    }
+}
+
+impl StableHash for String {
+    fn stable_hash_into(&self, hasher: &mut StableHasher) {
+        hasher.write_str(self);
+    }
+}
+
+impl StableHash for bool {
+    fn stable_hash_into(&self, hasher: &mut StableHasher) {
+        hasher.write_bool(*self);
+    }
+}
+
+impl StableHash for u8 {
+    fn stable_hash_into(&self, hasher: &mut StableHasher) {
+        hasher.write_u8(*self);
+    }
+}
+
+impl StableHash for u16 {
+    fn stable_hash_into(&self, hasher: &mut StableHasher) {
+        hasher.write_u16(*self);
+    }
+}
+
+impl StableHash for u32 {
+    fn stable_hash_into(&self, hasher: &mut StableHasher) {
+        hasher.write_u32(*self);
+    }
+}
+
+impl StableHash for u64 {
+    fn stable_hash_into(&self, hasher: &mut StableHasher) {
+        hasher.write_u64(*self);
+    }
+}
+
+impl StableHash for usize {
+    fn stable_hash_into(&self, hasher: &mut StableHasher) {
+        hasher.write_u64(*self as u64);
+    }
+}
+
+impl StableHash for i32 {
+    fn stable_hash_into(&self, hasher: &mut StableHasher) {
+        hasher.write_i32(*self);
+    }
+}
+
+impl StableHash for i64 {
+    fn stable_hash_into(&self, hasher: &mut StableHasher) {
+        hasher.write_i64(*self);
+    }
+}
+
+impl StableHash for f64 {
+    fn stable_hash_into(&self, hasher: &mut StableHasher) {
+        hasher.write_f64(*self);
+    }
+}
+
+impl<T: StableHash> StableHash for Option<T> {
+    fn stable_hash_into(&self, hasher: &mut StableHasher) {
+        match self {
+            None => hasher.write_u8(0),
+            Some(value) => {
+                hasher.write_u8(1);
+                value.stable_hash_into(hasher);
+            }
+        }
+    }
+}
+
+impl<T: StableHash> StableHash for [T] {
+    fn stable_hash_into(&self, hasher: &mut StableHasher) {
+        hasher.write_len(self.len());
+        for item in self {
+            item.stable_hash_into(hasher);
+        }
+    }
+}
+
+impl<T: StableHash> StableHash for Vec<T> {
+    fn stable_hash_into(&self, hasher: &mut StableHasher) {
+        self.as_slice().stable_hash_into(hasher);
+    }
+}
+
+impl<A: StableHash, B: StableHash> StableHash for (A, B) {
+    fn stable_hash_into(&self, hasher: &mut StableHasher) {
+        self.0.stable_hash_into(hasher);
+        self.1.stable_hash_into(hasher);
+    }
+}
+
+/// Seconds and nanoseconds since the epoch: the offset a timestamp was
+/// written with does not matter.
+impl StableHash for DateTime<Utc> {
+    fn stable_hash_into(&self, hasher: &mut StableHasher) {
+        hasher.write_i64(self.timestamp());
+        hasher.write_u32(self.timestamp_subsec_nanos());
+    }
+}
+
+/// `StableHash` for a struct: field count, then (name, value) per field, in
+/// the listed order. The destructuring makes the field list exhaustive.
+macro_rules! stable_hash_struct {
+    ($ty:ident { $($field:ident),* $(,)? }) => {
+        impl StableHash for $ty {
+            fn stable_hash_into(&self, hasher: &mut StableHasher) {
+                let $ty { $($field),* } = self;
+                hasher.write_len([$(stringify!($field)),*].len());
+                $(hasher.write_field(stringify!($field), $field);)*
+            }
+        }
+    };
+}
+
+/// `StableHash` for a fieldless enum: the variant name.
+macro_rules! stable_hash_unit_enum {
+    ($ty:ident { $($variant:ident),* $(,)? }) => {
+        impl StableHash for $ty {
+            fn stable_hash_into(&self, hasher: &mut StableHasher) {
+                hasher.write_str(match self {
+                    $($ty::$variant => stringify!($variant),)*
+                });
+            }
+        }
+    };
+}
+
+/// `StableHash` for an enum of struct variants: variant name, then its fields
+/// as for a struct.
+macro_rules! stable_hash_struct_enum {
+    ($ty:ident { $($variant:ident { $($field:ident),* $(,)? }),* $(,)? }) => {
+        impl StableHash for $ty {
+            fn stable_hash_into(&self, hasher: &mut StableHasher) {
+                match self {
+                    $($ty::$variant { $($field),* } => {
+                        hasher.write_str(stringify!($variant));
+                        hasher.write_len([$(stringify!($field)),*].len());
+                        $(hasher.write_field(stringify!($field), $field);)*
+                    })*
+                }
+            }
+        }
+    };
+}
+
+// advisor_todos_backend
+stable_hash_unit_enum!(AdviceTypeBackend {
+    Policy,
+    Threat,
+    NetworkPort,
+    NetworkSession,
+    PwnedBreach,
+    Configure,
+});
+stable_hash_struct!(AdvisorAdviceBackend {
+    advice_type,
+    advice_details,
+});
+stable_hash_unit_enum!(AdvicePriorityBackend {
+    Low,
+    Medium,
+    High,
+    Critical,
+});
+stable_hash_struct!(AdvisorTodoBackend { advice, priority });
+stable_hash_struct!(AdvisorTodosBackend {
+    system_overview,
+    todos,
+    email,
+    question,
+});
+
+// agent_inventory_backend
+stable_hash_struct!(AgentInventoryRowBackend {
+    agent_type,
+    display_name,
+    installed,
+    installed_on_host,
+    discovered,
+    observer_enabled,
+    mcp_endpoint_count,
+    component_count,
+    alertable_finding_count,
+});
+stable_hash_struct!(AgentInventoryBackend {
+    timestamp,
+    hostname,
+    ip4,
+    ip6,
+    model,
+    os_version,
+    agent_count,
+    unobserved_count,
+    alertable_finding_count,
+    agents,
+});
+
+// agentic_backend
+stable_hash_struct!(AgenticAnalysisRequestBackend {
+    prompt,
+    system_prompt,
+    max_tokens,
+    analysis_type,
+});
+stable_hash_struct!(AgenticDecisionBackend {
+    action,
+    reasoning,
+    risk_score,
+    priority,
+    recommended_actions,
+});
+stable_hash_struct!(AgenticAnalysisResponseBackend {
+    decision,
+    input_tokens,
+    output_tokens,
+});
+stable_hash_struct!(AgenticSubscriptionStatusBackend { plan_name, usage });
+stable_hash_unit_enum!(AgenticNotificationSourceBackend {
+    Vulnerability,
+    Divergence,
+    ActionReport,
+    Escalation,
+});
+stable_hash_unit_enum!(AgenticNotificationCriticalityBackend {
+    Info,
+    Warning,
+    Critical,
+});
+stable_hash_struct!(AgenticNotificationFindingBackend {
+    finding_key,
+    severity,
+    description,
+    reference,
+    process_name,
+    destination_domain,
+    destination_ip,
+    destination_port,
+    dismissed,
+});
+stable_hash_struct!(AgenticNotificationActionBackend {
+    action_id,
+    action_type,
+    advice_type,
+    result_status,
+    risk_score,
+    priority,
+    reasoning,
+    description,
+    error,
+    undo_available,
+    session_display,
+});
+stable_hash_struct!(AgenticNotificationBackend {
+    source,
+    criticality,
+    timestamp,
+    hostname,
+    ip4,
+    ip6,
+    model,
+    os_version,
+    title,
+    body,
+    process_name,
+    destination_domain,
+    destination_ip,
+    destination_port,
+    active_findings_count,
+    security_score,
+    verdict,
+    decision_source,
+    findings,
+    actions,
+    auto_resolved_count,
+    requires_confirmation_count,
+    escalated_count,
+    failed_count,
+});
+
+// agentic_dismissal_report_backend
+stable_hash_struct!(AgenticDismissalReportBackend {
+    domain,
+    finding_key,
+    finding_title,
+    finding_severity,
+    check_or_category,
+    scope,
+    severity_ceiling,
+    ttl_secs,
+    process_name,
+    process_path,
+    parent_process_name,
+    parent_process_path,
+    parent_script_path,
+    material_classes,
+    sensitive_path_classes,
+    destination_class,
+    destination_port,
+    agent_type,
+    agent_instance_id,
+    workspace_root,
+    reason,
+    note,
+    email,
+    os_name,
+    os_version,
+    core_version,
+});
+
+// ai_whitelist_backend
+stable_hash_struct!(AiWhitelistStatusBackend {
+    fits,
+    covered_by,
+    evidence_shared,
+    truncated,
+    violations,
+    whitelists,
+});
+stable_hash_struct!(AiWhitelistBackend {
+    whitelist_id,
+    name,
+    enforced_kinds,
+    allowed,
+    accepted,
+    covered,
+    fits,
+    not_permitted,
+    not_accepted,
+});
+stable_hash_struct!(AiWhitelistSelectorBackend { kind, key, scope });
+stable_hash_struct!(AiWhitelistUncoveredCheckBackend { check, causes });
+
+// detail_backend. The `*KindBackend`/`*ModeBackend` vocabularies travel as
+// strings, so the strings are what gets hashed.
+stable_hash_struct!(FailureSelectorBackend { kind, key });
+stable_hash_struct!(FailureCauseBackend { scope, selectors });
+stable_hash_struct!(CheckContextBackend { kind, key, scope });
+stable_hash_struct!(CheckDetailBackend {
+    check,
+    causes,
+    context,
+    truncated,
+});
+stable_hash_struct!(CoverageRowBackend {
+    kind,
+    key,
+    present,
+    monitored,
+});
+stable_hash_struct!(DetailBackend {
+    domain,
+    mode,
+    coverage,
+    inventory,
+    checks,
+});
+stable_hash_struct!(AiInventoryBackend {
+    host,
+    harnesses,
+    agents,
+    truncated,
+});
+stable_hash_struct!(AiHostInventoryBackend {
+    assessed,
+    passwordless_root,
+    admin_user,
+    elevated_session,
+    user,
+    platform,
+});
+stable_hash_struct!(AiHarnessInventoryBackend {
+    slug,
+    display_name,
+    detected,
+});
+stable_hash_struct!(AiAgentInventoryBackend {
+    key,
+    present,
+    monitored,
+    sandbox,
+    amplifiers,
+    critical_processes,
+    secret_exposure_labels,
+    mcp_servers,
+});
+stable_hash_struct!(AiSandboxInventoryBackend {
+    sandboxed,
+    mechanism,
+    file_access_scope,
+});
+stable_hash_struct!(AiAmplifiersInventoryBackend {
+    unsandboxed,
+    passwordless_root,
+    critical_subprocess,
+    secret_exposure,
+});
+stable_hash_struct!(AiMcpServerInventoryBackend {
+    server_name,
+    transport,
+    exposure_scope,
+    auth_strength,
+    is_edamame_server,
+    max_severity,
+    alertable,
+    rule_ids,
+});
+
+// feedback_info_backend
+stable_hash_struct!(FeedbackInfoBackend {
+    core_info,
+    threat_model_name,
+    threat_model_date,
+    threat_model_signature,
+    stars,
+    helper_state,
+    os_name,
+    os_version,
+    context,
+    note,
+    email,
+    app_log,
+    helper_log,
+});
+
+// helper_state_backend
+stable_hash_unit_enum!(HelperStateBackend {
+    Disabled,
+    Enabled,
+    EnabledFullDisk,
+    Outdated,
+    Fatal,
+    Unsupported,
+});
+
+// history_backend, order_backend, order_type_backend
+stable_hash_struct!(OrderHistoryBackend { history });
+stable_hash_struct!(MetricOrderResultBackend {
+    metricname,
+    ordertype,
+    timestamp,
+    success,
+    validated,
+});
+stable_hash_unit_enum!(MetricOrderTypeBackend {
+    Capture,
+    Remediate,
+    Rollback,
+});
+
+// lanscan_*_backend
+stable_hash_struct!(DeviceInfoBackend {
+    mdns_services,
+    device_vendor,
+    vulnerabilities,
+    open_ports,
+});
+stable_hash_struct!(DislikeDeviceInfoBackend {
+    device_type,
+    open_ports,
+    mdns_services,
+    device_vendor,
+    hostname,
+    note,
+});
+stable_hash_struct!(PortInfoBackend {
+    port,
+    protocol,
+    service,
+    banner,
+    vulnerabilities,
+});
+stable_hash_struct!(VulnerabilityInfoBackend {
+    name,
+    description,
+    cve_id,
+    cvss_vector,
+    cvss_score,
+    epss,
+    cpe,
+    references,
+});
+
+// policy_backend
+stable_hash_struct!(PoliciesStatusResponseBackend { policies });
+stable_hash_struct!(PoliciesStatusBackend {
+    name,
+    passed,
+    reason,
+    providers,
+    passed_rules,
+});
+stable_hash_struct_enum!(ReasonBackend {
+    MinScoreNotRespectedBackend { required, got },
+    SecurityChecksNotPassedBackend {
+        required,
+        passed,
+        failed,
+    },
+    TagsNotRespectedBackend {
+        required,
+        got,
+        failed_security_checks,
+        passed_security_checks,
+    },
+});
+stable_hash_struct_enum!(PassedRuleBackend {
+    MinScoreRespectedBackend { required, got },
+    SecurityChecksPassedBackend { passed },
+    TagsRespectedBackend {
+        required,
+        got,
+        passed_security_checks,
+    },
+});
+
+// pwned_backend
+stable_hash_unit_enum!(PwnedCriticalityBackend {
+    Unknown,
+    Low,
+    Medium,
+    High,
+});
+stable_hash_struct!(BreachDetailBackend {
+    name,
+    title,
+    domain,
+    breachdate,
+    count,
+    description,
+    short_data_classes,
+    data_classes,
+    is_verified,
+    is_sensitive,
+    logo_path,
+    is_stealer_log,
+    criticality,
+});
+stable_hash_struct!(BreachInfoBackend {
+    name,
+    description,
+    is_service,
+});
+
+// score_backend
+stable_hash_struct!(ScoreBackend {
+    network,
+    system_integrity,
+    system_services,
+    applications,
+    credentials,
+    overall,
+    stars,
+    compliance,
+    metrics,
+    history,
+});
+stable_hash_struct!(NumericalScoreBackend {
+    device_id,
+    os_name,
+    os_version,
+    ip,
+    ip6,
+    mac,
+    peer_ids,
+    score,
+    connected_user,
+    connected_domain,
+});
+stable_hash_struct!(DetailedScoreBackend {
+    device_id,
+    os_name,
+    os_version,
+    ip,
+    ip6,
+    mac,
+    hostname,
+    peer_ids,
+    core_version,
+    is_cicd,
+    city,
+    region,
+    country,
+    timezone,
+    latitude,
+    longitude,
+    helper_state,
+    score,
+    timestamp,
+    connected_user,
+    connected_domain,
+    details,
+});
+
+// session_info_backend
+stable_hash_struct!(SessionInfoBackend {
+    ip,
+    port,
+    protocol,
+    domain,
+    asn_number,
+    asn_country,
+    asn_owner,
+    criticality,
+    service,
+    l7_process_name,
+    l7_process_path,
+    l7_process_user,
+});
+
+// threat_backend
+stable_hash_struct!(ThreatMetricEducationJSONBackend {
+    locale,
+    class,
+    target,
+});
+stable_hash_struct!(ThreatMetricImplementationJSONBackend {
+    system,
+    minversion,
+    maxversion,
+    class,
+    elevation,
+    target,
+    education,
+});
+stable_hash_struct!(ThreatMetricDescriptionJSONBackend {
+    locale,
+    title,
+    summary,
+});
+stable_hash_struct!(ThreatMetricJSONBackend {
+    name,
+    metrictype,
+    dimension,
+    severity,
+    scope,
+    tags,
+    description,
+    implementation,
+    remediation,
+    rollback,
+});
+stable_hash_struct!(ThreatMetricsJSONBackend {
+    name,
+    extends,
+    date,
+    signature,
+    metrics,
+});
+stable_hash_unit_enum!(ThreatStatusBackend {
+    Active,
+    Inactive,
+    Unknown,
+});
+stable_hash_struct!(ThreatMetricBackend {
+    metric,
+    timestamp,
+    status,
+});
+stable_hash_struct!(ThreatMetricsBackend {
+    metrics,
+    name,
+    extends,
+    date,
+    signature,
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures as fixtures;
+
+    fn session() -> SessionInfoBackend {
+        SessionInfoBackend {
+            asn_owner: None,
+            criticality: "High".to_string(),
+            l7_process_path: None,
+            ..fixtures::session("10.0.0.1")
+        }
+    }
+
+    fn device() -> DeviceInfoBackend {
+        let mut vulnerability = VulnerabilityInfoBackend::new("CVE-2018-15473", "User enum");
+        vulnerability.cvss_score = Some(5.3);
+        DeviceInfoBackend {
+            mdns_services: vec!["_ssh._tcp.local".to_string()],
+            device_vendor: "Raspberry Pi Trading".to_string(),
+            vulnerabilities: vec![vulnerability.clone()],
+            open_ports: vec![PortInfoBackend {
+                port: 22,
+                protocol: "tcp".to_string(),
+                service: "ssh".to_string(),
+                banner: "SSH-2.0-OpenSSH_7.4".to_string(),
+                vulnerabilities: vec![vulnerability],
+            }],
+        }
+    }
+
+    fn todos() -> AdvisorTodosBackend {
+        AdvisorTodosBackend {
+            system_overview: "ok".to_string(),
+            todos: vec![AdvisorTodoBackend {
+                advice: AdvisorAdviceBackend {
+                    advice_type: AdviceTypeBackend::NetworkPort,
+                    advice_details: "22".to_string(),
+                },
+                priority: AdvicePriorityBackend::High,
+            }],
+            email: None,
+            question: Some(String::new()),
+        }
+    }
+
+    fn breach() -> BreachDetailBackend {
+        BreachDetailBackend {
+            domain: "adobe.com".to_string(),
+            breachdate: "2013-10-04".to_string(),
+            count: 152445165,
+            description: "In October 2013…".to_string(),
+            data_classes: vec!["Email addresses".to_string(), "Passwords".to_string()],
+            criticality: PwnedCriticalityBackend::High,
+            ..fixtures::breach("Adobe")
+        }
+    }
+
+    fn policy() -> PoliciesStatusBackend {
+        PoliciesStatusBackend {
+            name: "baseline".to_string(),
+            passed: false,
+            reason: vec![ReasonBackend::TagsNotRespectedBackend {
+                required: "SOC-2".to_string(),
+                got: 87.5,
+                failed_security_checks: vec!["firewall".to_string()],
+                passed_security_checks: vec![],
+            }],
+            providers: vec!["edamame".to_string()],
+            passed_rules: vec![PassedRuleBackend::MinScoreRespectedBackend {
+                required: 3,
+                got: 4,
+            }],
+        }
+    }
+
+    /// If one of these fails, the encoding changed: bump
+    /// `STABLE_HASH_VERSION` and migrate the uids built on it.
+    #[test]
+    fn golden_digests() {
+        assert_eq!(
+            session().stable_hash(),
+            "3756f8120deef22ccd9ec3f83a952a515703c2c08c8b3a2a642fdb7bb95f039c"
+        );
+        assert_eq!(
+            device().stable_hash(),
+            "a7207660500199c7c98fe477542779003e4158bc235bb586e1e84eec8eeb91c4"
+        );
+        assert_eq!(
+            todos().stable_hash(),
+            "510b61335dbe7a44ac545212b75d7a84ca580cc1739fb7ed9143eee59fb86354"
+        );
+        assert_eq!(
+            breach().stable_hash(),
+            "28d7fc6d4cdb695622ff8ead0267db5ff4ee0119cc926f76aeab41f6bc5d7c15"
+        );
+        assert_eq!(
+            policy().stable_hash(),
+            "cd42fd99950290f266df739a0a08259a21ed8fbe9df34537ecc5b9bf229c09e9"
+        );
+        assert_eq!(
+            "".stable_hash(),
+            "73c1176bf2b39bac950a15bfea649ab7e136464e0b57f4e45aec7f4d6c5c7147"
+        );
+    }
+
+    #[test]
+    fn encoding_is_injective_on_boundaries_and_options() {
+        assert_ne!(("ab", "c").stable_hash(), ("a", "bc").stable_hash());
+        assert_ne!(
+            (None::<String>, Some("a".to_string())).stable_hash(),
+            (Some("a".to_string()), None::<String>).stable_hash()
+        );
+        assert_ne!(
+            None::<String>.stable_hash(),
+            Some(String::new()).stable_hash()
+        );
+        assert_ne!(
+            vec![vec![1u8], vec![]].stable_hash(),
+            vec![vec![], vec![1u8]].stable_hash()
+        );
+
+        let mut moved = session();
+        moved.asn_owner = moved.domain.take();
+        assert_ne!(moved.stable_hash(), session().stable_hash());
+    }
+
+    #[test]
+    fn floats_are_canonical() {
+        assert_eq!(0.0f64.stable_hash(), (-0.0f64).stable_hash());
+        assert_eq!(f64::NAN.stable_hash(), (-f64::NAN).stable_hash());
+        assert_ne!(1.0f64.stable_hash(), 1.0000001f64.stable_hash());
+    }
+
+    #[test]
+    fn digest_covers_fields_debug_omits() {
+        // `Debug` on a vulnerability omits unset fields; the stable encoding
+        // still tells unset from set, even to an empty value.
+        let unset = VulnerabilityInfoBackend::new("x", "y");
+        let mut empty = unset.clone();
+        empty.cve_id = Some(String::new());
+        assert_ne!(empty.stable_hash(), unset.stable_hash());
+        let mut scored = unset.clone();
+        scored.epss = Some(0.5);
+        assert_ne!(scored.stable_hash(), unset.stable_hash());
+    }
+}
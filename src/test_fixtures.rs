@@ -0,0 +1,142 @@
+//! Fixture factories shared by the unit tests.
+//!
+//! Each factory returns a fully populated value; a test overrides the fields
+//! it is about, e.g. `SessionInfoBackend { port: 22, ..session("10.0.0.1") }`.
+
+use crate::helper_state_backend::HelperStateBackend;
+use crate::history_backend::OrderHistoryBackend;
+use crate::pwned_backend::{BreachDetailBackend, PwnedCriticalityBackend};
+use crate::score_backend::{DetailedScoreBackend, ScoreBackend};
+use crate::session_info_backend::SessionInfoBackend;
+use crate::threat_backend::{
+    ThreatMetricBackend, ThreatMetricImplementationJSONBackend, ThreatMetricJSONBackend,
+    ThreatMetricsBackend, ThreatStatusBackend,
+};
+
+pub(crate) fn implementation() -> ThreatMetricImplementationJSONBackend {
+    ThreatMetricImplementationJSONBackend {
+        system: "macOS".to_string(),
+        minversion: 12,
+        maxversion: 0,
+        class: "cli".to_string(),
+        elevation: "user".to_string(),
+        target: String::new(),
+        education: Vec::new(),
+    }
+}
+
+pub(crate) fn metric_json(name: &str) -> ThreatMetricJSONBackend {
+    ThreatMetricJSONBackend {
+        name: name.to_string(),
+        metrictype: "bool".to_string(),
+        dimension: "network".to_string(),
+        severity: 3,
+        scope: "generic".to_string(),
+        tags: Vec::new(),
+        description: Vec::new(),
+        implementation: implementation(),
+        remediation: implementation(),
+        rollback: implementation(),
+    }
+}
+
+pub(crate) fn metric(name: &str, status: ThreatStatusBackend) -> ThreatMetricBackend {
+    ThreatMetricBackend {
+        metric: metric_json(name),
+        timestamp: String::new(),
+        status,
+    }
+}
+
+pub(crate) fn metrics(metrics: Vec<ThreatMetricBackend>) -> ThreatMetricsBackend {
+    ThreatMetricsBackend {
+        metrics,
+        name: "threatmodel-macOS".to_string(),
+        extends: "none".to_string(),
+        date: "2026-10-01".to_string(),
+        signature: String::new(),
+    }
+}
+
+pub(crate) fn score(metrics: Vec<ThreatMetricBackend>) -> ScoreBackend {
+    ScoreBackend {
+        network: 0,
+        system_integrity: 0,
+        system_services: 0,
+        applications: 0,
+        credentials: 0,
+        overall: 0,
+        stars: 0.0,
+        compliance: Vec::new(),
+        metrics: self::metrics(metrics),
+        history: OrderHistoryBackend {
+            history: Vec::new(),
+        },
+    }
+}
+
+pub(crate) fn detailed_score(
+    metrics: Vec<ThreatMetricBackend>,
+    timestamp: &str,
+) -> DetailedScoreBackend {
+    DetailedScoreBackend {
+        device_id: "device".to_string(),
+        os_name: "macOS".to_string(),
+        os_version: "15.0".to_string(),
+        ip: String::new(),
+        ip6: String::new(),
+        mac: String::new(),
+        hostname: "host".to_string(),
+        peer_ids: Vec::new(),
+        core_version: "0.9.0".to_string(),
+        is_cicd: false,
+        city: String::new(),
+        region: String::new(),
+        country: String::new(),
+        timezone: String::new(),
+        latitude: String::new(),
+        longitude: String::new(),
+        helper_state: HelperStateBackend::Enabled,
+        score: score(metrics),
+        timestamp: timestamp.to_string(),
+        connected_user: String::new(),
+        connected_domain: String::new(),
+        details: Vec::new(),
+    }
+}
+
+/// An unflagged HTTPS session from `curl` to `example.com`.
+pub(crate) fn session(ip: &str) -> SessionInfoBackend {
+    SessionInfoBackend {
+        ip: ip.to_string(),
+        port: 443,
+        protocol: "TCP".to_string(),
+        domain: Some("example.com".to_string()),
+        asn_number: Some(15169),
+        asn_country: Some("US".to_string()),
+        asn_owner: Some("GOOGLE".to_string()),
+        criticality: String::new(),
+        service: Some("https".to_string()),
+        l7_process_name: Some("curl".to_string()),
+        l7_process_path: Some("/usr/bin/curl".to_string()),
+        l7_process_user: Some("alice".to_string()),
+    }
+}
+
+pub(crate) fn breach(name: &str) -> BreachDetailBackend {
+    BreachDetailBackend {
+        name: name.to_string(),
+        title: name.to_string(),
+        domain: "example.com".to_string(),
+        breachdate: "2024-01-01".to_string(),
+        count: 1000,
+        description: String::new(),
+        short_data_classes: Vec::new(),
+        data_classes: vec!["Email addresses".to_string()],
+        is_verified: true,
+        is_sensitive: false,
+        logo_path: String::new(),
+        is_stealer_log: false,
+        criticality: PwnedCriticalityBackend::Unknown,
+    }
+}
@@ -80,3 +80,11 @@ pub struct ThreatMetricsBackend {
     pub date: String,
     pub signature: String,
 }
+
+impl ThreatMetricsBackend {
+    /// Name-indexed view for lookups and filters -- see
+    /// [`crate::threat_query_backend`].
+    pub fn index(&self) -> crate::threat_query_backend::ThreatMetricsIndexBackend<'_> {
+        crate::threat_query_backend::ThreatMetricsIndexBackend::new(self)
+    }
+}
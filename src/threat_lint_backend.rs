@@ -0,0 +1,492 @@
+//! Static checks over a JSON threat model before it ships.
+//!
+//! Catalogue authors otherwise only learn about a malformed model when clients
+//! misbehave on it. [`lint_threat_model`] walks a [`ThreatMetricsJSONBackend`]
+//! and returns one [`ThreatLintDiagnosticBackend`] per problem, each anchored
+//! to a JSON path (`metrics[3].remediation.target`) so CI can point at the
+//! offending field.
+//!
+//! Codes and levels are carried as plain strings so a tool reading diagnostics
+//! from a newer linter does not fail on a code it does not know.
+
+use crate::threat_backend::{ThreatMetricImplementationJSONBackend, ThreatMetricsJSONBackend};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Documented severity range for a threat metric, inclusive.
+pub const MIN_THREAT_SEVERITY: i32 = 1;
+pub const MAX_THREAT_SEVERITY: i32 = 5;
+
+/// Locale every metric must describe itself in.
+pub const DEFAULT_THREAT_LOCALE: &str = "EN";
+
+/// Frameworks the shipped threat models tag metrics with. A tag is either a
+/// bare framework (`Personal Posture`) or `framework,control`.
+pub const DEFAULT_TAG_FRAMEWORKS: &[&str] = &[
+    "Personal Posture",
+    "CIS Benchmark Level 1",
+    "CIS Benchmark Level 2",
+    "ISO 27001/2",
+    "SOC 2",
+];
+
+/// What a diagnostic is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ThreatLintCodeBackend {
+    /// Two metrics share a name. Clients key status, history and details by
+    /// name, so the second one silently shadows the first.
+    DuplicateMetricName,
+    /// Severity outside [`MIN_THREAT_SEVERITY`]..=[`MAX_THREAT_SEVERITY`].
+    SeverityOutOfRange,
+    /// No description, or a blank title or summary, for the default locale.
+    MissingDefaultDescription,
+    /// A remediation target with no rollback target: the fix cannot be undone.
+    RemediationWithoutRollback,
+    /// An implementation block names a class but no target to run.
+    ClassWithoutTarget,
+    /// `minversion` greater than a bounded `maxversion`.
+    InvertedVersionRange,
+    /// A tag that cannot be attributed to a known compliance framework.
+    OrphanedTag,
+}
+
+impl ThreatLintCodeBackend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::DuplicateMetricName => "duplicate_metric_name",
+            Self::SeverityOutOfRange => "severity_out_of_range",
+            Self::MissingDefaultDescription => "missing_default_description",
+            Self::RemediationWithoutRollback => "remediation_without_rollback",
+            Self::ClassWithoutTarget => "class_without_target",
+            Self::InvertedVersionRange => "inverted_version_range",
+            Self::OrphanedTag => "orphaned_tag",
+        }
+    }
+
+    /// Default level for the code. Errors break clients, warnings degrade them.
+    pub fn level(&self) -> ThreatLintLevelBackend {
+        match self {
+            Self::DuplicateMetricName
+            | Self::SeverityOutOfRange
+            | Self::ClassWithoutTarget
+            | Self::InvertedVersionRange => ThreatLintLevelBackend::Error,
+            Self::MissingDefaultDescription
+            | Self::RemediationWithoutRollback
+            | Self::OrphanedTag => ThreatLintLevelBackend::Warning,
+        }
+    }
+}
+
+/// How bad a diagnostic is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ThreatLintLevelBackend {
+    Warning,
+    Error,
+}
+
+impl ThreatLintLevelBackend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Warning => "warning",
+            Self::Error => "error",
+        }
+    }
+}
+
+/// One problem found in a threat model.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ThreatLintDiagnosticBackend {
+    /// See [`ThreatLintCodeBackend`].
+    pub code: String,
+    /// See [`ThreatLintLevelBackend`].
+    pub level: String,
+    /// JSON path of the offending field, e.g. `metrics[3].rollback.target`.
+    pub path: String,
+    /// Name of the metric the path points into.
+    pub metric: String,
+    /// Human-readable explanation.
+    pub message: String,
+}
+
+impl ThreatLintDiagnosticBackend {
+    pub fn new(
+        code: ThreatLintCodeBackend,
+        path: impl Into<String>,
+        metric: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            code: code.as_str().to_string(),
+            level: code.level().as_str().to_string(),
+            path: path.into(),
+            metric: metric.into(),
+            message: message.into(),
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.level == ThreatLintLevelBackend::Error.as_str()
+    }
+}
+
+/// Knobs for [`lint_threat_model`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThreatLintOptionsBackend {
+    /// Locale that must carry a title and summary. Compared case-insensitively.
+    pub default_locale: String,
+    /// Compliance frameworks a tag may belong to. A tag whose framework (the
+    /// whole tag, or the part before the first comma) is not listed here is
+    /// orphaned. Compared case-insensitively. Defaults to
+    /// [`DEFAULT_TAG_FRAMEWORKS`].
+    pub known_tag_frameworks: Vec<String>,
+}
+
+impl Default for ThreatLintOptionsBackend {
+    fn default() -> Self {
+        Self {
+            default_locale: DEFAULT_THREAT_LOCALE.to_string(),
+            known_tag_frameworks: DEFAULT_TAG_FRAMEWORKS
+                .iter()
+                .map(|framework| framework.to_string())
+                .collect(),
+        }
+    }
+}
+
+/// Run every check over `model`. Diagnostics come out in metric order, and in
+/// field order within a metric, so two runs over the same model diff cleanly.
+pub fn lint_threat_model(
+    model: &ThreatMetricsJSONBackend,
+    options: &ThreatLintOptionsBackend,
+) -> Vec<ThreatLintDiagnosticBackend> {
+    let mut diagnostics = Vec::new();
+    let mut first_index: HashMap<&str, usize> = HashMap::new();
+
+    for (index, metric) in model.metrics.iter().enumerate() {
+        let base = format!("metrics[{index}]");
+        let name = metric.name.as_str();
+
+        if let Some(first) = first_index.get(name) {
+            diagnostics.push(ThreatLintDiagnosticBackend::new(
+                ThreatLintCodeBackend::DuplicateMetricName,
+                format!("{base}.name"),
+                name,
+                format!("metric name already used by metrics[{first}]"),
+            ));
+        } else {
+            first_index.insert(name, index);
+        }
+
+        if !(MIN_THREAT_SEVERITY..=MAX_THREAT_SEVERITY).contains(&metric.severity) {
+            diagnostics.push(ThreatLintDiagnosticBackend::new(
+                ThreatLintCodeBackend::SeverityOutOfRange,
+                format!("{base}.severity"),
+                name,
+                format!(
+                    "severity {} outside {MIN_THREAT_SEVERITY}..={MAX_THREAT_SEVERITY}",
+                    metric.severity
+                ),
+            ));
+        }
+
+        match metric
+            .description
+            .iter()
+            .position(|d| d.locale.eq_ignore_ascii_case(&options.default_locale))
+        {
+            None => diagnostics.push(ThreatLintDiagnosticBackend::new(
+                ThreatLintCodeBackend::MissingDefaultDescription,
+                format!("{base}.description"),
+                name,
+                format!("no description for locale {}", options.default_locale),
+            )),
+            Some(position) => {
+                let description = &metric.description[position];
+                for (field, value) in [
+                    ("title", &description.title),
+                    ("summary", &description.summary),
+                ] {
+                    if value.trim().is_empty() {
+                        diagnostics.push(ThreatLintDiagnosticBackend::new(
+                            ThreatLintCodeBackend::MissingDefaultDescription,
+                            format!("{base}.description[{position}].{field}"),
+                            name,
+                            format!("empty {field} for locale {}", description.locale),
+                        ));
+                    }
+                }
+            }
+        }
+
+        for (field, block) in [
+            ("implementation", &metric.implementation),
+            ("remediation", &metric.remediation),
+            ("rollback", &metric.rollback),
+        ] {
+            lint_implementation(&mut diagnostics, &format!("{base}.{field}"), name, block);
+        }
+
+        if !metric.remediation.target.trim().is_empty() && metric.rollback.target.trim().is_empty()
+        {
+            diagnostics.push(ThreatLintDiagnosticBackend::new(
+                ThreatLintCodeBackend::RemediationWithoutRollback,
+                format!("{base}.rollback.target"),
+                name,
+                "remediation has a target but rollback does not",
+            ));
+        }
+
+        for (tag_index, tag) in metric.tags.iter().enumerate() {
+            if is_orphaned_tag(tag, &options.known_tag_frameworks) {
+                diagnostics.push(ThreatLintDiagnosticBackend::new(
+                    ThreatLintCodeBackend::OrphanedTag,
+                    format!("{base}.tags[{tag_index}]"),
+                    name,
+                    format!("tag {tag:?} does not belong to a known framework"),
+                ));
+            }
+        }
+    }
+
+    diagnostics
+}
+
+fn lint_implementation(
+    diagnostics: &mut Vec<ThreatLintDiagnosticBackend>,
+    path: &str,
+    metric: &str,
+    block: &ThreatMetricImplementationJSONBackend,
+) {
+    if !block.class.trim().is_empty() && block.target.trim().is_empty() {
+        diagnostics.push(ThreatLintDiagnosticBackend::new(
+            ThreatLintCodeBackend::ClassWithoutTarget,
+            format!("{path}.target"),
+            metric,
+            format!("class {:?} has no target", block.class),
+        ));
+    }
+    // A maxversion of 0 means "no upper bound" in the catalogue.
+    if block.maxversion != 0 && block.minversion > block.maxversion {
+        diagnostics.push(ThreatLintDiagnosticBackend::new(
+            ThreatLintCodeBackend::InvertedVersionRange,
+            format!("{path}.minversion"),
+            metric,
+            format!(
+                "minversion {} is above maxversion {}",
+                block.minversion, block.maxversion
+            ),
+        ));
+    }
+}
+
+fn is_orphaned_tag(tag: &str, known_frameworks: &[String]) -> bool {
+    let framework = match tag.split_once(',') {
+        Some((framework, _)) => framework.trim(),
+        None => tag.trim(),
+    };
+    !known_frameworks
+        .iter()
+        .any(|known| known.trim().eq_ignore_ascii_case(framework))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures as fixtures;
+    use crate::threat_backend::{ThreatMetricDescriptionJSONBackend, ThreatMetricJSONBackend};
+
+    fn implementation(class: &str, target: &str) -> ThreatMetricImplementationJSONBackend {
+        ThreatMetricImplementationJSONBackend {
+            class: class.to_string(),
+            target: target.to_string(),
+            ..fixtures::implementation()
+        }
+    }
+
+    fn metric(name: &str) -> ThreatMetricJSONBackend {
+        ThreatMetricJSONBackend {
+            tags: vec!["CIS Benchmark Level 1,Firewall".to_string()],
+            description: vec![ThreatMetricDescriptionJSONBackend {
+                locale: "EN".to_string(),
+                title: "Firewall disabled".to_string(),
+                summary: "The firewall is off.".to_string(),
+            }],
+            implementation: implementation("cli", "check_firewall"),
+            remediation: implementation("cli", "enable_firewall"),
+            rollback: implementation("cli", "disable_firewall"),
+            ..fixtures::metric_json(name)
+        }
+    }
+
+    fn model(metrics: Vec<ThreatMetricJSONBackend>) -> ThreatMetricsJSONBackend {
+        ThreatMetricsJSONBackend {
+            name: "threatmodel-macOS".to_string(),
+            extends: "none".to_string(),
+            date: "2026-10-01".to_string(),
+            signature: String::new(),
+            metrics,
+        }
+    }
+
+    fn codes(diagnostics: &[ThreatLintDiagnosticBackend]) -> Vec<&str> {
+        diagnostics.iter().map(|d| d.code.as_str()).collect()
+    }
+
+    #[test]
+    fn clean_model_has_no_diagnostics() {
+        let diagnostics = lint_threat_model(
+            &model(vec![metric("firewall disabled")]),
+            &ThreatLintOptionsBackend::default(),
+        );
+        assert!(diagnostics.is_empty(), "{diagnostics:?}");
+    }
+
+    #[test]
+    fn duplicate_name_points_at_second_occurrence() {
+        let diagnostics = lint_threat_model(
+            &model(vec![metric("a"), metric("b"), metric("a")]),
+            &ThreatLintOptionsBackend::default(),
+        );
+        assert_eq!(codes(&diagnostics), vec!["duplicate_metric_name"]);
+        assert_eq!(diagnostics[0].path, "metrics[2].name");
+        assert!(diagnostics[0].message.contains("metrics[0]"));
+        assert!(diagnostics[0].is_error());
+    }
+
+    #[test]
+    fn severity_bounds_are_inclusive() {
+        let mut low = metric("low");
+        low.severity = MIN_THREAT_SEVERITY;
+        let mut high = metric("high");
+        high.severity = MAX_THREAT_SEVERITY;
+        let mut zero = metric("zero");
+        zero.severity = 0;
+        let mut six = metric("six");
+        six.severity = 6;
+        let diagnostics = lint_threat_model(
+            &model(vec![low, high, zero, six]),
+            &ThreatLintOptionsBackend::default(),
+        );
+        let paths: Vec<&str> = diagnostics.iter().map(|d| d.path.as_str()).collect();
+        assert_eq!(paths, vec!["metrics[2].severity", "metrics[3].severity"]);
+    }
+
+    #[test]
+    fn default_locale_description_must_exist_and_be_filled() {
+        let mut missing = metric("missing");
+        missing.description[0].locale = "FR".to_string();
+        let mut blank = metric("blank");
+        blank.description[0].summary = "  ".to_string();
+        let mut lower = metric("lower");
+        lower.description[0].locale = "en".to_string();
+        let diagnostics = lint_threat_model(
+            &model(vec![missing, blank, lower]),
+            &ThreatLintOptionsBackend::default(),
+        );
+        let paths: Vec<&str> = diagnostics.iter().map(|d| d.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "metrics[0].description",
+                "metrics[1].description[0].summary"
+            ]
+        );
+    }
+
+    #[test]
+    fn remediation_needs_rollback() {
+        let mut m = metric("one way");
+        m.rollback = implementation("", "");
+        let diagnostics = lint_threat_model(&model(vec![m]), &ThreatLintOptionsBackend::default());
+        assert_eq!(codes(&diagnostics), vec!["remediation_without_rollback"]);
+        assert_eq!(diagnostics[0].path, "metrics[0].rollback.target");
+        assert!(!diagnostics[0].is_error());
+    }
+
+    #[test]
+    fn class_without_target_is_reported_per_block() {
+        let mut m = metric("no target");
+        m.implementation.target = String::new();
+        m.rollback.target = String::new();
+        let diagnostics = lint_threat_model(&model(vec![m]), &ThreatLintOptionsBackend::default());
+        let paths: Vec<&str> = diagnostics.iter().map(|d| d.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "metrics[0].implementation.target",
+                "metrics[0].rollback.target",
+                "metrics[0].rollback.target",
+            ]
+        );
+        assert_eq!(
+            codes(&diagnostics),
+            vec![
+                "class_without_target",
+                "class_without_target",
+                "remediation_without_rollback",
+            ]
+        );
+    }
+
+    #[test]
+    fn inverted_versions_ignore_unbounded_max() {
+        let mut inverted = metric("inverted");
+        inverted.remediation.minversion = 14;
+        inverted.remediation.maxversion = 12;
+        let mut unbounded = metric("unbounded");
+        unbounded.remediation.minversion = 14;
+        unbounded.remediation.maxversion = 0;
+        let diagnostics = lint_threat_model(
+            &model(vec![inverted, unbounded]),
+            &ThreatLintOptionsBackend::default(),
+        );
+        assert_eq!(codes(&diagnostics), vec!["inverted_version_range"]);
+        assert_eq!(diagnostics[0].path, "metrics[0].remediation.minversion");
+    }
+
+    #[test]
+    fn orphaned_tags_against_known_frameworks() {
+        let mut m = metric("tags");
+        m.tags = vec![
+            "CIS Benchmark Level 1,Firewall".to_string(),
+            "Personal Posture".to_string(),
+            "NIST,AC-1".to_string(),
+            ",Firewall".to_string(),
+            "iso 27001/2,Access Control".to_string(),
+        ];
+
+        let shipped = lint_threat_model(
+            &model(vec![m.clone()]),
+            &ThreatLintOptionsBackend::default(),
+        );
+        let paths: Vec<&str> = shipped.iter().map(|d| d.path.as_str()).collect();
+        assert_eq!(paths, vec!["metrics[0].tags[2]", "metrics[0].tags[3]"]);
+
+        let options = ThreatLintOptionsBackend {
+            known_tag_frameworks: vec!["CIS Benchmark Level 1".to_string(), "NIST".to_string()],
+            ..Default::default()
+        };
+        let configured = lint_threat_model(&model(vec![m]), &options);
+        let paths: Vec<&str> = configured.iter().map(|d| d.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "metrics[0].tags[1]",
+                "metrics[0].tags[3]",
+                "metrics[0].tags[4]"
+            ]
+        );
+    }
+
+    #[test]
+    fn diagnostics_are_flat_strings_on_the_wire() {
+        let mut m = metric("wire");
+        m.severity = 9;
+        let diagnostics = lint_threat_model(&model(vec![m]), &ThreatLintOptionsBackend::default());
+        let json = serde_json::to_value(&diagnostics).expect("serialize");
+        assert_eq!(json[0]["code"], "severity_out_of_range");
+        assert_eq!(json[0]["level"], "error");
+        assert_eq!(json[0]["path"], "metrics[0].severity");
+        assert_eq!(json[0]["metric"], "wire");
+    }
+}
@@ -0,0 +1,406 @@
+//! Indexed lookups and filters over a [`ThreatMetricsBackend`].
+//!
+//! `metrics` is a plain list on the wire, and consumers kept re-implementing
+//! the same linear scans ("active metrics with severity ≥ 4 in dimension
+//! network"). [`ThreatMetricsIndexBackend`] borrows the list once, indexes it by name,
+//! and answers [`ThreatMetricQueryBackend`] filters and per-status counts.
+//!
+//! [`ThreatMetricsSummaryBackend`] is the serializable digest of the same
+//! data. Every list in it is sorted, so two summaries of the same metrics
+//! compare and serialize identically regardless of the order the client sent.
+
+use crate::threat_backend::{ThreatMetricBackend, ThreatMetricsBackend, ThreatStatusBackend};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Filter over threat metrics. Every criterion left unset matches everything;
+/// set criteria are ANDed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ThreatMetricQueryBackend {
+    pub status: Option<ThreatStatusBackend>,
+    pub dimension: Option<String>,
+    pub tag: Option<String>,
+    pub scope: Option<String>,
+    pub min_severity: Option<i32>,
+    pub max_severity: Option<i32>,
+}
+
+impl ThreatMetricQueryBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_status(mut self, status: ThreatStatusBackend) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    /// Case-insensitive, like the dimension names in the catalogue.
+    pub fn with_dimension(mut self, dimension: impl Into<String>) -> Self {
+        self.dimension = Some(dimension.into());
+        self
+    }
+
+    /// Whole tag (`CIS Benchmark Level 1,Firewall`), or a framework
+    /// (`CIS Benchmark Level 1`) matching every tag under it. Case-insensitive,
+    /// like the framework check in [`crate::threat_lint_backend`].
+    pub fn with_tag(mut self, tag: impl Into<String>) -> Self {
+        self.tag = Some(tag.into());
+        self
+    }
+
+    pub fn with_scope(mut self, scope: impl Into<String>) -> Self {
+        self.scope = Some(scope.into());
+        self
+    }
+
+    /// Inclusive lower bound.
+    pub fn with_min_severity(mut self, severity: i32) -> Self {
+        self.min_severity = Some(severity);
+        self
+    }
+
+    /// Inclusive upper bound.
+    pub fn with_max_severity(mut self, severity: i32) -> Self {
+        self.max_severity = Some(severity);
+        self
+    }
+
+    pub fn matches(&self, metric: &ThreatMetricBackend) -> bool {
+        let json = &metric.metric;
+        self.status.as_ref().is_none_or(|s| *s == metric.status)
+            && self
+                .dimension
+                .as_ref()
+                .is_none_or(|d| d.eq_ignore_ascii_case(&json.dimension))
+            && self
+                .scope
+                .as_ref()
+                .is_none_or(|s| s.eq_ignore_ascii_case(&json.scope))
+            && self.min_severity.is_none_or(|min| json.severity >= min)
+            && self.max_severity.is_none_or(|max| json.severity <= max)
+            && self.tag.as_ref().is_none_or(|wanted| {
+                let wanted = wanted.trim();
+                json.tags.iter().any(|tag| {
+                    tag.trim().eq_ignore_ascii_case(wanted)
+                        || tag.split_once(',').is_some_and(|(framework, _)| {
+                            framework.trim().eq_ignore_ascii_case(wanted)
+                        })
+                })
+            })
+    }
+}
+
+/// Count of metrics per status.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ThreatStatusCountsBackend {
+    pub active: u32,
+    pub inactive: u32,
+    pub unknown: u32,
+}
+
+impl ThreatStatusCountsBackend {
+    pub fn add(&mut self, status: &ThreatStatusBackend) {
+        match status {
+            ThreatStatusBackend::Active => self.active += 1,
+            ThreatStatusBackend::Inactive => self.inactive += 1,
+            ThreatStatusBackend::Unknown => self.unknown += 1,
+        }
+    }
+
+    pub fn total(&self) -> u32 {
+        self.active + self.inactive + self.unknown
+    }
+}
+
+/// Borrowed, name-indexed view of a [`ThreatMetricsBackend`].
+#[derive(Debug, Clone)]
+pub struct ThreatMetricsIndexBackend<'a> {
+    metrics: &'a ThreatMetricsBackend,
+    by_name: HashMap<&'a str, usize>,
+}
+
+impl<'a> ThreatMetricsIndexBackend<'a> {
+    /// Index `metrics` by name. A duplicated name resolves to its first
+    /// occurrence, matching what a linear scan returned.
+    pub fn new(metrics: &'a ThreatMetricsBackend) -> Self {
+        let mut by_name = HashMap::with_capacity(metrics.metrics.len());
+        for (index, metric) in metrics.metrics.iter().enumerate() {
+            by_name.entry(metric.metric.name.as_str()).or_insert(index);
+        }
+        Self { metrics, by_name }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&'a ThreatMetricBackend> {
+        self.by_name
+            .get(name)
+            .map(|&index| &self.metrics.metrics[index])
+    }
+
+    pub fn status(&self, name: &str) -> Option<&'a ThreatStatusBackend> {
+        self.get(name).map(|metric| &metric.status)
+    }
+
+    /// Matching metrics, in the order the client sent them.
+    pub fn query(&self, query: &ThreatMetricQueryBackend) -> Vec<&'a ThreatMetricBackend> {
+        self.metrics
+            .metrics
+            .iter()
+            .filter(|metric| query.matches(metric))
+            .collect()
+    }
+
+    pub fn count(&self, query: &ThreatMetricQueryBackend) -> usize {
+        self.metrics
+            .metrics
+            .iter()
+            .filter(|metric| query.matches(metric))
+            .count()
+    }
+
+    pub fn counts_by_status(&self) -> ThreatStatusCountsBackend {
+        let mut counts = ThreatStatusCountsBackend::default();
+        for metric in &self.metrics.metrics {
+            counts.add(&metric.status);
+        }
+        counts
+    }
+
+    pub fn summary(&self) -> ThreatMetricsSummaryBackend {
+        ThreatMetricsSummaryBackend::new(self.metrics)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ThreatDimensionSummaryBackend {
+    pub dimension: String,
+    pub counts: ThreatStatusCountsBackend,
+    /// Highest severity among Active metrics in the dimension; 0 when none.
+    pub max_active_severity: i32,
+}
+
+/// Serializable digest of a [`ThreatMetricsBackend`]. Order-independent: built
+/// from the same metrics in any order, it compares and serializes the same.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ThreatMetricsSummaryBackend {
+    // Copied from the threat model
+    pub name: String,
+    pub date: String,
+    pub counts: ThreatStatusCountsBackend,
+    /// Sorted by dimension. A Vec rather than a map to keep it simple with the
+    /// backend. Dimensions differing only in case are merged and sorted
+    /// case-insensitively.
+    pub dimensions: Vec<ThreatDimensionSummaryBackend>,
+    /// Names of Active metrics, sorted.
+    pub active: Vec<String>,
+}
+
+impl ThreatMetricsSummaryBackend {
+    pub fn new(metrics: &ThreatMetricsBackend) -> Self {
+        let mut counts = ThreatStatusCountsBackend::default();
+        // Keyed case-insensitively, like ThreatMetricQueryBackend::with_dimension.
+        let mut dimensions: BTreeMap<String, ThreatDimensionSummaryBackend> = BTreeMap::new();
+        let mut active = Vec::new();
+        for metric in &metrics.metrics {
+            counts.add(&metric.status);
+            let label = &metric.metric.dimension;
+            let dimension = dimensions.entry(label.to_lowercase()).or_insert_with(|| {
+                ThreatDimensionSummaryBackend {
+                    dimension: label.clone(),
+                    ..Default::default()
+                }
+            });
+            // Smallest spelling wins so the label does not depend on input order.
+            if *label < dimension.dimension {
+                dimension.dimension = label.clone();
+            }
+            dimension.counts.add(&metric.status);
+            if metric.status == ThreatStatusBackend::Active {
+                dimension.max_active_severity =
+                    dimension.max_active_severity.max(metric.metric.severity);
+                active.push(metric.metric.name.clone());
+            }
+        }
+        active.sort();
+        Self {
+            name: metrics.name.clone(),
+            date: metrics.date.clone(),
+            counts,
+            dimensions: dimensions.into_values().collect(),
+            active,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures as fixtures;
+
+    fn metric(
+        name: &str,
+        dimension: &str,
+        severity: i32,
+        tags: &[&str],
+        status: ThreatStatusBackend,
+    ) -> ThreatMetricBackend {
+        let mut metric = fixtures::metric(name, status);
+        metric.metric.dimension = dimension.to_string();
+        metric.metric.severity = severity;
+        metric.metric.tags = tags.iter().map(|t| t.to_string()).collect();
+        metric
+    }
+
+    fn sample() -> ThreatMetricsBackend {
+        fixtures::metrics(vec![
+            metric(
+                "firewall disabled",
+                "network",
+                4,
+                &["CIS Benchmark Level 1,Firewall"],
+                ThreatStatusBackend::Active,
+            ),
+            metric(
+                "remote login enabled",
+                "network",
+                3,
+                &["ISO 27001/2,Access Control"],
+                ThreatStatusBackend::Active,
+            ),
+            metric(
+                "encrypted disk disabled",
+                "system integrity",
+                5,
+                &["CIS Benchmark Level 1,Encryption"],
+                ThreatStatusBackend::Inactive,
+            ),
+            metric(
+                "no EPP",
+                "applications",
+                4,
+                &[],
+                ThreatStatusBackend::Unknown,
+            ),
+        ])
+    }
+
+    fn names(metrics: Vec<&ThreatMetricBackend>) -> Vec<&str> {
+        metrics.iter().map(|m| m.metric.name.as_str()).collect()
+    }
+
+    #[test]
+    fn lookup_by_name() {
+        let metrics = sample();
+        let index = ThreatMetricsIndexBackend::new(&metrics);
+        assert_eq!(
+            index.status("firewall disabled"),
+            Some(&ThreatStatusBackend::Active)
+        );
+        assert_eq!(index.get("no EPP").unwrap().metric.severity, 4);
+        assert!(index.get("missing").is_none());
+    }
+
+    #[test]
+    fn query_combines_criteria() {
+        let metrics = sample();
+        let index = metrics.index();
+        let query = ThreatMetricQueryBackend::new()
+            .with_status(ThreatStatusBackend::Active)
+            .with_min_severity(4)
+            .with_dimension("Network");
+        assert_eq!(names(index.query(&query)), vec!["firewall disabled"]);
+
+        let cis = ThreatMetricQueryBackend::new().with_tag("CIS Benchmark Level 1");
+        assert_eq!(
+            names(index.query(&cis)),
+            vec!["firewall disabled", "encrypted disk disabled"]
+        );
+        let exact = ThreatMetricQueryBackend::new().with_tag("ISO 27001/2,Access Control");
+        assert_eq!(index.count(&exact), 1);
+        let folded = ThreatMetricQueryBackend::new().with_tag("iso 27001/2,access control");
+        assert_eq!(index.count(&folded), 1);
+        let framework = ThreatMetricQueryBackend::new().with_tag(" cis benchmark level 1 ");
+        assert_eq!(index.count(&framework), 2);
+        let control = ThreatMetricQueryBackend::new().with_tag("Firewall");
+        assert_eq!(index.count(&control), 0);
+        let bounded = ThreatMetricQueryBackend::new()
+            .with_max_severity(3)
+            .with_scope("generic");
+        assert_eq!(names(index.query(&bounded)), vec!["remote login enabled"]);
+        assert_eq!(index.count(&ThreatMetricQueryBackend::new()), 4);
+    }
+
+    #[test]
+    fn counts_by_status() {
+        let metrics = sample();
+        let counts = ThreatMetricsIndexBackend::new(&metrics).counts_by_status();
+        assert_eq!(counts.active, 2);
+        assert_eq!(counts.inactive, 1);
+        assert_eq!(counts.unknown, 1);
+        assert_eq!(counts.total(), 4);
+    }
+
+    #[test]
+    fn summary_is_order_independent_and_round_trips() {
+        let metrics = sample();
+        let mut reversed = sample();
+        reversed.metrics.reverse();
+        let summary = metrics.index().summary();
+        assert_eq!(summary, reversed.index().summary());
+        assert_eq!(
+            serde_json::to_string(&summary).unwrap(),
+            serde_json::to_string(&reversed.index().summary()).unwrap()
+        );
+
+        assert_eq!(
+            summary.active,
+            vec!["firewall disabled", "remote login enabled"]
+        );
+        let dimensions: Vec<&str> = summary
+            .dimensions
+            .iter()
+            .map(|d| d.dimension.as_str())
+            .collect();
+        assert_eq!(
+            dimensions,
+            vec!["applications", "network", "system integrity"]
+        );
+        assert_eq!(summary.dimensions[1].max_active_severity, 4);
+        assert_eq!(summary.dimensions[2].max_active_severity, 0);
+
+        let json = serde_json::to_string(&summary).unwrap();
+        let parsed: ThreatMetricsSummaryBackend = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, summary);
+    }
+
+    #[test]
+    fn summary_groups_dimensions_like_the_query() {
+        let mut metrics = sample();
+        metrics.metrics.push(metric(
+            "upnp enabled",
+            "Network",
+            5,
+            &[],
+            ThreatStatusBackend::Active,
+        ));
+        let index = metrics.index();
+        let queried = index.query(&ThreatMetricQueryBackend::new().with_dimension("network"));
+        let summary = index.summary();
+        let mut reversed = metrics.clone();
+        reversed.metrics.reverse();
+        assert_eq!(summary, reversed.index().summary());
+
+        let dimensions: Vec<&str> = summary
+            .dimensions
+            .iter()
+            .map(|d| d.dimension.as_str())
+            .collect();
+        assert_eq!(
+            dimensions,
+            vec!["applications", "Network", "system integrity"]
+        );
+        let network = &summary.dimensions[1];
+        assert_eq!(network.counts.total() as usize, queried.len());
+        assert_eq!(network.max_active_severity, 5);
+    }
+}
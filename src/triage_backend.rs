@@ -0,0 +1,604 @@
+//! Routing labels for dislike and feedback reports.
+//!
+//! Support used to read `note` and `context` by hand to decide who should
+//! look at a report. [`triage_feedback`] and [`triage_dislike`] derive a
+//! [`TriageRecordBackend`] instead:
+//!
+//! - subsystem: keyword votes over the free text (see [`SUBSYSTEM_KEYWORDS`]),
+//!   strongest first; a dislike report is about LAN scan by definition, and
+//!   an unhealthy helper counts as one more vote for the helper;
+//! - platform, from `os_name`;
+//! - helper health, from `helper_state`;
+//! - threat model age in days, from `threat_model_date`, stale after
+//!   [`STALE_THREAT_MODEL_DAYS`];
+//! - the backend version found in `core_info` (a `backend X.Y.Z` token),
+//!   compared with [`BACKEND_VERSION`]; unknown when there is none.
+//!
+//! Every verdict is also flattened into `labels` (`subsystem:lan_scan`,
+//! `helper:broken`, …) for ticket systems that only take tags.
+
+use crate::feedback_info_backend::FeedbackInfoBackend;
+use crate::helper_state_backend::HelperStateBackend;
+use crate::lanscan_dislike_device_info_backend::DislikeDeviceInfoBackend;
+use crate::version::BACKEND_VERSION;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+/// A threat model older than this is reported as stale.
+pub const STALE_THREAT_MODEL_DAYS: i64 = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TriageSubsystemBackend {
+    LanScan,
+    Helper,
+    Score,
+    Agentic,
+    Other,
+}
+
+impl TriageSubsystemBackend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::LanScan => "lan_scan",
+            Self::Helper => "helper",
+            Self::Score => "score",
+            Self::Agentic => "agentic",
+            Self::Other => "other",
+        }
+    }
+}
+
+/// Words and phrases voting for each subsystem, matched as whole lowercase
+/// words of the free text (`"full disk access"` matches those three words in
+/// a row). Ambiguous everyday words ("check", "service", "disk") only count
+/// inside a phrase that pins their meaning.
+pub const SUBSYSTEM_KEYWORDS: &[(TriageSubsystemBackend, &[&str])] = &[
+    (
+        TriageSubsystemBackend::LanScan,
+        &[
+            "lan",
+            "lan scan",
+            "network scan",
+            "scan",
+            "scans",
+            "scanning",
+            "scanner",
+            "devices",
+            "device type",
+            "open port",
+            "open ports",
+            "ports",
+            "mdns",
+            "wifi",
+            "wi fi",
+            "router",
+            "printer",
+            "camera",
+        ],
+    ),
+    (
+        TriageSubsystemBackend::Helper,
+        &[
+            "helper",
+            "privileged helper",
+            "daemon",
+            "background service",
+            "full disk access",
+            "permission",
+            "permissions",
+            "privileges",
+            "sudo",
+        ],
+    ),
+    (
+        TriageSubsystemBackend::Score,
+        &[
+            "score",
+            "scores",
+            "scoring",
+            "threat",
+            "threats",
+            "remediate",
+            "remediation",
+            "rollback",
+            "roll back",
+            "policy",
+            "policies",
+            "compliance",
+            "security check",
+            "security checks",
+        ],
+    ),
+    (
+        TriageSubsystemBackend::Agentic,
+        &["agentic", "ai agent", "ai", "mcp", "llm", "assistant"],
+    ),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TriagePlatformBackend {
+    MacOs,
+    Windows,
+    Linux,
+    Ios,
+    Android,
+    Unknown,
+}
+
+impl TriagePlatformBackend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::MacOs => "macos",
+            Self::Windows => "windows",
+            Self::Linux => "linux",
+            Self::Ios => "ios",
+            Self::Android => "android",
+            Self::Unknown => "unknown",
+        }
+    }
+
+    pub fn from_os_name(os_name: &str) -> Self {
+        let os_name = os_name.trim().to_ascii_lowercase();
+        if os_name.contains("ios") || os_name.contains("ipados") {
+            Self::Ios
+        } else if os_name.contains("mac") || os_name.contains("darwin") {
+            Self::MacOs
+        } else if os_name.contains("windows") {
+            Self::Windows
+        } else if os_name.contains("android") {
+            Self::Android
+        } else if os_name.contains("linux")
+            || ["ubuntu", "debian", "fedora", "arch"]
+                .iter()
+                .any(|distro| os_name.contains(distro))
+        {
+            Self::Linux
+        } else {
+            Self::Unknown
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HelperHealthBackend {
+    Healthy,
+    /// Installed but off or out of date: fixable by the user.
+    Degraded,
+    Broken,
+    Unsupported,
+    Unknown,
+}
+
+impl HelperHealthBackend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Healthy => "healthy",
+            Self::Degraded => "degraded",
+            Self::Broken => "broken",
+            Self::Unsupported => "unsupported",
+            Self::Unknown => "unknown",
+        }
+    }
+
+    pub fn from_helper_state(helper_state: &str) -> Self {
+        match HelperStateBackend::from_str_opt(helper_state) {
+            Some(HelperStateBackend::Enabled | HelperStateBackend::EnabledFullDisk) => {
+                Self::Healthy
+            }
+            Some(HelperStateBackend::Disabled | HelperStateBackend::Outdated) => Self::Degraded,
+            Some(HelperStateBackend::Fatal) => Self::Broken,
+            Some(HelperStateBackend::Unsupported) => Self::Unsupported,
+            None => Self::Unknown,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TriageVersionStatusBackend {
+    Current,
+    Outdated,
+    /// Newer than this build: a pre-release or a stale backend.
+    Newer,
+    Unknown,
+}
+
+impl TriageVersionStatusBackend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Current => "current",
+            Self::Outdated => "outdated",
+            Self::Newer => "newer",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TriageRecordBackend {
+    /// `feedback` or `dislike`.
+    pub source: String,
+    /// See [`TriageSubsystemBackend`]. Strongest first; the first is where
+    /// the report is routed. Never empty.
+    pub subsystems: Vec<String>,
+    /// See [`TriagePlatformBackend`].
+    pub platform: String,
+    pub os_version: String,
+    /// See [`HelperHealthBackend`].
+    pub helper_health: String,
+    pub threat_model_age_days: Option<i64>,
+    pub threat_model_stale: bool,
+    pub reported_version: Option<String>,
+    /// See [`TriageVersionStatusBackend`].
+    pub version_status: String,
+    /// `key:value` tags summarizing the fields above, sorted.
+    pub labels: Vec<String>,
+}
+
+impl TriageRecordBackend {
+    /// Where the report goes.
+    pub fn subsystem(&self) -> &str {
+        self.subsystems
+            .first()
+            .map(String::as_str)
+            .unwrap_or(TriageSubsystemBackend::Other.as_str())
+    }
+}
+
+pub fn triage_feedback(feedback: &FeedbackInfoBackend, now: DateTime<Utc>) -> TriageRecordBackend {
+    let helper_health = HelperHealthBackend::from_helper_state(&feedback.helper_state);
+    let mut votes = keyword_votes(&[&feedback.note, &feedback.context]);
+    if matches!(
+        helper_health,
+        HelperHealthBackend::Degraded | HelperHealthBackend::Broken
+    ) {
+        add_vote(&mut votes, TriageSubsystemBackend::Helper, 1);
+    }
+    let threat_model_age_days = threat_model_age_days(&feedback.threat_model_date, now);
+    let reported_version = reported_version(&feedback.core_info);
+    let version_status = match &reported_version {
+        Some(version) => compare_versions(version, BACKEND_VERSION),
+        None => TriageVersionStatusBackend::Unknown,
+    };
+    record(
+        "feedback",
+        votes,
+        TriagePlatformBackend::from_os_name(&feedback.os_name),
+        feedback.os_version.trim(),
+        helper_health,
+        threat_model_age_days,
+        reported_version,
+        version_status,
+    )
+}
+
+/// Dislike reports carry no platform, helper or version information.
+pub fn triage_dislike(dislike: &DislikeDeviceInfoBackend) -> TriageRecordBackend {
+    let mut votes = keyword_votes(&[&dislike.note]);
+    // The report itself is about a LAN scan result: one more vote than the
+    // strongest keyword match keeps it first.
+    let strongest = votes.iter().map(|(_, count)| *count).max().unwrap_or(0);
+    add_vote(&mut votes, TriageSubsystemBackend::LanScan, strongest + 1);
+    record(
+        "dislike",
+        votes,
+        TriagePlatformBackend::Unknown,
+        "",
+        HelperHealthBackend::Unknown,
+        None,
+        None,
+        TriageVersionStatusBackend::Unknown,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn record(
+    source: &str,
+    mut votes: Vec<(TriageSubsystemBackend, u32)>,
+    platform: TriagePlatformBackend,
+    os_version: &str,
+    helper_health: HelperHealthBackend,
+    threat_model_age_days: Option<i64>,
+    reported_version: Option<String>,
+    version_status: TriageVersionStatusBackend,
+) -> TriageRecordBackend {
+    votes.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    let mut subsystems: Vec<String> = votes
+        .iter()
+        .map(|(subsystem, _)| subsystem.as_str().to_string())
+        .collect();
+    if subsystems.is_empty() {
+        subsystems.push(TriageSubsystemBackend::Other.as_str().to_string());
+    }
+    let threat_model_stale =
+        threat_model_age_days.is_some_and(|days| days > STALE_THREAT_MODEL_DAYS);
+
+    let mut labels = vec![
+        format!("source:{source}"),
+        format!("subsystem:{}", subsystems[0]),
+        format!("platform:{}", platform.as_str()),
+        format!("helper:{}", helper_health.as_str()),
+        format!("version:{}", version_status.as_str()),
+    ];
+    if threat_model_stale {
+        labels.push("threat_model:stale".to_string());
+    }
+    labels.sort();
+
+    TriageRecordBackend {
+        source: source.to_string(),
+        subsystems,
+        platform: platform.as_str().to_string(),
+        os_version: os_version.to_string(),
+        helper_health: helper_health.as_str().to_string(),
+        threat_model_age_days,
+        threat_model_stale,
+        reported_version,
+        version_status: version_status.as_str().to_string(),
+        labels,
+    }
+}
+
+fn keyword_votes(texts: &[&str]) -> Vec<(TriageSubsystemBackend, u32)> {
+    let mut votes = Vec::new();
+    for text in texts {
+        let lower = text.to_lowercase();
+        let words: Vec<&str> = lower
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .collect();
+        for (subsystem, keywords) in SUBSYSTEM_KEYWORDS {
+            for keyword in *keywords {
+                let phrase: Vec<&str> = keyword.split_whitespace().collect();
+                let found = words
+                    .windows(phrase.len())
+                    .filter(|window| *window == phrase.as_slice())
+                    .count();
+                if found > 0 {
+                    add_vote(&mut votes, *subsystem, found as u32);
+                }
+            }
+        }
+    }
+    votes
+}
+
+fn add_vote(
+    votes: &mut Vec<(TriageSubsystemBackend, u32)>,
+    subsystem: TriageSubsystemBackend,
+    n: u32,
+) {
+    match votes.iter_mut().find(|(other, _)| *other == subsystem) {
+        Some((_, count)) => *count += n,
+        None => votes.push((subsystem, n)),
+    }
+}
+
+/// Days since `threat_model_date` (RFC 3339 or `YYYY-MM-DD`); `None` when it
+/// does not parse.
+pub fn threat_model_age_days(threat_model_date: &str, now: DateTime<Utc>) -> Option<i64> {
+    let date = threat_model_date.trim();
+    let date = DateTime::parse_from_rfc3339(date)
+        .map(|date| date.with_timezone(&Utc).date_naive())
+        .or_else(|_| NaiveDate::parse_from_str(date, "%Y-%m-%d"))
+        .ok()?;
+    Some((now.date_naive() - date).num_days())
+}
+
+/// The backend version `core_info` reports: the `X.Y.Z` right after a
+/// `backend` token (`backend 0.3.5`, `backend: 0.3.5`, `edamame_backend
+/// 0.3.5`, `backend/0.3.5`). `None` without one: the other versions in
+/// `core_info` are the app's and core's, which [`BACKEND_VERSION`] says
+/// nothing about.
+pub fn reported_version(core_info: &str) -> Option<String> {
+    let tokens: Vec<&str> = core_info
+        .split(|c: char| c.is_whitespace() || c == ',' || c == ';' || c == '(' || c == ')')
+        .filter(|token| !token.is_empty())
+        .collect();
+    let is_backend = |token: &str| {
+        let token = token.to_ascii_lowercase();
+        let token = token.trim_end_matches([':', '=']);
+        token == "backend" || token.ends_with("_backend") || token.ends_with("-backend")
+    };
+    let version_of = |token: &str| -> Option<String> {
+        let token = token.trim_start_matches(['v', 'V']);
+        let token = token.split_once(['-', '+']).map_or(token, |(core, _)| core);
+        parse_version(token).map(|_| token.to_string())
+    };
+    tokens.iter().enumerate().find_map(|(index, token)| {
+        // `backend/0.3.5` or `backend=0.3.5` in one token.
+        if let Some((name, version)) = token.split_once(['/', '=', ':']) {
+            if is_backend(name) && !version.is_empty() {
+                return version_of(version);
+            }
+        }
+        if is_backend(token) {
+            return tokens.get(index + 1).and_then(|next| version_of(next));
+        }
+        None
+    })
+}
+
+fn parse_version(value: &str) -> Option<(u64, u64, u64)> {
+    let mut parts = value.split('.');
+    let version = (
+        parts.next()?.parse().ok()?,
+        parts.next()?.parse().ok()?,
+        parts.next()?.parse().ok()?,
+    );
+    parts.next().is_none().then_some(version)
+}
+
+fn compare_versions(reported: &str, current: &str) -> TriageVersionStatusBackend {
+    match (parse_version(reported), parse_version(current)) {
+        (Some(reported), Some(current)) => match reported.cmp(&current) {
+            Ordering::Less => TriageVersionStatusBackend::Outdated,
+            Ordering::Equal => TriageVersionStatusBackend::Current,
+            Ordering::Greater => TriageVersionStatusBackend::Newer,
+        },
+        _ => TriageVersionStatusBackend::Unknown,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap()
+    }
+
+    fn feedback(note: &str, helper_state: &str, core_info: &str) -> FeedbackInfoBackend {
+        FeedbackInfoBackend {
+            core_info: core_info.to_string(),
+            threat_model_name: "macOS".to_string(),
+            threat_model_date: "2024-03-01".to_string(),
+            threat_model_signature: String::new(),
+            stars: 2.0,
+            helper_state: helper_state.to_string(),
+            os_name: "macOS".to_string(),
+            os_version: "14.5".to_string(),
+            context: String::new(),
+            note: note.to_string(),
+            email: String::new(),
+            app_log: String::new(),
+            helper_log: String::new(),
+        }
+    }
+
+    #[test]
+    fn routes_feedback_by_keywords() {
+        let record = triage_feedback(
+            &feedback(
+                "The score dropped after remediation of the firewall threat",
+                "Enabled",
+                "core 0.9.1 backend 0.0.1",
+            ),
+            now(),
+        );
+        assert_eq!(record.subsystem(), "score");
+        assert_eq!(record.platform, "macos");
+        assert_eq!(record.helper_health, "healthy");
+        assert_eq!(record.threat_model_age_days, Some(92));
+        assert!(record.threat_model_stale);
+        assert_eq!(record.reported_version.as_deref(), Some("0.0.1"));
+        assert_eq!(record.version_status, "outdated");
+        assert_eq!(
+            record.labels,
+            vec![
+                "helper:healthy",
+                "platform:macos",
+                "source:feedback",
+                "subsystem:score",
+                "threat_model:stale",
+                "version:outdated",
+            ]
+        );
+    }
+
+    #[test]
+    fn broken_helper_breaks_ties_towards_helper() {
+        let record = triage_feedback(&feedback("nothing works", "Fatal", ""), now());
+        assert_eq!(record.subsystems, vec!["helper"]);
+        assert_eq!(record.helper_health, "broken");
+        assert_eq!(record.version_status, "unknown");
+
+        let record = triage_feedback(&feedback("nothing works", "enabled", ""), now());
+        assert_eq!(record.subsystems, vec!["other"]);
+    }
+
+    #[test]
+    fn agentic_words_are_whole_words() {
+        let record = triage_feedback(
+            &feedback("The AI agent keeps closing MCP alerts", "Enabled", ""),
+            now(),
+        );
+        assert_eq!(record.subsystem(), "agentic");
+        let record = triage_feedback(&feedback("said again", "Enabled", ""), now());
+        assert_eq!(record.subsystem(), "other");
+    }
+
+    #[test]
+    fn everyday_words_do_not_route() {
+        for note in [
+            "I could not check out my cart, the portal is a disaster",
+            "Customer service never answered about my disk space",
+            "The report was important, please support my passport app",
+            "My agency uses an agenda with a rating of five stars",
+            "Dismissing the popup whitelisted nothing",
+        ] {
+            let record = triage_feedback(&feedback(note, "Enabled", ""), now());
+            assert_eq!(record.subsystems, vec!["other"], "{note}");
+        }
+
+        let record = triage_feedback(
+            &feedback("Full disk access keeps being revoked", "Enabled", ""),
+            now(),
+        );
+        assert_eq!(record.subsystem(), "helper");
+        let record = triage_feedback(&feedback("A security check is wrong", "Enabled", ""), now());
+        assert_eq!(record.subsystem(), "score");
+    }
+
+    #[test]
+    fn dislike_is_lan_scan_first() {
+        let dislike = DislikeDeviceInfoBackend {
+            device_type: "Printer".to_string(),
+            open_ports: vec![],
+            mdns_services: vec![],
+            device_vendor: "HP".to_string(),
+            hostname: String::new(),
+            note: "the score and the threat list mention this".to_string(),
+        };
+        let record = triage_dislike(&dislike);
+        assert_eq!(record.subsystems, vec!["lan_scan", "score"]);
+        assert_eq!(record.source, "dislike");
+        assert_eq!(record.platform, "unknown");
+    }
+
+    #[test]
+    fn parses_platforms_dates_and_versions() {
+        assert_eq!(
+            TriagePlatformBackend::from_os_name("Windows 11"),
+            TriagePlatformBackend::Windows
+        );
+        assert_eq!(
+            TriagePlatformBackend::from_os_name("iPadOS"),
+            TriagePlatformBackend::Ios
+        );
+        assert_eq!(
+            TriagePlatformBackend::from_os_name("Ubuntu"),
+            TriagePlatformBackend::Linux
+        );
+        assert_eq!(
+            threat_model_age_days("2024-05-31T23:00:00Z", now()),
+            Some(1)
+        );
+        assert_eq!(threat_model_age_days("May 2024", now()), None);
+        assert_eq!(
+            reported_version("edamame 0.9.12 (backend: 0.3.5)").as_deref(),
+            Some("0.3.5")
+        );
+        assert_eq!(
+            reported_version("edamame_backend v0.3.4-beta").as_deref(),
+            Some("0.3.4")
+        );
+        assert_eq!(
+            reported_version("core 0.9.1, backend/0.2.0").as_deref(),
+            Some("0.2.0")
+        );
+        // App and core versions are not backend versions.
+        assert_eq!(reported_version("edamame 0.9.12 core 1.2.3"), None);
+        assert_eq!(reported_version("backend unknown 1.2.3"), None);
+        assert_eq!(reported_version("build 12.4"), None);
+        assert_eq!(
+            compare_versions(BACKEND_VERSION, BACKEND_VERSION),
+            TriageVersionStatusBackend::Current
+        );
+        assert_eq!(
+            compare_versions("999.0.0", BACKEND_VERSION),
+            TriageVersionStatusBackend::Newer
+        );
+    }
+}
@@ -0,0 +1,260 @@
+//! Versioned uids for cached Hub objects.
+//!
+//! The first uid scheme fed raw field bytes to blake3 back to back and
+//! skipped unset options, so distinct objects could collide: a session with
+//! `domain=Some("a")` and one with `asn_owner=Some("a")` hashed the same, and
+//! so did `"10.0.0.1"`+`"23"` and `"10.0.0.12"`+`"3"`. Uids are now built on
+//! [`StableHasher`], whose encoding is length-prefixed with explicit option
+//! markers, and every digest is tagged with the object kind and
+//! [`UID_SCHEME_VERSION`].
+//!
+//! Each type keeps its old scheme as `legacy_uid`, so a cache keyed by legacy
+//! uids can be rekeyed with [`UidMigrationBackend`]. Legacy uids that collided
+//! cannot be mapped back to a single object and are reported as ambiguous.
+
+use crate::advisor_todos_backend::AdvisorTodosBackend;
+use crate::lanscan_device_info_backend::DeviceInfoBackend;
+use crate::pwned_backend::{BreachDetailBackend, BreachInfoBackend};
+use crate::session_info_backend::SessionInfoBackend;
+use crate::stable_hash_backend::StableHasher;
+
+/// Scheme 1 is the legacy concatenation; bump whenever a uid's inputs change.
+pub const UID_SCHEME_VERSION: u8 = 2;
+
+/// Hasher for a uid of `kind` in `language`, with the scheme tag written.
+pub fn uid_hasher(kind: &str, language: &str) -> StableHasher {
+    let mut hasher = StableHasher::new();
+    hasher.write_str("uid");
+    hasher.write_u8(UID_SCHEME_VERSION);
+    hasher.write_str(kind);
+    hasher.write_str(language);
+    hasher
+}
+
+/// Types whose uid changed scheme.
+pub trait VersionedUidBackend {
+    fn uid(&self, language: &str) -> String;
+    fn legacy_uid(&self, language: &str) -> String;
+}
+
+macro_rules! versioned_uid {
+    ($ty:ty) => {
+        impl VersionedUidBackend for $ty {
+            fn uid(&self, language: &str) -> String {
+                <$ty>::uid(self, language)
+            }
+
+            fn legacy_uid(&self, language: &str) -> String {
+                <$ty>::legacy_uid(self, language)
+            }
+        }
+    };
+}
+
+versioned_uid!(SessionInfoBackend);
+versioned_uid!(BreachDetailBackend);
+versioned_uid!(BreachInfoBackend);
+versioned_uid!(AdvisorTodosBackend);
+versioned_uid!(DeviceInfoBackend);
+
+/// Legacy uid to current uid table, built from the objects currently known.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UidMigrationBackend {
+    /// (legacy, current), sorted by legacy uid.
+    entries: Vec<(String, String)>,
+    /// Legacy uids shared by objects with different current uids.
+    ambiguous: Vec<String>,
+}
+
+impl UidMigrationBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_items<'a, T, I>(items: I, language: &str) -> Self
+    where
+        T: VersionedUidBackend + 'a,
+        I: IntoIterator<Item = &'a T>,
+    {
+        let mut migration = Self::new();
+        for item in items {
+            migration.add(item, language);
+        }
+        migration
+    }
+
+    /// Records `item`. A legacy uid already mapped to a different current
+    /// uid becomes ambiguous and is no longer migrated.
+    pub fn add<T: VersionedUidBackend + ?Sized>(&mut self, item: &T, language: &str) {
+        let legacy = item.legacy_uid(language);
+        let current = item.uid(language);
+        if self.ambiguous.binary_search(&legacy).is_ok() {
+            return;
+        }
+        match self.entries.binary_search_by(|(old, _)| old.cmp(&legacy)) {
+            Ok(index) => {
+                if self.entries[index].1 != current {
+                    self.entries.remove(index);
+                    let position = self.ambiguous.binary_search(&legacy).unwrap_err();
+                    self.ambiguous.insert(position, legacy);
+                }
+            }
+            Err(index) => self.entries.insert(index, (legacy, current)),
+        }
+    }
+
+    /// Current uid for `legacy`, if it maps to exactly one object.
+    pub fn migrate(&self, legacy: &str) -> Option<&str> {
+        self.entries
+            .binary_search_by(|(old, _)| old.as_str().cmp(legacy))
+            .ok()
+            .map(|index| self.entries[index].1.as_str())
+    }
+
+    pub fn entries(&self) -> &[(String, String)] {
+        &self.entries
+    }
+
+    pub fn ambiguous(&self) -> &[String] {
+        &self.ambiguous
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::advisor_todos_backend::{
+        AdvicePriorityBackend, AdviceTypeBackend, AdvisorAdviceBackend, AdvisorTodoBackend,
+    };
+    use crate::lanscan_port_info_backend::PortInfoBackend;
+    use crate::test_fixtures as fixtures;
+
+    fn session(ip: &str, port: u16) -> SessionInfoBackend {
+        SessionInfoBackend {
+            port,
+            domain: None,
+            asn_number: None,
+            asn_country: None,
+            asn_owner: None,
+            criticality: "Low".to_string(),
+            service: None,
+            l7_process_name: None,
+            l7_process_path: None,
+            l7_process_user: None,
+            ..fixtures::session(ip)
+        }
+    }
+
+    #[test]
+    fn session_uid_separates_fields() {
+        let a = session("10.0.0.1", 23);
+        let b = session("10.0.0.12", 3);
+        assert_eq!(a.legacy_uid("EN"), b.legacy_uid("EN"));
+        assert_ne!(a.uid("EN"), b.uid("EN"));
+
+        let mut domain = session("10.0.0.1", 443);
+        domain.domain = Some("a".to_string());
+        let mut owner = session("10.0.0.1", 443);
+        owner.asn_owner = Some("a".to_string());
+        assert_eq!(domain.legacy_uid("EN"), owner.legacy_uid("EN"));
+        assert_ne!(domain.uid("EN"), owner.uid("EN"));
+
+        let mut empty = session("10.0.0.1", 443);
+        empty.domain = Some(String::new());
+        assert_ne!(empty.uid("EN"), session("10.0.0.1", 443).uid("EN"));
+    }
+
+    #[test]
+    fn uids_are_tagged_by_kind_and_language() {
+        let info = BreachInfoBackend {
+            name: "Nadobe".to_string(),
+            description: String::new(),
+            is_service: false,
+        };
+        let other = BreachInfoBackend {
+            name: "adobe".to_string(),
+            ..info.clone()
+        };
+        assert_eq!(info.legacy_uid("E"), other.legacy_uid("EN"));
+        assert_ne!(info.uid("E"), other.uid("EN"));
+        assert_ne!(info.uid("EN"), info.legacy_uid("EN"));
+    }
+
+    #[test]
+    fn uid_is_pinned() {
+        // Changing this digest invalidates every cached session: bump
+        // `UID_SCHEME_VERSION` and ship a migration.
+        assert_eq!(
+            session("10.0.0.1", 443).uid("EN"),
+            "94284bb3ee88f175fb92b0e6d9fd0a93c35cd5024243f2a7dcd336da70516191"
+        );
+    }
+
+    #[test]
+    fn device_uid_ignores_port_order() {
+        let port = |port: u16| PortInfoBackend {
+            port,
+            protocol: "tcp".to_string(),
+            service: String::new(),
+            banner: String::new(),
+            vulnerabilities: vec![],
+        };
+        let device = DeviceInfoBackend {
+            mdns_services: vec![],
+            device_vendor: "Acme".to_string(),
+            vulnerabilities: vec![],
+            open_ports: vec![port(80), port(22)],
+        };
+        let mut reordered = device.clone();
+        reordered.open_ports.reverse();
+        assert_eq!(device.uid("EN"), reordered.uid("EN"));
+        assert_eq!(device.legacy_uid("EN"), reordered.legacy_uid("EN"));
+    }
+
+    #[test]
+    fn migration_maps_legacy_to_current() {
+        let todos = AdvisorTodosBackend {
+            system_overview: "overview".to_string(),
+            todos: vec![AdvisorTodoBackend {
+                advice: AdvisorAdviceBackend {
+                    advice_type: AdviceTypeBackend::Threat,
+                    advice_details: "firewall".to_string(),
+                },
+                priority: AdvicePriorityBackend::High,
+            }],
+            email: None,
+            question: None,
+        };
+        let migration = UidMigrationBackend::from_items([&todos], "EN");
+        assert_eq!(migration.len(), 1);
+        assert_eq!(
+            migration.migrate(&todos.legacy_uid("EN")),
+            Some(todos.uid("EN").as_str())
+        );
+        assert_eq!(migration.migrate(&todos.uid("EN")), None);
+    }
+
+    #[test]
+    fn colliding_legacy_uids_are_ambiguous() {
+        let a = session("10.0.0.1", 23);
+        let b = session("10.0.0.12", 3);
+        let c = session("10.0.0.2", 80);
+        let mut migration = UidMigrationBackend::from_items([&a, &b, &c], "EN");
+        migration.add(&a, "EN");
+        assert_eq!(migration.ambiguous(), [a.legacy_uid("EN")]);
+        assert_eq!(migration.migrate(&a.legacy_uid("EN")), None);
+        assert_eq!(
+            migration.migrate(&c.legacy_uid("EN")),
+            Some(c.uid("EN").as_str())
+        );
+        assert_eq!(migration.len(), 1);
+    }
+}